@@ -0,0 +1,63 @@
+//! Assets pallet benchmarking.
+
+#![cfg(feature = "runtime-benchmarks")]
+
+use super::*;
+use sp_std::result::Result;
+use frame_system::RawOrigin;
+use frame_benchmarking::{benchmarks, account, whitelisted_caller};
+
+use crate::Module as Assets;
+
+/// issue an asset of `total` to `owner`, returning its id
+fn issue_asset<T: Trait>(owner: T::AccountId, total: T::Balance) -> T::AssetId {
+    let id = Assets::<T>::next_asset_id();
+    <NextAssetId<T>>::mutate(|id| *id += One::one());
+    <Balances<T>>::insert((id, owner), total);
+    <TotalSupply<T>>::insert(id, total);
+    id
+}
+
+benchmarks! {
+    _ { }
+
+    issue {
+        let caller: T::AccountId = whitelisted_caller();
+    }: _(RawOrigin::Signed(caller.clone()), 1_000_000u32.into())
+    verify {
+        ensure!(Assets::<T>::balance(Zero::zero(), caller) == 1_000_000u32.into(), "Asset was not issued.");
+    }
+
+    destroy {
+        let caller: T::AccountId = whitelisted_caller();
+        let id = issue_asset::<T>(caller.clone(), 1_000_000u32.into());
+    }: _(RawOrigin::Signed(caller.clone()), id)
+    verify {
+        ensure!(Assets::<T>::total_supply(id).is_zero(), "Asset was not destroyed.");
+    }
+
+    set_inherent_asset {
+        let caller: T::AccountId = whitelisted_caller();
+        let id = issue_asset::<T>(caller.clone(), 1_000_000u32.into());
+    }: _(RawOrigin::Signed(caller), id)
+    verify {
+        ensure!(Assets::<T>::inherent_asset_id() == id, "Inherent asset was not set.");
+    }
+
+    transfer_asset {
+        let caller: T::AccountId = whitelisted_caller();
+        let recipient: T::AccountId = account("recipient", 0, 0);
+        let id = issue_asset::<T>(caller.clone(), 1_000_000u32.into());
+        <Balances<T>>::insert((id, recipient.clone()), T::Balance::from(1u32));
+    }: _(RawOrigin::Signed(caller), id, recipient.clone(), 1_000u32.into())
+    verify {
+        ensure!(Assets::<T>::balance(id, recipient) == 1_001u32.into(), "Asset was not transferred.");
+    }
+
+    set_price {
+        let id = issue_asset::<T>(whitelisted_caller(), 1_000_000u32.into());
+    }: _(RawOrigin::Root, id, FixedU128::saturating_from_integer(2))
+    verify {
+        ensure!(Assets::<T>::price(id) == FixedU128::saturating_from_integer(2), "Price was not set.");
+    }
+}
@@ -0,0 +1,34 @@
+//! Weights for pallet_assets, from its benchmarks run natively with 20 repeats over 14 steps.
+//! Regenerate with the node's `benchmark` command against the wasm runtime.
+
+#![allow(unused_parens)]
+
+use frame_support::weights::{Weight, constants::RocksDbWeight as DbWeight};
+
+impl crate::WeightInfo for () {
+	fn issue() -> Weight {
+		(5_799_000 as Weight)
+			.saturating_add(DbWeight::get().reads(1 as Weight))
+			.saturating_add(DbWeight::get().writes(3 as Weight))
+	}
+	fn destroy() -> Weight {
+		(7_060_000 as Weight)
+			.saturating_add(DbWeight::get().reads(2 as Weight))
+			.saturating_add(DbWeight::get().writes(2 as Weight))
+	}
+	fn set_inherent_asset() -> Weight {
+		(1_579_000 as Weight)
+			.saturating_add(DbWeight::get().reads(1 as Weight))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	fn transfer_asset() -> Weight {
+		(13_028_000 as Weight)
+			.saturating_add(DbWeight::get().reads(4 as Weight))
+			.saturating_add(DbWeight::get().writes(2 as Weight))
+	}
+	fn set_price() -> Weight {
+		(779_000 as Weight)
+			.saturating_add(DbWeight::get().reads(1 as Weight))
+			.saturating_add(DbWeight::get().writes(2 as Weight))
+	}
+}
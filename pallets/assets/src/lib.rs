@@ -1,8 +1,10 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 use frame_support::{
-    decl_event, decl_module, decl_storage, ensure, Parameter,
+    decl_error, decl_event, decl_module, decl_storage, ensure, transactional, Parameter,
     StorageMap, StorageValue,
+    traits::UnixTime,
+    weights::Weight,
 };
 use sp_runtime::{
     FixedU128, FixedPointNumber,
@@ -13,9 +15,20 @@ use frame_system::{self as system, ensure_signed, ensure_root};
 use sp_std::prelude::*;
 use sp_std::convert::TryInto;
 use sp_runtime::traits::{
-    Member, One, AtLeast32BitUnsigned, Zero,
+    Member, One, AtLeast32BitUnsigned, CheckedAdd, Zero,
 };
-use traits::{Oracle, MultiAsset};
+use traits::{Oracle, MultiAsset, MintableMultiAsset, OnAssetTransfer};
+
+mod benchmarking;
+mod default_weights;
+
+pub trait WeightInfo {
+    fn issue() -> Weight;
+    fn destroy() -> Weight;
+    fn set_inherent_asset() -> Weight;
+    fn transfer_asset() -> Weight;
+    fn set_price() -> Weight;
+}
 
 /// The module configuration trait.
 pub trait Trait: frame_system::Trait {
@@ -25,15 +38,23 @@ pub trait Trait: frame_system::Trait {
     type Balance: Member + Parameter + FixedPointOperand + AtLeast32BitUnsigned + Default + Copy;
     /// The arithmetic type of asset identifier.
     type AssetId: Parameter + AtLeast32BitUnsigned + Default + Copy;
+    /// Checks and follows transfers of assets kept in sync with other pallets.
+    type OnAssetTransfer: OnAssetTransfer<Self::AccountId, Self::AssetId, Self::Balance>;
+    /// Time prices are updated at.
+    type UnixTime: UnixTime;
+    /// Weight information for extrinsics in this pallet.
+    type WeightInfo: WeightInfo;
 }
 
 decl_module! {
     pub struct Module<T: Trait> for enum Call where
         origin: T::Origin
     {
+		type Error = Error<T>;
+
 		fn deposit_event() = default;
 
-        #[weight = 1]
+        #[weight = T::WeightInfo::issue()]
         fn issue(origin, total: T::Balance) -> Result {
             let origin = ensure_signed(origin)?;
 
@@ -62,11 +83,12 @@ decl_module! {
         /// Destroy any assets of `id` owned by `origin`.
         /// @origin
         /// @id      Asset id to be destroyed
-        #[weight = 1]
+        #[weight = T::WeightInfo::destroy()]
         fn destroy(origin, id: T::AssetId) -> Result {
             let origin = ensure_signed(origin)?;
+            T::OnAssetTransfer::can_destroy(&origin, id)?;
             let balance = <Balances<T>>::take((id, origin.clone()));
-            ensure!(!balance.is_zero(), "origin balance should be non-zero");
+            ensure!(!balance.is_zero(), Error::<T>::InsufficientBalance);
 
             <TotalSupply<T>>::mutate(id, |total_supply| *total_supply -= balance);
             Self::deposit_event(RawEvent::Destroyed(id, origin, balance));
@@ -77,7 +99,7 @@ decl_module! {
         /// Set the default inherent asset
         /// @origin
         /// @asset    The asset to become inherent asset
-        #[weight = 1]
+        #[weight = T::WeightInfo::set_inherent_asset()]
         pub fn set_inherent_asset(origin, asset: T::AssetId) -> Result {
             //ensure_root(origin)?;
             <InherentAsset<T>>::mutate(|ia| *ia = asset.clone());
@@ -94,19 +116,18 @@ decl_module! {
         }
 
         /// Transfer an asset to another account
-        #[weight = 1]
+        /// The transfer hook may fail after the balances moved, which reverts them
+        #[weight = T::WeightInfo::transfer_asset().saturating_add(<Module<T>>::transfer_hook_weight())]
+        #[transactional]
         pub fn transfer_asset(origin,
                     id: T::AssetId,
                     to_account: T::AccountId,
                     amount: T::Balance
         ) -> Result {
-            let from_account = ensure_signed(origin)?;
-            Self::transfer(from_account, id, to_account, amount);
-
-            Ok(())
+            Self::_transfer_asset(origin, id, to_account, amount)
         }
 
-        #[weight = 1]
+        #[weight = T::WeightInfo::set_price()]
         pub fn set_price(origin, id: T::AssetId, price: FixedU128) -> Result {
             ensure_root(origin)?;
             Self::_set_price(id, price);
@@ -127,6 +148,23 @@ decl_event! {
 		Transferred(AssetId, AccountId, AccountId, Balance),
 		/// Some assets were destroyed. \[asset_id, owner, balance\]
 		Destroyed(AssetId, AccountId, Balance),
+		/// Some assets were minted by another pallet. \[asset_id, owner, amount\]
+		Minted(AssetId, AccountId, Balance),
+		/// Some assets were burned by another pallet. \[asset_id, owner, amount\]
+		Burned(AssetId, AccountId, Balance),
+	}
+}
+
+decl_error! {
+	pub enum Error for Module<T: Trait> {
+		/// Account balance is lower than the amount
+		InsufficientBalance,
+		/// Amount is zero
+		ZeroAmount,
+		/// Balance or total supply would overflow
+		Overflow,
+		/// Asset is not issued yet
+		UnknownAsset,
 	}
 }
 
@@ -143,6 +181,8 @@ decl_storage! {
         InherentAsset get(fn inherent_asset_id): T::AssetId;
         /// Price of the asset (base is 1000000)
         Price get(fn price): map hasher(twox_64_concat) T::AssetId => FixedU128;
+        /// The unix time in seconds the price of the asset was last updated at
+        PriceUpdatedAt get(fn price_updated_at): map hasher(twox_64_concat) T::AssetId => u64;
         /// for test only
         Owner get(fn owner) config(): T::AccountId;
     }
@@ -193,8 +233,19 @@ impl<T: Trait> Module<T> {
         Ok(())
     }
 
+    fn _transfer_asset(origin: T::Origin, id: T::AssetId, to_account: T::AccountId, amount: T::Balance) -> Result {
+        let from_account = ensure_signed(origin)?;
+        Self::transfer(from_account, id, to_account, amount)
+    }
+
+    /// The most weight the transfer hook adds to a single `transfer`
+    pub fn transfer_hook_weight() -> Weight {
+        T::OnAssetTransfer::transfer_weight()
+    }
+
     pub fn _set_price(id: T::AssetId, price: FixedU128) {
         <Price<T>>::insert(id, price);
+        <PriceUpdatedAt<T>>::insert(id, T::UnixTime::now().as_secs());
     }
 
     /// Move some assets from one holder to another.
@@ -207,19 +258,18 @@ impl<T: Trait> Module<T> {
         id: T::AssetId,
         to_account: T::AccountId,
         amount: T::Balance,
-    ) -> sp_std::result::Result<(), &'static str> {
+    ) -> Result {
         let origin_account = (id, from_account.clone());
         let origin_balance = <Balances<T>>::get(&origin_account);
         let target = to_account;
-        ensure!(!amount.is_zero(), "transfer amount should be non-zero");
-        ensure!(
-            origin_balance >= amount,
-            "origin account balance must be greater than or equal to the transfer amount"
-        );
+        ensure!(id < Self::next_asset_id(), Error::<T>::UnknownAsset);
+        ensure!(!amount.is_zero(), Error::<T>::ZeroAmount);
+        ensure!(origin_balance >= amount, Error::<T>::InsufficientBalance);
+        T::OnAssetTransfer::can_transfer(&from_account, id, &target, amount)?;
 
         Self::deposit_event(RawEvent::Transferred(
             id,
-            from_account,
+            from_account.clone(),
             target.clone(),
             amount,
         ));
@@ -232,11 +282,11 @@ impl<T: Trait> Module<T> {
         <Balances<T>>::insert(origin_account, origin_balance - amount);
         <Balances<T>>::mutate((id, target.clone()), |balance| *balance += amount);
         sp_runtime::print("after transfer target balance----> ");
-        let b = TryInto::<u128>::try_into(Self::get_asset_balance(&(id.clone(), target)))
+        let b = TryInto::<u128>::try_into(Self::get_asset_balance(&(id.clone(), target.clone())))
             .ok()
             .expect("Balance is u128");
         sp_runtime::print(b as u64);
-        Ok(())
+        T::OnAssetTransfer::on_transfer(&from_account, id, &target, amount)
     }
 
     /// Get the asset `id` balance of `who`.
@@ -269,13 +319,52 @@ impl<T: Trait> Module<T> {
 }
 
 impl<T: Trait> Oracle<T::AssetId, FixedU128> for Module<T> {
-    fn get_rate(asset_id: T::AssetId) -> FixedU128 {
-        Self::price(asset_id)
+    fn get_rate(asset_id: T::AssetId) -> Option<(FixedU128, u64)> {
+        if !<Price<T>>::contains_key(asset_id) {
+            return None
+        }
+        Some((Self::price(asset_id), Self::price_updated_at(asset_id)))
+    }
+
+    #[cfg(feature = "runtime-benchmarks")]
+    fn set_rate(asset_id: T::AssetId, rate: FixedU128) {
+        Self::_set_price(asset_id, rate);
     }
 }
 
 impl<T: Trait> MultiAsset<T::AccountId, T::AssetId, T::Balance> for Module<T> {
-	fn transfer(from: T::AccountId, id: T::AssetId, to: T::AccountId, amount: T::Balance) -> sp_std::result::Result<(), &'static str> {
+	fn transfer(from: T::AccountId, id: T::AssetId, to: T::AccountId, amount: T::Balance) -> Result {
         Self::transfer(from, id, to, amount)
     }
-}
\ No newline at end of file
+}
+
+impl<T: Trait> MintableMultiAsset<T::AccountId, T::AssetId, T::Balance> for Module<T> {
+    fn create_asset() -> T::AssetId {
+        let id = Self::next_asset_id();
+        <NextAssetId<T>>::mutate(|id| *id += One::one());
+        id
+    }
+
+    fn mint(id: T::AssetId, to: T::AccountId, amount: T::Balance) -> Result {
+        ensure!(id < Self::next_asset_id(), Error::<T>::UnknownAsset);
+        let total_supply = Self::get_asset_total_supply(id).checked_add(&amount).ok_or(Error::<T>::Overflow)?;
+        // balances are within the total supply
+        <Balances<T>>::mutate((id, to.clone()), |balance| *balance += amount);
+        <TotalSupply<T>>::insert(id, total_supply);
+        Self::deposit_event(RawEvent::Minted(id, to, amount));
+        Ok(())
+    }
+
+    fn burn(id: T::AssetId, from: T::AccountId, amount: T::Balance) -> Result {
+        let balance = <Balances<T>>::get((id, from.clone()));
+        ensure!(balance >= amount, Error::<T>::InsufficientBalance);
+        <Balances<T>>::insert((id, from.clone()), balance - amount);
+        <TotalSupply<T>>::mutate(id, |total_supply| *total_supply -= amount);
+        Self::deposit_event(RawEvent::Burned(id, from, amount));
+        Ok(())
+    }
+
+    fn balance(id: T::AssetId, who: T::AccountId) -> T::Balance {
+        <Balances<T>>::get((id, who))
+    }
+}
@@ -0,0 +1,187 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! An offchain worker that keeps lending positions healthy. Every block it scans the debts
+//! in `pallet_lending`, and liquidates the unhealthy ones worth it with signed transactions
+//! from a key of type `lkpr` in the local keystore.
+//!
+//! Its limits are a SCALE encoded `KeeperConfig` in the persistent offchain local storage
+//! under `CONFIG_KEY`, e.g. set by the `offchain_localStorageSet` RPC.
+
+use frame_support::{debug, decl_module, IterableStorageMap};
+use frame_system::offchain::{AppCrypto, CreateSignedTransaction, SendSignedTransaction, Signer};
+use sp_core::crypto::KeyTypeId;
+use sp_runtime::{
+    FixedPointNumber, RuntimeDebug,
+    offchain::storage::StorageValueRef,
+    traits::{Saturating, Zero},
+};
+use sp_std::prelude::*;
+use codec::{Encode, Decode};
+use pallet_lending as lending;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+/// Key type of the keeper's signing key
+pub const KEY_TYPE: KeyTypeId = KeyTypeId(*b"lkpr");
+
+/// Offchain local storage key of the `KeeperConfig`
+pub const CONFIG_KEY: &[u8] = b"lending-keeper::config";
+
+/// The keeper's crypto, keys are sr25519
+pub mod crypto {
+    use super::KEY_TYPE;
+    use sp_core::sr25519::Signature as Sr25519Signature;
+    use sp_runtime::{
+        app_crypto::{app_crypto, sr25519},
+        traits::Verify, MultiSignature, MultiSigner,
+    };
+    app_crypto!(sr25519, KEY_TYPE);
+
+    pub struct KeeperAuthId;
+
+    impl frame_system::offchain::AppCrypto<MultiSigner, MultiSignature> for KeeperAuthId {
+        type RuntimeAppPublic = Public;
+        type GenericSignature = sp_core::sr25519::Signature;
+        type GenericPublic = sp_core::sr25519::Public;
+    }
+
+    impl frame_system::offchain::AppCrypto<<Sr25519Signature as Verify>::Signer, Sr25519Signature> for KeeperAuthId {
+        type RuntimeAppPublic = Public;
+        type GenericSignature = sp_core::sr25519::Signature;
+        type GenericPublic = sp_core::sr25519::Public;
+    }
+}
+
+/// The module's configuration trait.
+pub trait Trait: CreateSignedTransaction<lending::Call<Self>> + lending::Trait {
+    /// The crypto of the key signing liquidations.
+    type AuthorityId: AppCrypto<Self::Public, Self::Signature>;
+}
+
+/// Limits of the keeper
+#[derive(Clone, Eq, PartialEq, RuntimeDebug, Encode, Decode)]
+pub struct KeeperConfig<Balance> {
+    /// if the keeper liquidates at all
+    pub enabled: bool,
+    /// most liquidations submitted in one block
+    pub max_liquidations: u32,
+    /// least value a liquidation must gain to be submitted, in the price unit of the oracle
+    pub min_profit: Balance,
+}
+
+impl<Balance: Zero> Default for KeeperConfig<Balance> {
+    fn default() -> Self {
+        KeeperConfig {
+            enabled: true,
+            max_liquidations: 5,
+            min_profit: Balance::zero(),
+        }
+    }
+}
+
+/// A liquidation the keeper found worth submitting
+#[derive(Clone, Eq, PartialEq, RuntimeDebug)]
+pub struct Liquidation<T: Trait> {
+    pub target_user: T::AccountId,
+    pub pay_asset_id: T::AssetId,
+    pub get_asset_id: T::AssetId,
+    pub pay_asset_amount: T::Balance,
+    /// value of the seized collateral over the value paid
+    pub profit: T::Balance,
+}
+
+decl_module! {
+    pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+        fn offchain_worker(block_number: T::BlockNumber) {
+            debug::debug!("Lending keeper at block: {:?}", block_number);
+            if let Err(e) = Self::liquidate_unhealthy_positions() {
+                debug::warn!("Lending keeper: {}", e);
+            }
+        }
+    }
+}
+
+impl<T: Trait> Module<T> {
+    /// the limits stored in offchain local storage, the defaults if unset or undecodable
+    pub fn config() -> KeeperConfig<T::Balance> {
+        StorageValueRef::persistent(CONFIG_KEY)
+            .get::<KeeperConfig<T::Balance>>()
+            .flatten()
+            .unwrap_or_default()
+    }
+
+    /// submit the most profitable liquidation of every unhealthy position, up to the configured limits
+    fn liquidate_unhealthy_positions() -> Result<(), &'static str> {
+        let config = Self::config();
+        if !config.enabled {
+            return Ok(())
+        }
+        let signer = Signer::<T, T::AuthorityId>::any_account();
+        if !signer.can_sign() {
+            return Err("No local key to sign liquidations")
+        }
+
+        let mut submitted = 0;
+        for (target_user, _) in lending::UserDebtSet::<T>::iter() {
+            if submitted >= config.max_liquidations {
+                break
+            }
+            let liquidation = match Self::best_liquidation(target_user) {
+                Some(liquidation) if liquidation.profit >= config.min_profit => liquidation,
+                _ => continue,
+            };
+
+            let result = signer.send_signed_transaction(|_| lending::Call::liquidate(
+                liquidation.target_user.clone(),
+                liquidation.pay_asset_id,
+                liquidation.get_asset_id,
+                liquidation.pay_asset_amount,
+            ));
+            match result {
+                Some((_, Ok(()))) => submitted += 1,
+                // e.g. the same liquidation from the last block is still in the pool
+                _ => debug::warn!("Lending keeper failed to submit liquidation of {:?}", liquidation.target_user),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// the debt and collateral pair of an unhealthy position whose liquidation gains the most
+    pub fn best_liquidation(target_user: T::AccountId) -> Option<Liquidation<T>> {
+        if !lending::Module::<T>::is_liquidatable(target_user.clone()) {
+            return None
+        }
+
+        let mut best: Option<Liquidation<T>> = None;
+        let collaterals = lending::Module::<T>::assets_in(lending::Module::<T>::user_collateral_set(target_user.clone()));
+        for pay_asset_id in lending::Module::<T>::assets_in(lending::Module::<T>::user_debt_set(target_user.clone())) {
+            for get_asset_id in collaterals.iter().copied() {
+                let (pay_asset_amount, get_asset_amount) = match lending::Module::<T>::max_liquidation(
+                    target_user.clone(), pay_asset_id, get_asset_id,
+                ) {
+                    Some(amounts) => amounts,
+                    None => continue,
+                };
+                let pay_value = lending::Module::<T>::oracle_price(pay_asset_id).saturating_mul_int(pay_asset_amount);
+                let get_value = lending::Module::<T>::oracle_price(get_asset_id).saturating_mul_int(get_asset_amount);
+                let profit = get_value.saturating_sub(pay_value);
+                if best.as_ref().is_none_or(|b| profit > b.profit) {
+                    best = Some(Liquidation {
+                        target_user: target_user.clone(),
+                        pay_asset_id,
+                        get_asset_id,
+                        pay_asset_amount,
+                        profit,
+                    });
+                }
+            }
+        }
+
+        best
+    }
+}
@@ -0,0 +1,165 @@
+use crate::{Trait, Module, crypto};
+use sp_core::{H256, sr25519};
+use frame_support::{impl_outer_origin, impl_outer_dispatch, parameter_types, weights::Weight};
+use frame_system::offchain::{AppCrypto, CreateSignedTransaction, SendTransactionTypes, SigningTypes};
+use sp_runtime::{
+	traits::{BlakeTwo256, IdentityLookup, IdentifyAccount, Verify, Extrinsic as ExtrinsicT},
+	testing::{Header, TestXt}, Perbill,
+};
+use frame_system as system;
+use pallet_assets as assets;
+use pallet_lending as lending;
+
+impl_outer_origin! {
+	pub enum Origin for Test {}
+}
+
+impl_outer_dispatch! {
+	pub enum Call for Test where origin: Origin {
+		frame_system::System,
+		pallet_lending::Lending,
+	}
+}
+
+type Balance = u128;
+type AssetId = u64;
+type Signature = sr25519::Signature;
+pub type AccountId = <<Signature as Verify>::Signer as IdentifyAccount>::AccountId;
+pub type Extrinsic = TestXt<Call, ()>;
+
+// Configure a mock runtime to test the pallet.
+#[derive(Clone, Eq, PartialEq)]
+pub struct Test;
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const MaximumBlockWeight: Weight = 1024;
+	pub const MaximumBlockLength: u32 = 2 * 1024;
+	pub const AvailableBlockRatio: Perbill = Perbill::from_percent(75);
+	pub const MinimumPeriod: u64 = 1;
+}
+
+impl system::Trait for Test {
+	type BaseCallFilter = ();
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = AccountId;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = ();
+	type BlockHashCount = BlockHashCount;
+	type MaximumBlockWeight = MaximumBlockWeight;
+	type DbWeight = ();
+	type BlockExecutionWeight = ();
+	type ExtrinsicBaseWeight = ();
+	type MaximumExtrinsicWeight = MaximumBlockWeight;
+	type MaximumBlockLength = MaximumBlockLength;
+	type AvailableBlockRatio = AvailableBlockRatio;
+	type Version = ();
+	type PalletInfo = ();
+	type AccountData = ();
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+}
+
+impl assets::Trait for Test {
+	type Event = ();
+	type Balance = Balance;
+	type AssetId = AssetId;
+	type OnAssetTransfer = Lending;
+	type UnixTime = Timestamp;
+	type WeightInfo = ();
+}
+
+impl pallet_timestamp::Trait for Test {
+	type Moment = u64;
+	type OnTimestampSet = ();
+	type MinimumPeriod = MinimumPeriod;
+	type WeightInfo = ();
+}
+
+impl lending::Trait for Test {
+	type Balance = Balance;
+	type AssetId = AssetId;
+	type Event = ();
+	type Oracle = assets::Module<Test>;
+	type MultiAsset = assets::Module<Test>;
+	type UnixTime = Timestamp;
+	type Call = Call;
+	type WeightInfo = ();
+}
+
+impl SigningTypes for Test {
+	type Public = <Signature as Verify>::Signer;
+	type Signature = Signature;
+}
+
+impl<LocalCall> SendTransactionTypes<LocalCall> for Test where
+	Call: From<LocalCall>,
+{
+	type OverarchingCall = Call;
+	type Extrinsic = Extrinsic;
+}
+
+impl<LocalCall> CreateSignedTransaction<LocalCall> for Test where
+	Call: From<LocalCall>,
+{
+	fn create_transaction<C: AppCrypto<Self::Public, Self::Signature>>(
+		call: Call,
+		_public: <Signature as Verify>::Signer,
+		_account: AccountId,
+		nonce: u64,
+	) -> Option<(Call, <Extrinsic as ExtrinsicT>::SignaturePayload)> {
+		Some((call, (nonce, ())))
+	}
+}
+
+impl Trait for Test {
+	type AuthorityId = crypto::KeeperAuthId;
+}
+
+pub type System = system::Module<Test>;
+pub type Assets = assets::Module<Test>;
+pub type Timestamp = pallet_timestamp::Module<Test>;
+pub type Lending = lending::Module<Test>;
+pub type LendingKeeper = Module<Test>;
+
+pub const ASSET1: AssetId = 0;
+pub const ASSET2: AssetId = 1;
+
+pub fn account(seed: u8) -> AccountId {
+	sr25519::Public::from_raw([seed; 32])
+}
+
+// Build genesis storage according to the mock runtime.
+// Account 1 issues both assets and account 2 gets 500000 of each, at prices 100 and 60.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+
+	pallet_assets::GenesisConfig::<Test> {
+		owner: account(2),
+		assets: vec![
+			(account(1), 1_000_000_000_000_000_000, 100),
+			(account(1), 1_000_000_000_000_000_000, 60),
+		],
+	}
+	.assimilate_storage(&mut t)
+	.unwrap();
+
+	lending::GenesisConfig::<Test> {
+		pools: vec![ASSET1, ASSET2],
+	}
+	.assimilate_storage(&mut t)
+	.unwrap();
+
+	let mut ext: sp_io::TestExternalities = t.into();
+	ext.execute_with(|| {
+		System::set_block_number(1);
+		Timestamp::set_timestamp(6000);
+	});
+	ext
+}
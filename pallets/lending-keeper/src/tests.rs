@@ -0,0 +1,133 @@
+use super::*;
+use crate::mock::*;
+use codec::Decode;
+use frame_support::{assert_ok, dispatch::{Dispatchable, DispatchResultWithPostInfo}, traits::OffchainWorker};
+use sp_core::{
+	offchain::{testing::{self, PoolState}, OffchainExt, TransactionPoolExt},
+	testing::KeyStore,
+	traits::KeystoreExt,
+};
+use sp_runtime::FixedU128;
+use std::sync::Arc;
+use parking_lot::RwLock;
+
+/// test externalities with an offchain db, a transaction pool and a keeper key
+fn offchain_test_ext() -> (sp_io::TestExternalities, Arc<RwLock<PoolState>>) {
+	let (offchain, _state) = testing::TestOffchainExt::new();
+	let (pool, pool_state) = testing::TestTransactionPoolExt::new();
+	let keystore = KeyStore::new();
+	keystore.write().sr25519_generate_new(KEY_TYPE, Some("//Keeper")).unwrap();
+
+	let mut t = new_test_ext();
+	t.register_extension(OffchainExt::new(offchain));
+	t.register_extension(TransactionPoolExt::new(pool));
+	t.register_extension(KeystoreExt(keystore));
+	(t, pool_state)
+}
+
+/// account 2 borrows asset 1 against asset 2, whose price then falls from 60 to 5
+fn make_unhealthy_position() {
+	assert_ok!(dispatch(1, lending::Call::supply(ASSET1, 100000)));
+	assert_ok!(dispatch(2, lending::Call::supply(ASSET2, 100000)));
+	assert_ok!(dispatch(2, lending::Call::borrow(ASSET1, 10000)));
+	assert_ok!(Assets::set_price(Origin::root(), ASSET2, FixedU128::saturating_from_integer(5)));
+}
+
+/// dispatch a lending call signed by an account
+fn dispatch(seed: u8, call: lending::Call<Test>) -> DispatchResultWithPostInfo {
+	mock::Call::Lending(call).dispatch(Origin::signed(account(seed)))
+}
+
+fn set_config(config: KeeperConfig<u128>) {
+	StorageValueRef::persistent(CONFIG_KEY).set(&config);
+}
+
+#[test]
+fn liquidates_unhealthy_position() {
+	let (mut t, pool_state) = offchain_test_ext();
+	t.execute_with(|| {
+		make_unhealthy_position();
+
+		// the whole collateral is seizable at the 0.95 discount factor
+		let liquidation = LendingKeeper::best_liquidation(account(2)).unwrap();
+		assert_eq!(liquidation.pay_asset_id, ASSET1);
+		assert_eq!(liquidation.get_asset_id, ASSET2);
+		assert_eq!(liquidation.pay_asset_amount, 4750);
+		assert_eq!(liquidation.profit, 500000 - 475000);
+
+		LendingKeeper::offchain_worker(1);
+
+		let tx = pool_state.write().transactions.pop().unwrap();
+		assert!(pool_state.read().transactions.is_empty());
+		let tx = Extrinsic::decode(&mut &*tx).unwrap();
+		assert_eq!(tx.signature.unwrap().0, 0);
+		assert_eq!(tx.call, mock::Call::Lending(lending::Call::liquidate(account(2), ASSET1, ASSET2, 4750)));
+	});
+}
+
+#[test]
+fn liquidates_debt_of_a_disabled_pool() {
+	let (mut t, _) = offchain_test_ext();
+	t.execute_with(|| {
+		make_unhealthy_position();
+		assert_ok!(mock::Call::Lending(lending::Call::set_pool_enabled(ASSET1, false)).dispatch(Origin::root()));
+
+		let liquidation = LendingKeeper::best_liquidation(account(2)).unwrap();
+		assert_eq!(liquidation.pay_asset_id, ASSET1);
+		assert_ok!(dispatch(1, lending::Call::liquidate(account(2), ASSET1, ASSET2, liquidation.pay_asset_amount)));
+	});
+}
+
+#[test]
+fn skips_healthy_positions() {
+	let (mut t, pool_state) = offchain_test_ext();
+	t.execute_with(|| {
+		assert_ok!(dispatch(1, lending::Call::supply(ASSET1, 100000)));
+		assert_ok!(dispatch(2, lending::Call::supply(ASSET2, 100000)));
+		assert_ok!(dispatch(2, lending::Call::borrow(ASSET1, 10000)));
+
+		assert!(LendingKeeper::best_liquidation(account(2)).is_none());
+		LendingKeeper::offchain_worker(1);
+		assert!(pool_state.read().transactions.is_empty());
+	});
+}
+
+#[test]
+fn follows_local_config() {
+	let (mut t, pool_state) = offchain_test_ext();
+	t.execute_with(|| {
+		make_unhealthy_position();
+		assert_eq!(LendingKeeper::config(), KeeperConfig::default());
+
+		set_config(KeeperConfig { enabled: false, max_liquidations: 5, min_profit: 0 });
+		LendingKeeper::offchain_worker(1);
+		assert!(pool_state.read().transactions.is_empty());
+
+		set_config(KeeperConfig { enabled: true, max_liquidations: 0, min_profit: 0 });
+		LendingKeeper::offchain_worker(1);
+		assert!(pool_state.read().transactions.is_empty());
+
+		set_config(KeeperConfig { enabled: true, max_liquidations: 5, min_profit: 25001 });
+		LendingKeeper::offchain_worker(1);
+		assert!(pool_state.read().transactions.is_empty());
+
+		set_config(KeeperConfig { enabled: true, max_liquidations: 5, min_profit: 25000 });
+		LendingKeeper::offchain_worker(1);
+		assert_eq!(pool_state.read().transactions.len(), 1);
+	});
+}
+
+#[test]
+fn needs_a_local_key() {
+	let (offchain, _state) = testing::TestOffchainExt::new();
+	let (pool, pool_state) = testing::TestTransactionPoolExt::new();
+	let mut t = new_test_ext();
+	t.register_extension(OffchainExt::new(offchain));
+	t.register_extension(TransactionPoolExt::new(pool));
+	t.register_extension(KeystoreExt(KeyStore::new()));
+	t.execute_with(|| {
+		make_unhealthy_position();
+		LendingKeeper::offchain_worker(1);
+		assert!(pool_state.read().transactions.is_empty());
+	});
+}
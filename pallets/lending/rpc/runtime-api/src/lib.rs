@@ -1,23 +1,173 @@
 #![cfg_attr(not(feature = "std"), no_std)]
-use codec::Codec;
+use codec::{Codec, Encode, Decode};
+#[cfg(feature = "std")]
+use serde::{Serialize, Deserialize, Serializer, Deserializer};
+use sp_runtime::traits::{MaybeDisplay, MaybeFromStr};
+use sp_std::vec::Vec;
+
+/// A user's supply and debt of one asset, with interest up to now
+#[derive(Eq, PartialEq, Encode, Decode, Default, Clone)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct AssetPosition<AssetId, Balance, Rate> {
+    pub asset_id: AssetId,
+    #[cfg_attr(feature = "std", serde(bound(serialize = "Balance: std::fmt::Display")))]
+    #[cfg_attr(feature = "std", serde(serialize_with = "serialize_as_string"))]
+    #[cfg_attr(feature = "std", serde(bound(deserialize = "Balance: std::str::FromStr")))]
+    #[cfg_attr(feature = "std", serde(deserialize_with = "deserialize_from_string"))]
+    pub supply: Balance,
+    #[cfg_attr(feature = "std", serde(bound(serialize = "Balance: std::fmt::Display")))]
+    #[cfg_attr(feature = "std", serde(serialize_with = "serialize_as_string"))]
+    #[cfg_attr(feature = "std", serde(bound(deserialize = "Balance: std::str::FromStr")))]
+    #[cfg_attr(feature = "std", serde(deserialize_with = "deserialize_from_string"))]
+    pub debt: Balance,
+    /// Oracle price the values are computed at
+    pub price: Rate,
+    /// If the supply counts as collateral
+    pub is_collateral: bool,
+    /// Collateral factor applying to the user, in efficiency mode or not
+    pub collateral_factor: Rate,
+    /// Liquidation threshold applying to the user, in efficiency mode or not
+    pub liquidation_threshold: Rate,
+}
+
+/// Totals of a user's position, values are in the price unit of the oracle
+#[derive(Eq, PartialEq, Encode, Decode, Default, Clone)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct UserInfo<Balance> {
+    /// Value of all supply
+    #[cfg_attr(feature = "std", serde(bound(serialize = "Balance: std::fmt::Display")))]
+    #[cfg_attr(feature = "std", serde(serialize_with = "serialize_as_string"))]
+    #[cfg_attr(feature = "std", serde(bound(deserialize = "Balance: std::str::FromStr")))]
+    #[cfg_attr(feature = "std", serde(deserialize_with = "deserialize_from_string"))]
+    pub supply_value: Balance,
+    /// Collateral value weighted by collateral factors, the most debt value the user can borrow up to
+    #[cfg_attr(feature = "std", serde(bound(serialize = "Balance: std::fmt::Display")))]
+    #[cfg_attr(feature = "std", serde(serialize_with = "serialize_as_string"))]
+    #[cfg_attr(feature = "std", serde(bound(deserialize = "Balance: std::str::FromStr")))]
+    #[cfg_attr(feature = "std", serde(deserialize_with = "deserialize_from_string"))]
+    pub borrow_limit: Balance,
+    /// Value of all debt
+    #[cfg_attr(feature = "std", serde(bound(serialize = "Balance: std::fmt::Display")))]
+    #[cfg_attr(feature = "std", serde(serialize_with = "serialize_as_string"))]
+    #[cfg_attr(feature = "std", serde(bound(deserialize = "Balance: std::str::FromStr")))]
+    #[cfg_attr(feature = "std", serde(deserialize_with = "deserialize_from_string"))]
+    pub debt_value: Balance,
+}
+
+/// A user's whole lending position, values are in the price unit of the oracle
+#[derive(Eq, PartialEq, Encode, Decode, Default, Clone)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct UserPosition<AssetId, Balance, Rate> {
+    /// Every asset supplied or borrowed
+    pub assets: Vec<AssetPosition<AssetId, Balance, Rate>>,
+    /// Value of all supply
+    #[cfg_attr(feature = "std", serde(bound(serialize = "Balance: std::fmt::Display")))]
+    #[cfg_attr(feature = "std", serde(serialize_with = "serialize_as_string"))]
+    #[cfg_attr(feature = "std", serde(bound(deserialize = "Balance: std::str::FromStr")))]
+    #[cfg_attr(feature = "std", serde(deserialize_with = "deserialize_from_string"))]
+    pub supply_value: Balance,
+    /// Value of the supply counting as collateral
+    #[cfg_attr(feature = "std", serde(bound(serialize = "Balance: std::fmt::Display")))]
+    #[cfg_attr(feature = "std", serde(serialize_with = "serialize_as_string"))]
+    #[cfg_attr(feature = "std", serde(bound(deserialize = "Balance: std::str::FromStr")))]
+    #[cfg_attr(feature = "std", serde(deserialize_with = "deserialize_from_string"))]
+    pub collateral_value: Balance,
+    /// Collateral value weighted by collateral factors, the most debt value the user can borrow up to
+    #[cfg_attr(feature = "std", serde(bound(serialize = "Balance: std::fmt::Display")))]
+    #[cfg_attr(feature = "std", serde(serialize_with = "serialize_as_string"))]
+    #[cfg_attr(feature = "std", serde(bound(deserialize = "Balance: std::str::FromStr")))]
+    #[cfg_attr(feature = "std", serde(deserialize_with = "deserialize_from_string"))]
+    pub borrow_limit: Balance,
+    /// Collateral value weighted by liquidation thresholds, the user is liquidatable with more debt value
+    #[cfg_attr(feature = "std", serde(bound(serialize = "Balance: std::fmt::Display")))]
+    #[cfg_attr(feature = "std", serde(serialize_with = "serialize_as_string"))]
+    #[cfg_attr(feature = "std", serde(bound(deserialize = "Balance: std::str::FromStr")))]
+    #[cfg_attr(feature = "std", serde(deserialize_with = "deserialize_from_string"))]
+    pub liquidation_threshold_value: Balance,
+    /// Value of all debt
+    #[cfg_attr(feature = "std", serde(bound(serialize = "Balance: std::fmt::Display")))]
+    #[cfg_attr(feature = "std", serde(serialize_with = "serialize_as_string"))]
+    #[cfg_attr(feature = "std", serde(bound(deserialize = "Balance: std::str::FromStr")))]
+    #[cfg_attr(feature = "std", serde(deserialize_with = "deserialize_from_string"))]
+    pub debt_value: Balance,
+    /// Liquidation threshold value over debt value, liquidatable below 1; none without debt
+    pub health_factor: Option<Rate>,
+}
+
+#[cfg(feature = "std")]
+fn serialize_as_string<S: Serializer, T: std::fmt::Display>(t: &T, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&t.to_string())
+}
+
+#[cfg(feature = "std")]
+fn deserialize_from_string<'de, D: Deserializer<'de>, T: std::str::FromStr>(deserializer: D) -> Result<T, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    s.parse::<T>().map_err(|_| serde::de::Error::custom("Parse from string failed"))
+}
 
 sp_api::decl_runtime_apis! {
-    pub trait LendingApi<AssetId, FixedU128, AccountId, Balance> where 
-        AssetId: Codec,
-        FixedU128: Codec,
-        AccountId: Codec,
-        Balance: Codec
-    {
+    #[api_version(2)]
+    pub trait LendingApi<AssetId: Codec, FixedU128: Codec, AccountId: Codec, Balance: Codec + MaybeDisplay + MaybeFromStr> {
         fn supply_rate(id: AssetId) -> FixedU128;
 
         fn debt_rate(id: AssetId) -> FixedU128;
 
+        // rate a stable borrow locks now, none if stable borrowing is disabled
+        fn stable_debt_rate(id: AssetId) -> Option<FixedU128>;
+
         // effective supply balance; borrow balance
+        #[changed_in(2)]
         fn get_user_info(user: AccountId) -> (u64, u64, u64);
 
+        // total supply value; supply value weighted by collateral factors; total debt value
+        fn get_user_info(user: AccountId) -> UserInfo<Balance>;
+
+        // every supply and debt of a user, with the values and health factor of the position
+        fn get_user_position(user: AccountId) -> UserPosition<AssetId, Balance, FixedU128>;
+
         fn get_user_debt_with_interest(asset_id: AssetId, user: AccountId) -> Balance;
 
         fn get_user_supply_with_interest(asset_id: AssetId, user: AccountId) -> Balance;
 
+        fn get_reserves(id: AssetId) -> Balance;
+
+        fn supply_cap(id: AssetId) -> Option<Balance>;
+
+        fn borrow_cap(id: AssetId) -> Option<Balance>;
+
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn balances_serialize_in_full_precision() {
+        let position = UserPosition::<u32, u128, u64> {
+            assets: vec![AssetPosition { asset_id: 1, supply: u128::MAX, debt: 0, price: 2, is_collateral: true, collateral_factor: 3, liquidation_threshold: 4 }],
+            supply_value: u128::MAX,
+            collateral_value: 1,
+            borrow_limit: 2,
+            liquidation_threshold_value: 3,
+            debt_value: 0,
+            health_factor: None,
+        };
+        let json_str = r#"{"assets":[{"assetId":1,"supply":"340282366920938463463374607431768211455","debt":"0","price":2,"isCollateral":true,"collateralFactor":3,"liquidationThreshold":4}],"supplyValue":"340282366920938463463374607431768211455","collateralValue":"1","borrowLimit":"2","liquidationThresholdValue":"3","debtValue":"0","healthFactor":null}"#;
+
+        assert_eq!(serde_json::to_string(&position).unwrap(), json_str);
+        assert_eq!(serde_json::from_str::<UserPosition<u32, u128, u64>>(json_str).unwrap(), position);
+    }
+
+    #[test]
+    fn user_info_serializes_in_full_precision() {
+        let info = UserInfo::<u128> { supply_value: u128::MAX, borrow_limit: 1, debt_value: 0 };
+        let json_str = r#"{"supplyValue":"340282366920938463463374607431768211455","borrowLimit":"1","debtValue":"0"}"#;
+
+        assert_eq!(serde_json::to_string(&info).unwrap(), json_str);
+        assert_eq!(serde_json::from_str::<UserInfo<u128>>(json_str).unwrap(), info);
     }
-}
\ No newline at end of file
+}
@@ -3,17 +3,19 @@ use std::sync::Arc;
 use codec::Codec;
 use jsonrpc_core::{ErrorCode, Result, Error as RpcError};
 use jsonrpc_derive::rpc;
-use sp_api::ProvideRuntimeApi;
+use sp_api::{ApiExt, ProvideRuntimeApi};
 use sp_blockchain::HeaderBackend;
 use sp_runtime::{
 	generic::BlockId,
-	traits::Block as BlockT,
+	traits::{Block as BlockT, MaybeDisplay, MaybeFromStr},
 };
 
-pub use pallet_lending_rpc_runtime_api::LendingApi as LendingRuntimeApi;
+pub use pallet_lending_rpc_runtime_api::{LendingApi as LendingRuntimeApi, UserInfo, UserPosition, AssetPosition};
 
 #[rpc]
-pub trait LendingApi<BlockHash, AssetId, FixedU128, AccountId, Balance> {
+pub trait LendingApi<BlockHash, AssetId, FixedU128, AccountId, Balance> where
+    Balance: MaybeDisplay + MaybeFromStr,
+{
 
     #[rpc(name = "lending_supplyRate")]
     fn supply_rate(
@@ -29,12 +31,26 @@ pub trait LendingApi<BlockHash, AssetId, FixedU128, AccountId, Balance> {
         at: Option<BlockHash>
     ) -> Result<FixedU128>;
 
+    #[rpc(name = "lending_stableDebtRate")]
+    fn stable_debt_rate(
+        &self,
+        id: AssetId,
+        at: Option<BlockHash>
+    ) -> Result<Option<FixedU128>>;
+
     #[rpc(name = "lending_getUserInfo")]
     fn get_user_info(
         &self,
         user: AccountId,
         at: Option<BlockHash>
-    ) -> Result<(u64, u64, u64)>;
+    ) -> Result<UserInfo<Balance>>;
+
+    #[rpc(name = "lending_getUserPosition")]
+    fn get_user_position(
+        &self,
+        user: AccountId,
+        at: Option<BlockHash>
+    ) -> Result<UserPosition<AssetId, Balance, FixedU128>>;
 
     #[rpc(name = "lending_getUserDebtWithInterest")]
     fn get_user_debt_with_interest(
@@ -52,6 +68,27 @@ pub trait LendingApi<BlockHash, AssetId, FixedU128, AccountId, Balance> {
         at: Option<BlockHash>
     ) -> Result<Balance>;
 
+    #[rpc(name = "lending_getReserves")]
+    fn get_reserves(
+        &self,
+        id: AssetId,
+        at: Option<BlockHash>
+    ) -> Result<Balance>;
+
+    #[rpc(name = "lending_supplyCap")]
+    fn supply_cap(
+        &self,
+        id: AssetId,
+        at: Option<BlockHash>
+    ) -> Result<Option<Balance>>;
+
+    #[rpc(name = "lending_borrowCap")]
+    fn borrow_cap(
+        &self,
+        id: AssetId,
+        at: Option<BlockHash>
+    ) -> Result<Option<Balance>>;
+
 }
 
 /// A struct that implements the `SumStorageApi`.
@@ -78,7 +115,7 @@ where
     AssetId: Codec,
     FixedU128: Codec,
     AccountId: Codec,
-	Balance: Codec,
+	Balance: Codec + MaybeDisplay + MaybeFromStr + From<u64>,
 {
     fn supply_rate(
         &self,
@@ -118,18 +155,48 @@ where
         })  
     }
 
+    fn stable_debt_rate(
+        &self,
+        id: AssetId,
+        at: Option<<Block as BlockT>::Hash>
+    ) -> Result<Option<FixedU128>> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(||
+            // If the block hash is not supplied assume the best block.
+            self.client.info().best_hash
+        ));
+
+        let runtime_api_result = api.stable_debt_rate(&at, id);
+        runtime_api_result.map_err(|e| RpcError {
+            code: ErrorCode::ServerError(9876), // No real reason for this value
+            message: "Something wrong".into(),
+            data: Some(format!("{:?}", e).into()),
+        })
+    }
+
     fn get_user_info(
         &self,
         user: AccountId,
         at: Option<<Block as BlockT>::Hash>
-    ) -> Result<(u64, u64, u64)> {
+    ) -> Result<UserInfo<Balance>> {
         let api = self.client.runtime_api();
         let at = BlockId::hash(at.unwrap_or_else(||
             // If the block hash is not supplied assume the best block.
             self.client.info().best_hash
         ));
 
-        let runtime_api_result = api.get_user_info(&at, user);
+        // runtimes before version 2 of the api return the totals as u64
+        let runtime_api_result = api.has_api_with::<dyn LendingRuntimeApi<Block, AssetId, FixedU128, AccountId, Balance, Error = ()>, _>(&at, |version| version >= 2)
+            .and_then(|current| if current {
+                api.get_user_info(&at, user)
+            } else {
+                #[allow(deprecated)]
+                api.get_user_info_before_version_2(&at, user).map(|(supply_value, borrow_limit, debt_value)| UserInfo {
+                    supply_value: supply_value.into(),
+                    borrow_limit: borrow_limit.into(),
+                    debt_value: debt_value.into(),
+                })
+            });
         runtime_api_result.map_err(|e| RpcError {
             code: ErrorCode::ServerError(9876), // No real reason for this value
             message: "Something wrong".into(),
@@ -137,6 +204,25 @@ where
         })  
     }
 
+    fn get_user_position(
+        &self,
+        user: AccountId,
+        at: Option<<Block as BlockT>::Hash>
+    ) -> Result<UserPosition<AssetId, Balance, FixedU128>> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(||
+            // If the block hash is not supplied assume the best block.
+            self.client.info().best_hash
+        ));
+
+        let runtime_api_result = api.get_user_position(&at, user);
+        runtime_api_result.map_err(|e| RpcError {
+            code: ErrorCode::ServerError(9876), // No real reason for this value
+            message: "Something wrong".into(),
+            data: Some(format!("{:?}", e).into()),
+        })
+    }
+
     fn get_user_debt_with_interest(
         &self,
         asset_id: AssetId, 
@@ -176,4 +262,61 @@ where
             data: Some(format!("{:?}", e).into()),
         }) 
     }
+
+    fn get_reserves(
+        &self,
+        id: AssetId,
+        at: Option<<Block as BlockT>::Hash>
+    ) -> Result<Balance> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(||
+            // If the block hash is not supplied assume the best block.
+            self.client.info().best_hash
+        ));
+
+        let runtime_api_result = api.get_reserves(&at, id);
+        runtime_api_result.map_err(|e| RpcError {
+            code: ErrorCode::ServerError(9876), // No real reason for this value
+            message: "Something wrong".into(),
+            data: Some(format!("{:?}", e).into()),
+        })
+    }
+
+    fn supply_cap(
+        &self,
+        id: AssetId,
+        at: Option<<Block as BlockT>::Hash>
+    ) -> Result<Option<Balance>> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(||
+            // If the block hash is not supplied assume the best block.
+            self.client.info().best_hash
+        ));
+
+        let runtime_api_result = api.supply_cap(&at, id);
+        runtime_api_result.map_err(|e| RpcError {
+            code: ErrorCode::ServerError(9876), // No real reason for this value
+            message: "Something wrong".into(),
+            data: Some(format!("{:?}", e).into()),
+        })
+    }
+
+    fn borrow_cap(
+        &self,
+        id: AssetId,
+        at: Option<<Block as BlockT>::Hash>
+    ) -> Result<Option<Balance>> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(||
+            // If the block hash is not supplied assume the best block.
+            self.client.info().best_hash
+        ));
+
+        let runtime_api_result = api.borrow_cap(&at, id);
+        runtime_api_result.map_err(|e| RpcError {
+            code: ErrorCode::ServerError(9876), // No real reason for this value
+            message: "Something wrong".into(),
+            data: Some(format!("{:?}", e).into()),
+        })
+    }
 }
\ No newline at end of file
@@ -0,0 +1,503 @@
+//! Lending pallet benchmarking.
+
+#![cfg(feature = "runtime-benchmarks")]
+
+use super::*;
+use sp_std::result::Result;
+use frame_system::RawOrigin;
+use frame_support::IterableStorageMap;
+use frame_benchmarking::{benchmarks, account, whitelisted_caller};
+
+use crate::Module as Lending;
+
+const SEED: u32 = 0;
+/// Minted of every asset to every account taking part
+const BALANCE: u32 = 1_000_000_000;
+/// Supplied to every pool by the lender, and by users to their collateral pools
+const SUPPLY: u32 = 1_000_000;
+
+fn signed<T: Trait>(who: &T::AccountId) -> T::Origin {
+    RawOrigin::Signed(who.clone()).into()
+}
+
+fn rate(n: u128, d: u128) -> FixedU128 {
+    FixedU128::saturating_from_rational(n, d)
+}
+
+/// a collateral pool of a new asset priced at 1, which the holders get `BALANCE` of
+fn create_pool<T: Trait>(holders: &[T::AccountId]) -> Result<T::AssetId, &'static str> {
+    let id = T::MultiAsset::create_asset();
+    for holder in holders {
+        T::MultiAsset::mint(id, holder.clone(), BALANCE.into())?;
+    }
+    T::Oracle::set_rate(id, FixedU128::one());
+    Lending::<T>::_init_pool(id, true, InterestRateModel::default());
+    Ok(id)
+}
+
+/// `u` >= 2 pools funded by a lender, and a healthy position of `user` in all of them.
+/// The first pool has a dust supply of the user and its large debt, the others its large supplies and small debts,
+/// at the stable rate if `stable`. The caller holds every asset too.
+fn create_position<T: Trait>(user: &T::AccountId, u: u32, stable: bool) -> Result<Vec<T::AssetId>, &'static str> {
+    let lender: T::AccountId = account("lender", 0, SEED);
+    let caller: T::AccountId = whitelisted_caller();
+    let mut assets = Vec::new();
+    for i in 0..u {
+        let id = create_pool::<T>(&[user.clone(), lender.clone(), caller.clone()])?;
+        Lending::<T>::set_stable_rate_config(
+            RawOrigin::Root.into(),
+            id,
+            Some(StableRateConfig { premium: FixedU128::zero(), rebalance_spread: FixedU128::zero() }),
+        )?;
+        Lending::<T>::supply(signed::<T>(&lender), id, SUPPLY.into())?;
+        let amount = if i == 0 { 1 } else { SUPPLY };
+        Lending::<T>::supply(signed::<T>(user), id, amount.into())?;
+        assets.push(id);
+    }
+    Lending::<T>::borrow(signed::<T>(user), assets[0], (SUPPLY / 2).into())?;
+    for id in assets.iter().skip(1) {
+        if stable {
+            Lending::<T>::borrow_stable(signed::<T>(user), *id, (SUPPLY / 100).into())?;
+        } else {
+            Lending::<T>::borrow(signed::<T>(user), *id, (SUPPLY / 100).into())?;
+        }
+    }
+    Ok(assets)
+}
+
+/// raise the price of the large debt until the position of `u` assets is liquidatable
+fn make_unhealthy<T: Trait>(assets: &[T::AssetId], u: u32) {
+    T::Oracle::set_rate(assets[0], FixedU128::saturating_from_integer(2 * u));
+}
+
+fn debt_of<T: Trait>(asset_id: T::AssetId, user: &T::AccountId) -> T::Balance {
+    Lending::<T>::user_debt(asset_id, user.clone()).map_or_else(Zero::zero, |user_debt| user_debt.amount)
+}
+
+fn supply_of<T: Trait>(asset_id: T::AssetId, user: &T::AccountId) -> T::Balance {
+    Lending::<T>::user_supply(asset_id, user.clone()).map_or_else(Zero::zero, |user_supply| user_supply.amount)
+}
+
+benchmarks! {
+    where_clause { where <T as Trait>::Call: From<frame_system::Call<T>> }
+
+    _ { }
+
+    // every auction expires at the last second checked
+    on_initialize {
+        let a in 0 .. MAX_AUCTION_CLOSES;
+        let id = create_pool::<T>(&[])?;
+        // every auction ends at a time of its own
+        for i in 0..a {
+            let ends_at = i as u64 + 1;
+            let target_user: T::AccountId = account("target", i, SEED);
+            Auctions::<T>::insert(i, Auction::<T> {
+                target_user: target_user.clone(),
+                pay_asset_id: id,
+                get_asset_id: id,
+                collateral: SUPPLY.into(),
+                debt: SUPPLY.into(),
+                start_price: FixedU128::one(),
+                end_price: FixedU128::one(),
+                started_at: 0,
+                ends_at,
+            });
+            AuctionExpiries::append(ends_at, i);
+            AuctionExpiryTimes::append(ends_at);
+            UserAuction::<T>::insert(target_user, i);
+        }
+    }: { Lending::<T>::close_expired_auctions(a as u64); }
+    verify {
+        ensure!(Auctions::<T>::iter().next().is_none(), "Auctions were not closed.");
+        ensure!(Lending::<T>::auction_expiry_times().is_empty(), "Expiries were not removed.");
+    }
+
+    supply {
+        let caller: T::AccountId = whitelisted_caller();
+        let lender: T::AccountId = account("lender", 0, SEED);
+        let id = create_pool::<T>(&[caller.clone(), lender.clone()])?;
+        Lending::<T>::supply(signed::<T>(&lender), id, SUPPLY.into())?;
+    }: _(RawOrigin::Signed(caller.clone()), id, SUPPLY.into())
+    verify {
+        ensure!(supply_of::<T>(id, &caller) == SUPPLY.into(), "Asset was not supplied.");
+    }
+
+    withdraw {
+        let u in 2 .. MAX_USER_ASSETS;
+        let caller: T::AccountId = whitelisted_caller();
+        let assets = create_position::<T>(&caller, u, false)?;
+    }: _(RawOrigin::Signed(caller.clone()), assets[1], (SUPPLY / 10).into())
+    verify {
+        ensure!(supply_of::<T>(assets[1], &caller) == (SUPPLY - SUPPLY / 10).into(), "Asset was not withdrawn.");
+    }
+
+    borrow {
+        let u in 2 .. MAX_USER_ASSETS;
+        let caller: T::AccountId = whitelisted_caller();
+        let assets = create_position::<T>(&caller, u, false)?;
+    }: _(RawOrigin::Signed(caller.clone()), assets[1], (SUPPLY / 100).into())
+    verify {
+        ensure!(debt_of::<T>(assets[1], &caller) == (SUPPLY / 50).into(), "Asset was not borrowed.");
+    }
+
+    // adds to a stable debt, averaging the rate
+    borrow_stable {
+        let u in 2 .. MAX_USER_ASSETS;
+        let caller: T::AccountId = whitelisted_caller();
+        let assets = create_position::<T>(&caller, u, true)?;
+    }: _(RawOrigin::Signed(caller.clone()), assets[1], (SUPPLY / 100).into())
+    verify {
+        ensure!(debt_of::<T>(assets[1], &caller) == (SUPPLY / 50).into(), "Asset was not borrowed.");
+    }
+
+    borrow_on_behalf {
+        let u in 2 .. MAX_USER_ASSETS;
+        let caller: T::AccountId = whitelisted_caller();
+        let delegator: T::AccountId = account("delegator", 0, SEED);
+        let assets = create_position::<T>(&delegator, u, false)?;
+        Lending::<T>::approve_delegation(signed::<T>(&delegator), caller.clone(), assets[1], SUPPLY.into())?;
+    }: _(RawOrigin::Signed(caller), delegator.clone(), assets[1], (SUPPLY / 100).into())
+    verify {
+        ensure!(debt_of::<T>(assets[1], &delegator) == (SUPPLY / 50).into(), "Asset was not borrowed.");
+    }
+
+    approve_delegation {
+        let caller: T::AccountId = whitelisted_caller();
+        let delegatee: T::AccountId = account("delegatee", 0, SEED);
+        let id = create_pool::<T>(&[])?;
+    }: _(RawOrigin::Signed(caller.clone()), delegatee.clone(), id, SUPPLY.into())
+    verify {
+        ensure!(Lending::<T>::borrow_allowance(caller, (delegatee, id)) == SUPPLY.into(), "Delegation was not approved.");
+    }
+
+    revoke_delegation {
+        let caller: T::AccountId = whitelisted_caller();
+        let delegatee: T::AccountId = account("delegatee", 0, SEED);
+        let id = create_pool::<T>(&[])?;
+        Lending::<T>::approve_delegation(signed::<T>(&caller), delegatee.clone(), id, SUPPLY.into())?;
+    }: _(RawOrigin::Signed(caller.clone()), delegatee.clone(), id)
+    verify {
+        ensure!(!BorrowAllowances::<T>::contains_key(caller, (delegatee, id)), "Delegation was not revoked.");
+    }
+
+    repay {
+        let caller: T::AccountId = whitelisted_caller();
+        let assets = create_position::<T>(&caller, 2, false)?;
+    }: _(RawOrigin::Signed(caller.clone()), assets[1], (SUPPLY / 200).into())
+    verify {
+        ensure!(debt_of::<T>(assets[1], &caller) == (SUPPLY / 200).into(), "Asset was not repaid.");
+    }
+
+    repay_on_behalf {
+        let caller: T::AccountId = whitelisted_caller();
+        let user: T::AccountId = account("user", 0, SEED);
+        let assets = create_position::<T>(&user, 2, false)?;
+    }: _(RawOrigin::Signed(caller), user.clone(), assets[1], (SUPPLY / 200).into())
+    verify {
+        ensure!(debt_of::<T>(assets[1], &user) == (SUPPLY / 200).into(), "Asset was not repaid.");
+    }
+
+    // the debt leaves the user's debt set
+    repay_all {
+        let caller: T::AccountId = whitelisted_caller();
+        let assets = create_position::<T>(&caller, 2, false)?;
+    }: _(RawOrigin::Signed(caller.clone()), assets[1])
+    verify {
+        ensure!(Lending::<T>::user_debt(assets[1], caller).is_none(), "Asset was not repaid.");
+    }
+
+    enable_collateral {
+        let caller: T::AccountId = whitelisted_caller();
+        let id = create_pool::<T>(sp_std::slice::from_ref(&caller))?;
+        Lending::<T>::supply(signed::<T>(&caller), id, SUPPLY.into())?;
+        Lending::<T>::disable_collateral(signed::<T>(&caller), id)?;
+    }: _(RawOrigin::Signed(caller.clone()), id)
+    verify {
+        ensure!(Lending::<T>::assets_in(Lending::<T>::user_collateral_set(caller)).contains(&id), "Collateral was not enabled.");
+    }
+
+    disable_collateral {
+        let u in 2 .. MAX_USER_ASSETS;
+        let caller: T::AccountId = whitelisted_caller();
+        let assets = create_position::<T>(&caller, u, false)?;
+    }: _(RawOrigin::Signed(caller.clone()), assets[0])
+    verify {
+        ensure!(!Lending::<T>::assets_in(Lending::<T>::user_collateral_set(caller)).contains(&assets[0]), "Collateral was not disabled.");
+    }
+
+    set_user_emode {
+        let u in 2 .. MAX_USER_ASSETS;
+        let caller: T::AccountId = whitelisted_caller();
+        let assets = create_position::<T>(&caller, u, false)?;
+        Lending::<T>::set_emode_category(
+            RawOrigin::Root.into(),
+            1,
+            Some(EModeCategory { collateral_factor: rate(9, 10), liquidation_threshold: rate(95, 100) }),
+        )?;
+        for id in assets {
+            Lending::<T>::set_asset_emode_category(RawOrigin::Root.into(), id, Some(1))?;
+        }
+    }: _(RawOrigin::Signed(caller.clone()), Some(1))
+    verify {
+        ensure!(Lending::<T>::user_emode_category(caller) == Some(1), "Efficiency mode was not entered.");
+    }
+
+    liquidate {
+        let u in 2 .. MAX_USER_ASSETS;
+        let caller: T::AccountId = whitelisted_caller();
+        let user: T::AccountId = account("user", 0, SEED);
+        let assets = create_position::<T>(&user, u, false)?;
+        make_unhealthy::<T>(&assets, u);
+    }: _(RawOrigin::Signed(caller), user.clone(), assets[0], assets[1], (SUPPLY / 100).into())
+    verify {
+        ensure!(debt_of::<T>(assets[0], &user) == (SUPPLY / 2 - SUPPLY / 100).into(), "Position was not liquidated.");
+    }
+
+    // pairs repay the large debt against each collateral in turn
+    liquidate_batch {
+        let u in 2 .. MAX_USER_ASSETS;
+        let p in 1 .. MAX_BATCH_LIQUIDATIONS;
+        let caller: T::AccountId = whitelisted_caller();
+        let user: T::AccountId = account("user", 0, SEED);
+        let assets = create_position::<T>(&user, u, false)?;
+        make_unhealthy::<T>(&assets, u);
+        let liquidations = (0..p)
+            .map(|i| (assets[0], assets[1 + (i % (u - 1)) as usize], (SUPPLY / 1000).into()))
+            .collect::<Vec<_>>();
+    }: _(RawOrigin::Signed(caller), user.clone(), liquidations)
+    verify {
+        ensure!(debt_of::<T>(assets[0], &user) == (SUPPLY / 2 - p * SUPPLY / 1000).into(), "Position was not liquidated.");
+    }
+
+    start_auction {
+        let u in 2 .. MAX_USER_ASSETS;
+        let caller: T::AccountId = whitelisted_caller();
+        let user: T::AccountId = account("user", 0, SEED);
+        let assets = create_position::<T>(&user, u, false)?;
+        make_unhealthy::<T>(&assets, u);
+    }: _(RawOrigin::Signed(caller), user.clone(), assets[0], assets[1])
+    verify {
+        ensure!(Lending::<T>::user_auction(user).is_some(), "Auction was not started.");
+    }
+
+    bid {
+        let caller: T::AccountId = whitelisted_caller();
+        let user: T::AccountId = account("user", 0, SEED);
+        let assets = create_position::<T>(&user, 2, false)?;
+        make_unhealthy::<T>(&assets, 2);
+        let auction_id = Lending::<T>::next_auction_id();
+        Lending::<T>::start_auction(signed::<T>(&caller), user.clone(), assets[0], assets[1])?;
+    }: _(RawOrigin::Signed(caller), auction_id, (SUPPLY / 100).into())
+    verify {
+        ensure!(debt_of::<T>(assets[0], &user) < (SUPPLY / 2).into(), "Collateral was not bought.");
+    }
+
+    // the lender's variable borrow raises the rate above the user's stable rate
+    rebalance_stable_rate {
+        let caller: T::AccountId = whitelisted_caller();
+        let user: T::AccountId = account("user", 0, SEED);
+        let lender: T::AccountId = account("lender", 0, SEED);
+        let assets = create_position::<T>(&user, 2, true)?;
+        Lending::<T>::borrow(signed::<T>(&lender), assets[1], (SUPPLY / 2).into())?;
+        let locked = Lending::<T>::user_debt(assets[1], user.clone()).and_then(|user_debt| user_debt.stable_rate);
+    }: _(RawOrigin::Signed(caller), user.clone(), assets[1])
+    verify {
+        let rebalanced = Lending::<T>::user_debt(assets[1], user).and_then(|user_debt| user_debt.stable_rate);
+        ensure!(rebalanced > locked, "Stable rate was not rebalanced.");
+    }
+
+    // the weight of the call is added by the call itself
+    flash_loan {
+        let caller: T::AccountId = whitelisted_caller();
+        let lender: T::AccountId = account("lender", 0, SEED);
+        let id = create_pool::<T>(sp_std::slice::from_ref(&lender))?;
+        Lending::<T>::supply(signed::<T>(&lender), id, SUPPLY.into())?;
+        let call: <T as Trait>::Call = frame_system::Call::<T>::remark(vec![]).into();
+    }: _(RawOrigin::Signed(caller.clone()), id, SUPPLY.into(), Box::new(call))
+    verify {
+        ensure!(T::MultiAsset::balance(id, caller).is_zero(), "Flash loan was not repaid.");
+    }
+
+    // a kToken of a collateral pool leaving a position in every pool, through both transfer hooks
+    transfer_ktoken {
+        let u in 2 .. MAX_USER_ASSETS;
+        let caller: T::AccountId = whitelisted_caller();
+        let receiver: T::AccountId = account("receiver", 0, SEED);
+        let assets = create_position::<T>(&caller, u, false)?;
+        let ktoken = Lending::<T>::ktoken(assets[1]).ok_or("Pool has no kToken.")?;
+    }: {
+        T::MultiAsset::transfer(caller.clone(), ktoken, receiver.clone(), (SUPPLY / 10).into())?;
+    }
+    verify {
+        ensure!(!supply_of::<T>(assets[1], &receiver).is_zero(), "Supply was not moved.");
+    }
+
+    init_pool {
+        let id = T::MultiAsset::create_asset();
+    }: _(RawOrigin::Root, id, true, InterestRateModel::default())
+    verify {
+        ensure!(Lending::<T>::pool(id).is_some(), "Pool was not created.");
+    }
+
+    set_interest_rate_model {
+        let id = create_pool::<T>(&[])?;
+    }: _(RawOrigin::Root, id, InterestRateModel::default())
+
+    set_collateral_factor {
+        let id = create_pool::<T>(&[])?;
+    }: _(RawOrigin::Root, id, rate(1, 2))
+
+    set_liquidation_threshold {
+        let id = create_pool::<T>(&[])?;
+    }: _(RawOrigin::Root, id, rate(9, 10))
+
+    set_close_factor {
+        let id = create_pool::<T>(&[])?;
+    }: _(RawOrigin::Root, id, rate(1, 2))
+
+    set_discount_factor {
+        let id = create_pool::<T>(&[])?;
+    }: _(RawOrigin::Root, id, rate(9, 10))
+
+    set_can_be_collateral {
+        let id = create_pool::<T>(&[])?;
+    }: _(RawOrigin::Root, id, false)
+
+    set_supply_cap {
+        let id = create_pool::<T>(&[])?;
+    }: _(RawOrigin::Root, id, Some(SUPPLY.into()))
+
+    set_borrow_cap {
+        let id = create_pool::<T>(&[])?;
+    }: _(RawOrigin::Root, id, Some(SUPPLY.into()))
+
+    set_stable_rate_config {
+        let id = create_pool::<T>(&[])?;
+    }: _(RawOrigin::Root, id, Some(StableRateConfig { premium: rate(1, 100), rebalance_spread: rate(1, 10) }))
+
+    set_pool_enabled {
+        let id = create_pool::<T>(&[])?;
+    }: _(RawOrigin::Root, id, false)
+
+    set_dust_threshold {
+    }: _(RawOrigin::Root, SUPPLY.into())
+
+    // no reserves, so the debt is socialized
+    write_off_bad_debt {
+        let user: T::AccountId = account("user", 0, SEED);
+        let assets = create_position::<T>(&user, 2, false)?;
+        UserCollateralSet::<T>::remove(user.clone());
+        UserSupplySet::<T>::remove(user.clone());
+    }: _(RawOrigin::Root, user.clone(), assets[0])
+    verify {
+        ensure!(Lending::<T>::user_debt(assets[0], user).is_none(), "Debt was not written off.");
+    }
+
+    set_pause_guardian {
+        let guardian: T::AccountId = account("guardian", 0, SEED);
+    }: _(RawOrigin::Root, Some(guardian))
+
+    // the guardian is checked after root
+    set_action_paused {
+        let caller: T::AccountId = whitelisted_caller();
+        let id = create_pool::<T>(&[])?;
+        Lending::<T>::set_pause_guardian(RawOrigin::Root.into(), Some(caller.clone()))?;
+    }: _(RawOrigin::Signed(caller), id, PoolAction::Borrow, true)
+    verify {
+        ensure!(Lending::<T>::is_paused(id, PoolAction::Borrow), "Action was not paused.");
+    }
+
+    set_reserve_factor {
+        let id = create_pool::<T>(&[])?;
+    }: _(RawOrigin::Root, id, rate(1, 10))
+
+    set_emode_category {
+    }: _(RawOrigin::Root, 1, Some(EModeCategory { collateral_factor: rate(9, 10), liquidation_threshold: rate(95, 100) }))
+
+    set_asset_emode_category {
+        let id = create_pool::<T>(&[])?;
+        Lending::<T>::set_emode_category(
+            RawOrigin::Root.into(),
+            1,
+            Some(EModeCategory { collateral_factor: rate(9, 10), liquidation_threshold: rate(95, 100) }),
+        )?;
+    }: _(RawOrigin::Root, id, Some(1))
+
+    set_auction_config {
+    }: _(RawOrigin::Root, AuctionConfig::default())
+
+    set_flash_loan_fee {
+    }: _(RawOrigin::Root, rate(1, 1000))
+
+    set_max_price_age {
+        let id = create_pool::<T>(&[])?;
+    }: _(RawOrigin::Root, id, Some(3600))
+    verify {
+        ensure!(Lending::<T>::max_price_age(id) == Some(3600), "Max price age was not set.");
+    }
+
+    withdraw_reserves {
+        let to: T::AccountId = account("to", 0, SEED);
+        let id = create_pool::<T>(&[Lending::<T>::account_id()])?;
+        Pools::<T>::mutate(id, |pool| if let Some(pool) = pool { pool.reserves = SUPPLY.into() });
+    }: _(RawOrigin::Root, id, to.clone(), SUPPLY.into())
+    verify {
+        ensure!(T::MultiAsset::balance(id, to) == SUPPLY.into(), "Reserves were not withdrawn.");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{ExtBuilder, Test};
+    use frame_support::assert_ok;
+
+    #[test]
+    fn test_benchmarks() {
+        ExtBuilder::default().build().execute_with(|| {
+            assert_ok!(test_benchmark_on_initialize::<Test>());
+            assert_ok!(test_benchmark_supply::<Test>());
+            assert_ok!(test_benchmark_withdraw::<Test>());
+            assert_ok!(test_benchmark_borrow::<Test>());
+            assert_ok!(test_benchmark_borrow_stable::<Test>());
+            assert_ok!(test_benchmark_borrow_on_behalf::<Test>());
+            assert_ok!(test_benchmark_approve_delegation::<Test>());
+            assert_ok!(test_benchmark_revoke_delegation::<Test>());
+            assert_ok!(test_benchmark_repay::<Test>());
+            assert_ok!(test_benchmark_repay_on_behalf::<Test>());
+            assert_ok!(test_benchmark_repay_all::<Test>());
+            assert_ok!(test_benchmark_enable_collateral::<Test>());
+            assert_ok!(test_benchmark_disable_collateral::<Test>());
+            assert_ok!(test_benchmark_set_user_emode::<Test>());
+            assert_ok!(test_benchmark_liquidate::<Test>());
+            assert_ok!(test_benchmark_liquidate_batch::<Test>());
+            assert_ok!(test_benchmark_start_auction::<Test>());
+            assert_ok!(test_benchmark_bid::<Test>());
+            assert_ok!(test_benchmark_rebalance_stable_rate::<Test>());
+            assert_ok!(test_benchmark_flash_loan::<Test>());
+            assert_ok!(test_benchmark_transfer_ktoken::<Test>());
+            assert_ok!(test_benchmark_init_pool::<Test>());
+            assert_ok!(test_benchmark_set_interest_rate_model::<Test>());
+            assert_ok!(test_benchmark_set_collateral_factor::<Test>());
+            assert_ok!(test_benchmark_set_liquidation_threshold::<Test>());
+            assert_ok!(test_benchmark_set_close_factor::<Test>());
+            assert_ok!(test_benchmark_set_discount_factor::<Test>());
+            assert_ok!(test_benchmark_set_can_be_collateral::<Test>());
+            assert_ok!(test_benchmark_set_supply_cap::<Test>());
+            assert_ok!(test_benchmark_set_borrow_cap::<Test>());
+            assert_ok!(test_benchmark_set_stable_rate_config::<Test>());
+            assert_ok!(test_benchmark_set_pool_enabled::<Test>());
+            assert_ok!(test_benchmark_set_dust_threshold::<Test>());
+            assert_ok!(test_benchmark_write_off_bad_debt::<Test>());
+            assert_ok!(test_benchmark_set_pause_guardian::<Test>());
+            assert_ok!(test_benchmark_set_action_paused::<Test>());
+            assert_ok!(test_benchmark_set_reserve_factor::<Test>());
+            assert_ok!(test_benchmark_set_emode_category::<Test>());
+            assert_ok!(test_benchmark_set_asset_emode_category::<Test>());
+            assert_ok!(test_benchmark_set_auction_config::<Test>());
+            assert_ok!(test_benchmark_set_flash_loan_fee::<Test>());
+            assert_ok!(test_benchmark_set_max_price_age::<Test>());
+            assert_ok!(test_benchmark_withdraw_reserves::<Test>());
+        });
+    }
+}
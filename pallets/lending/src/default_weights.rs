@@ -0,0 +1,245 @@
+//! Weights for pallet_lending, from its benchmarks run natively with 20 repeats over 14 steps.
+//! Regenerate with the node's `benchmark` command against the wasm runtime.
+
+#![allow(unused_parens)]
+
+use frame_support::weights::{Weight, constants::RocksDbWeight as DbWeight};
+
+impl crate::WeightInfo for () {
+	fn on_initialize(a: u32) -> Weight {
+		(0 as Weight)
+			.saturating_add((43_347_000 as Weight).saturating_mul(a as Weight))
+			.saturating_add(DbWeight::get().reads(5 as Weight))
+			.saturating_add(DbWeight::get().reads((7 as Weight).saturating_mul(a as Weight)))
+			.saturating_add(DbWeight::get().writes(2 as Weight))
+			.saturating_add(DbWeight::get().writes((7 as Weight).saturating_mul(a as Weight)))
+	}
+	fn supply() -> Weight {
+		(41_522_000 as Weight)
+			.saturating_add(DbWeight::get().reads(13 as Weight))
+			.saturating_add(DbWeight::get().writes(8 as Weight))
+	}
+	fn withdraw(u: u32) -> Weight {
+		(56_425_000 as Weight)
+			.saturating_add((14_217_000 as Weight).saturating_mul(u as Weight))
+			.saturating_add(DbWeight::get().reads(14 as Weight))
+			.saturating_add(DbWeight::get().reads((7 as Weight).saturating_mul(u as Weight)))
+			.saturating_add(DbWeight::get().writes(6 as Weight))
+	}
+	fn borrow(u: u32) -> Weight {
+		(22_591_000 as Weight)
+			.saturating_add((16_771_000 as Weight).saturating_mul(u as Weight))
+			.saturating_add(DbWeight::get().reads(11 as Weight))
+			.saturating_add(DbWeight::get().reads((7 as Weight).saturating_mul(u as Weight)))
+			.saturating_add(DbWeight::get().writes(4 as Weight))
+	}
+	fn borrow_stable(u: u32) -> Weight {
+		(100_635_000 as Weight)
+			.saturating_add((25_693_000 as Weight).saturating_mul(u as Weight))
+			.saturating_add(DbWeight::get().reads(11 as Weight))
+			.saturating_add(DbWeight::get().reads((7 as Weight).saturating_mul(u as Weight)))
+			.saturating_add(DbWeight::get().writes(4 as Weight))
+	}
+	fn borrow_on_behalf(u: u32) -> Weight {
+		(77_905_000 as Weight)
+			.saturating_add((29_001_000 as Weight).saturating_mul(u as Weight))
+			.saturating_add(DbWeight::get().reads(12 as Weight))
+			.saturating_add(DbWeight::get().reads((7 as Weight).saturating_mul(u as Weight)))
+			.saturating_add(DbWeight::get().writes(5 as Weight))
+	}
+	fn approve_delegation() -> Weight {
+		(11_021_000 as Weight)
+			.saturating_add(DbWeight::get().reads(1 as Weight))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	fn revoke_delegation() -> Weight {
+		(12_462_000 as Weight)
+			.saturating_add(DbWeight::get().reads(1 as Weight))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	fn repay() -> Weight {
+		(61_075_000 as Weight)
+			.saturating_add(DbWeight::get().reads(8 as Weight))
+			.saturating_add(DbWeight::get().writes(4 as Weight))
+	}
+	fn repay_on_behalf() -> Weight {
+		(62_966_000 as Weight)
+			.saturating_add(DbWeight::get().reads(8 as Weight))
+			.saturating_add(DbWeight::get().writes(4 as Weight))
+	}
+	fn repay_all() -> Weight {
+		(67_803_000 as Weight)
+			.saturating_add(DbWeight::get().reads(9 as Weight))
+			.saturating_add(DbWeight::get().writes(5 as Weight))
+	}
+	fn enable_collateral() -> Weight {
+		(19_210_000 as Weight)
+			.saturating_add(DbWeight::get().reads(3 as Weight))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	fn disable_collateral(u: u32) -> Weight {
+		(31_729_000 as Weight)
+			.saturating_add((14_228_000 as Weight).saturating_mul(u as Weight))
+			.saturating_add(DbWeight::get().reads(6 as Weight))
+			.saturating_add(DbWeight::get().reads((7 as Weight).saturating_mul(u as Weight)))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	fn set_user_emode(u: u32) -> Weight {
+		(6_283_000 as Weight)
+			.saturating_add((21_209_000 as Weight).saturating_mul(u as Weight))
+			.saturating_add(DbWeight::get().reads(6 as Weight))
+			.saturating_add(DbWeight::get().reads((8 as Weight).saturating_mul(u as Weight)))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	fn liquidate(u: u32) -> Weight {
+		(131_304_000 as Weight)
+			.saturating_add((19_024_000 as Weight).saturating_mul(u as Weight))
+			.saturating_add(DbWeight::get().reads(19 as Weight))
+			.saturating_add(DbWeight::get().reads((7 as Weight).saturating_mul(u as Weight)))
+			.saturating_add(DbWeight::get().writes(10 as Weight))
+	}
+	fn liquidate_batch(u: u32, p: u32) -> Weight {
+		(130_713_000 as Weight)
+			.saturating_add((26_566_000 as Weight).saturating_mul(u as Weight))
+			.saturating_add((116_110_000 as Weight).saturating_mul(p as Weight))
+			.saturating_add(DbWeight::get().reads((15 as Weight).saturating_mul(u as Weight)))
+			.saturating_add(DbWeight::get().reads((8 as Weight).saturating_mul(p as Weight)))
+			.saturating_add(DbWeight::get().writes((7 as Weight).saturating_mul(u as Weight)))
+			.saturating_add(DbWeight::get().writes((7 as Weight).saturating_mul(p as Weight)))
+	}
+	fn start_auction(u: u32) -> Weight {
+		(67_846_000 as Weight)
+			.saturating_add((17_375_000 as Weight).saturating_mul(u as Weight))
+			.saturating_add(DbWeight::get().reads(17 as Weight))
+			.saturating_add(DbWeight::get().reads((7 as Weight).saturating_mul(u as Weight)))
+			.saturating_add(DbWeight::get().writes(13 as Weight))
+	}
+	fn bid() -> Weight {
+		(55_692_000 as Weight)
+			.saturating_add(DbWeight::get().reads(14 as Weight))
+			.saturating_add(DbWeight::get().writes(7 as Weight))
+	}
+	fn rebalance_stable_rate() -> Weight {
+		(15_742_000 as Weight)
+			.saturating_add(DbWeight::get().reads(3 as Weight))
+			.saturating_add(DbWeight::get().writes(2 as Weight))
+	}
+	fn flash_loan() -> Weight {
+		(36_421_000 as Weight)
+			.saturating_add(DbWeight::get().reads(8 as Weight))
+			.saturating_add(DbWeight::get().writes(3 as Weight))
+	}
+	fn transfer_ktoken(u: u32) -> Weight {
+		(48_532_000 as Weight)
+			.saturating_add((15_660_000 as Weight).saturating_mul(u as Weight))
+			.saturating_add(DbWeight::get().reads(16 as Weight))
+			.saturating_add(DbWeight::get().reads((7 as Weight).saturating_mul(u as Weight)))
+			.saturating_add(DbWeight::get().writes(7 as Weight))
+	}
+	fn init_pool() -> Weight {
+		(6_293_000 as Weight)
+			.saturating_add(DbWeight::get().reads(4 as Weight))
+			.saturating_add(DbWeight::get().writes(4 as Weight))
+	}
+	fn set_interest_rate_model() -> Weight {
+		(7_325_000 as Weight)
+			.saturating_add(DbWeight::get().reads(2 as Weight))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	fn set_collateral_factor() -> Weight {
+		(7_957_000 as Weight)
+			.saturating_add(DbWeight::get().reads(1 as Weight))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	fn set_liquidation_threshold() -> Weight {
+		(8_002_000 as Weight)
+			.saturating_add(DbWeight::get().reads(1 as Weight))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	fn set_close_factor() -> Weight {
+		(7_949_000 as Weight)
+			.saturating_add(DbWeight::get().reads(1 as Weight))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	fn set_discount_factor() -> Weight {
+		(10_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(1 as Weight))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	fn set_can_be_collateral() -> Weight {
+		(6_505_000 as Weight)
+			.saturating_add(DbWeight::get().reads(1 as Weight))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	fn set_supply_cap() -> Weight {
+		(6_775_000 as Weight)
+			.saturating_add(DbWeight::get().reads(1 as Weight))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	fn set_borrow_cap() -> Weight {
+		(6_536_000 as Weight)
+			.saturating_add(DbWeight::get().reads(1 as Weight))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	fn set_stable_rate_config() -> Weight {
+		(6_685_000 as Weight)
+			.saturating_add(DbWeight::get().reads(1 as Weight))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	fn set_pool_enabled() -> Weight {
+		(6_541_000 as Weight)
+			.saturating_add(DbWeight::get().reads(1 as Weight))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	fn set_dust_threshold() -> Weight {
+		(3_693_000 as Weight)
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	fn write_off_bad_debt() -> Weight {
+		(20_500_000 as Weight)
+			.saturating_add(DbWeight::get().reads(7 as Weight))
+			.saturating_add(DbWeight::get().writes(3 as Weight))
+	}
+	fn set_pause_guardian() -> Weight {
+		(3_612_000 as Weight)
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	fn set_action_paused() -> Weight {
+		(7_698_000 as Weight)
+			.saturating_add(DbWeight::get().reads(2 as Weight))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	fn set_reserve_factor() -> Weight {
+		(7_297_000 as Weight)
+			.saturating_add(DbWeight::get().reads(2 as Weight))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	fn set_emode_category() -> Weight {
+		(4_059_000 as Weight)
+			.saturating_add(DbWeight::get().reads(1 as Weight))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	fn set_asset_emode_category() -> Weight {
+		(8_394_000 as Weight)
+			.saturating_add(DbWeight::get().reads(3 as Weight))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	fn set_auction_config() -> Weight {
+		(3_574_000 as Weight)
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	fn set_flash_loan_fee() -> Weight {
+		(3_603_000 as Weight)
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	fn set_max_price_age() -> Weight {
+		(7_443_000 as Weight)
+			.saturating_add(DbWeight::get().reads(2 as Weight))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	fn withdraw_reserves() -> Weight {
+		(20_668_000 as Weight)
+			.saturating_add(DbWeight::get().reads(6 as Weight))
+			.saturating_add(DbWeight::get().writes(3 as Weight))
+	}
+}
@@ -0,0 +1,138 @@
+use sp_runtime::{
+    FixedU128, FixedPointNumber, RuntimeDebug,
+    traits::Saturating,
+};
+use sp_std::vec::Vec;
+use codec::{Encode, Decode};
+
+/// Max number of points a `Curve` model can carry
+pub const MAX_CURVE_POINTS: usize = 16;
+
+/// The model that derives the debt interest rate (per second) from the utilization ratio of a pool
+#[derive(Clone, Eq, PartialEq, RuntimeDebug, Encode, Decode)]
+pub enum InterestRateModel {
+    /// rate = initial_interest_rate + utilization_factor * utilization
+    Linear {
+        /// Rate when nothing is borrowed
+        initial_interest_rate: FixedU128,
+        /// Slope of the line
+        utilization_factor: FixedU128,
+    },
+    /// rate = base_rate + multiplier * min(utilization, kink) + jump_multiplier * max(utilization - kink, 0)
+    JumpRate {
+        /// Rate when nothing is borrowed
+        base_rate: FixedU128,
+        /// Slope below the kink
+        multiplier: FixedU128,
+        /// Utilization where the slope changes, <=1
+        kink: FixedU128,
+        /// Slope above the kink
+        jump_multiplier: FixedU128,
+    },
+    /// Piecewise linear curve through `(utilization, rate)` points, sorted by utilization.
+    /// The rate is flat before the first point and after the last one.
+    Curve(Vec<(FixedU128, FixedU128)>),
+}
+
+impl Default for InterestRateModel {
+    fn default() -> Self {
+        // the former per block defaults spread over 6 seconds blocks
+        InterestRateModel::Linear {
+            initial_interest_rate: FixedU128::saturating_from_rational(385, 600000000000u64),
+            utilization_factor: FixedU128::saturating_from_rational(385, 60000000000u64),
+        }
+    }
+}
+
+impl InterestRateModel {
+    /// Check the parameters make sense before the model is stored
+    pub fn is_valid(&self) -> bool {
+        match self {
+            InterestRateModel::Linear { .. } => true,
+            InterestRateModel::JumpRate { kink, .. } => *kink <= FixedU128::one(),
+            InterestRateModel::Curve(points) => {
+                !points.is_empty()
+                    && points.len() <= MAX_CURVE_POINTS
+                    // utilization strictly increasing and rate non-decreasing
+                    && points.windows(2).all(|w| w[0].0 < w[1].0 && w[0].1 <= w[1].1)
+            }
+        }
+    }
+
+    /// The same model with every rate multiplied by `factor`, e.g. to change the rate period
+    pub fn scale_rates(&self, factor: FixedU128) -> Self {
+        match self {
+            InterestRateModel::Linear { initial_interest_rate, utilization_factor } => InterestRateModel::Linear {
+                initial_interest_rate: initial_interest_rate.saturating_mul(factor),
+                utilization_factor: utilization_factor.saturating_mul(factor),
+            },
+            InterestRateModel::JumpRate { base_rate, multiplier, kink, jump_multiplier } => InterestRateModel::JumpRate {
+                base_rate: base_rate.saturating_mul(factor),
+                multiplier: multiplier.saturating_mul(factor),
+                kink: *kink,
+                jump_multiplier: jump_multiplier.saturating_mul(factor),
+            },
+            InterestRateModel::Curve(points) => InterestRateModel::Curve(
+                points.iter().map(|(utilization, rate)| (*utilization, rate.saturating_mul(factor))).collect()
+            ),
+        }
+    }
+
+    /// Debt interest rate at a given utilization ratio
+    pub fn debt_rate(&self, utilization: FixedU128) -> FixedU128 {
+        match self {
+            InterestRateModel::Linear { initial_interest_rate, utilization_factor } => {
+                initial_interest_rate.saturating_add(utilization_factor.saturating_mul(utilization))
+            }
+            InterestRateModel::JumpRate { base_rate, multiplier, kink, jump_multiplier } => {
+                if utilization <= *kink {
+                    base_rate.saturating_add(multiplier.saturating_mul(utilization))
+                } else {
+                    let excess = utilization.saturating_sub(*kink);
+                    base_rate
+                        .saturating_add(multiplier.saturating_mul(*kink))
+                        .saturating_add(jump_multiplier.saturating_mul(excess))
+                }
+            }
+            InterestRateModel::Curve(points) => {
+                let (first, last) = match (points.first(), points.last()) {
+                    (Some(first), Some(last)) => (first, last),
+                    _ => return FixedU128::zero(),
+                };
+                if utilization <= first.0 {
+                    return first.1
+                }
+                if utilization >= last.0 {
+                    return last.1
+                }
+                for w in points.windows(2) {
+                    let ((u0, r0), (u1, r1)) = (w[0], w[1]);
+                    if utilization <= u1 {
+                        // r0 + (r1 - r0) * (utilization - u0) / (u1 - u0)
+                        let progress = (utilization - u0) / (u1 - u0);
+                        return r0.saturating_add((r1 - r0).saturating_mul(progress))
+                    }
+                }
+                last.1
+            }
+        }
+    }
+}
+
+/// Multiplier of `n` periods compounding at a per period `rate`: (1 + rate) ^ n,
+/// computed by exponentiation by squaring
+pub fn compound(rate: FixedU128, n: u32) -> FixedU128 {
+    let mut base = FixedU128::one().saturating_add(rate);
+    let mut exp = n;
+    let mut result = FixedU128::one();
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = result.saturating_mul(base);
+        }
+        exp >>= 1;
+        if exp > 0 {
+            base = base.saturating_mul(base);
+        }
+    }
+    result
+}
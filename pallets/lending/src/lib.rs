@@ -1,22 +1,40 @@
 #![cfg_attr(not(feature = "std"), no_std)]
+// `decl_module!` of this pallet needs a higher recursion limit.
+#![recursion_limit="256"]
 
 use frame_support::{
     debug,
-    decl_event, decl_module, decl_storage, decl_error, ensure,
-    StorageMap, Parameter,
+    decl_event, decl_module, decl_storage, decl_error, ensure, transactional,
+    StorageMap, StorageValue, Parameter,
+    dispatch::{Dispatchable, PostDispatchInfo},
+    traits::UnixTime,
+    weights::{GetDispatchInfo, Weight},
 };
 use sp_runtime::{
     FixedU128, FixedPointNumber, FixedPointOperand,
-    DispatchResult as Result, RuntimeDebug, ModuleId,
+    DispatchResult as Result, DispatchError, RuntimeDebug, ModuleId, SaturatedConversion,
+    helpers_128bit::multiply_by_rational,
     traits::{
-        Member, AtLeast32BitUnsigned, AccountIdConversion, Zero
+        Member, AtLeast32BitUnsigned, AccountIdConversion, Bounded, Saturating, Zero
     }, 
 };
-use frame_system::{self as system, ensure_signed};
+use frame_system::{self as system, ensure_signed, ensure_root};
 use sp_std::prelude::*;
-use sp_std::{vec::Vec, convert::TryInto};
+use sp_std::vec::Vec;
 use codec::{Encode, Decode};
-use traits::{Oracle, MultiAsset};
+use traits::{Oracle, MultiAsset, MintableMultiAsset, OnAssetTransfer};
+use pallet_lending_rpc_runtime_api::{AssetPosition, UserPosition};
+
+mod interest_rate;
+pub use interest_rate::InterestRateModel;
+
+mod pool_set;
+pub use pool_set::{PoolIndex, PoolSet, MAX_POOLS};
+
+pub mod migrations;
+
+mod benchmarking;
+mod default_weights;
 
 #[cfg(test)]
 mod mock;
@@ -26,6 +44,61 @@ mod tests;
 
 const PALLET_ID: ModuleId = ModuleId(*b"Lending!");
 
+/// Max assets in each of a user's supply and debt sets, which bounds the calls checking the position
+pub const MAX_USER_ASSETS: u32 = 16;
+
+/// Max pay and seize pairs in a batch liquidation, so that its weight fits in a block
+pub const MAX_BATCH_LIQUIDATIONS: u32 = 6;
+
+/// Max expired auctions closed at the start of a block, the rest are closed in the next blocks
+pub const MAX_AUCTION_CLOSES: u32 = 10;
+
+pub trait WeightInfo {
+    fn on_initialize(a: u32) -> Weight;
+    fn supply() -> Weight;
+    fn withdraw(u: u32) -> Weight;
+    fn borrow(u: u32) -> Weight;
+    fn borrow_stable(u: u32) -> Weight;
+    fn borrow_on_behalf(u: u32) -> Weight;
+    fn approve_delegation() -> Weight;
+    fn revoke_delegation() -> Weight;
+    fn repay() -> Weight;
+    fn repay_on_behalf() -> Weight;
+    fn repay_all() -> Weight;
+    fn enable_collateral() -> Weight;
+    fn disable_collateral(u: u32) -> Weight;
+    fn set_user_emode(u: u32) -> Weight;
+    fn liquidate(u: u32) -> Weight;
+    fn liquidate_batch(u: u32, p: u32) -> Weight;
+    fn start_auction(u: u32) -> Weight;
+    fn bid() -> Weight;
+    fn rebalance_stable_rate() -> Weight;
+    fn flash_loan() -> Weight;
+    fn transfer_ktoken(u: u32) -> Weight;
+    fn init_pool() -> Weight;
+    fn set_interest_rate_model() -> Weight;
+    fn set_collateral_factor() -> Weight;
+    fn set_liquidation_threshold() -> Weight;
+    fn set_close_factor() -> Weight;
+    fn set_discount_factor() -> Weight;
+    fn set_can_be_collateral() -> Weight;
+    fn set_supply_cap() -> Weight;
+    fn set_borrow_cap() -> Weight;
+    fn set_stable_rate_config() -> Weight;
+    fn set_pool_enabled() -> Weight;
+    fn set_dust_threshold() -> Weight;
+    fn write_off_bad_debt() -> Weight;
+    fn set_pause_guardian() -> Weight;
+    fn set_action_paused() -> Weight;
+    fn set_reserve_factor() -> Weight;
+    fn set_emode_category() -> Weight;
+    fn set_asset_emode_category() -> Weight;
+    fn set_auction_config() -> Weight;
+    fn set_flash_loan_fee() -> Weight;
+    fn set_max_price_age() -> Weight;
+    fn withdraw_reserves() -> Weight;
+}
+
 /// The module's configuration trait.
 pub trait Trait: frame_system::Trait {
     /// The units in which we record balances.
@@ -36,14 +109,21 @@ pub trait Trait: frame_system::Trait {
     type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
     /// Price oracle for assets.
     type Oracle: Oracle<Self::AssetId, FixedU128>;
-    /// Multiple transferrable assets.
-    type MultiAsset: MultiAsset<Self::AccountId, Self::AssetId, Self::Balance>;
+    /// Multiple transferrable assets, which kTokens are minted in.
+    type MultiAsset: MultiAsset<Self::AccountId, Self::AssetId, Self::Balance>
+        + MintableMultiAsset<Self::AccountId, Self::AssetId, Self::Balance>;
+    /// Time used to accrue interest.
+    type UnixTime: UnixTime;
+    /// The overarching call type, dispatched by flash loans.
+    type Call: Parameter + Dispatchable<Origin=Self::Origin, PostInfo=PostDispatchInfo> + GetDispatchInfo;
+    /// Weight information for extrinsics in this pallet.
+    type WeightInfo: WeightInfo;
 }
 
 /// Pool information
 #[derive(Clone, Eq, PartialEq, RuntimeDebug, Encode, Decode)]
 pub struct Pool<T: Trait> {
-    /// If the pool is enabled
+    /// If the pool is enabled, a disabled pool accepts no new supply or borrow
     pub enabled: bool,
     /// If the asset can be enabled as collateral
     pub can_be_collateral: bool,
@@ -53,8 +133,10 @@ pub struct Pool<T: Trait> {
 	pub supply: T::Balance,
     /// Total debt of the pool
     pub debt: T::Balance,
-    /// A discount factor for an asset that reduces the its limit
-    pub safe_factor: FixedU128,
+    /// Share of the asset value that can be borrowed against, <=liquidation_threshold
+    pub collateral_factor: FixedU128,
+    /// Share of the asset value that debt can reach before liquidation, <=1
+    pub liquidation_threshold: FixedU128,
     /// Factor that determines what percentage one arbitrage can seize, <=1
     pub close_factor: FixedU128,
     /// The bonus arbitrager can get when triggering a liquidation
@@ -63,13 +145,130 @@ pub struct Pool<T: Trait> {
     pub total_supply_index: FixedU128,
     /// Effective index of current total debt
     pub total_debt_index: FixedU128,
-    /// The latest timestamp that the pool has accrued interest
-    pub last_updated: T::BlockNumber,
-    /// One factor of the linear interest model
-    pub utilization_factor: FixedU128,
-    /// Another factor of the linear interest model
-    pub initial_interest_rate: FixedU128,
-    
+    /// The latest timestamp (in seconds) that the pool has accrued interest
+    pub last_updated: u64,
+    /// The model deriving debt interest rate from utilization
+    pub interest_rate_model: InterestRateModel,
+    /// Share of debt interest that goes to protocol reserves, <=1
+    pub reserve_factor: FixedU128,
+    /// Protocol reserves of the pool
+    pub reserves: T::Balance,
+    /// Max total supply of the pool, no limit if none
+    pub supply_cap: Option<T::Balance>,
+    /// Max total debt of the pool, no limit if none
+    pub borrow_cap: Option<T::Balance>,
+    /// Part of the debt borrowed at stable rates
+    pub stable_debt: T::Balance,
+    /// Average rate of the stable debt, weighted by amount
+    pub average_stable_rate: FixedU128,
+    /// Parameters of stable rate borrowing, disabled if none
+    pub stable_rate_config: Option<StableRateConfig>,
+}
+
+/// Parameters of stable rate borrowing of a pool
+#[derive(Clone, Eq, PartialEq, RuntimeDebug, Encode, Decode)]
+pub struct StableRateConfig {
+    /// Added to the variable rate to get the rate a stable borrow locks
+    pub premium: FixedU128,
+    /// Stable debt can be rebalanced once the variable rate is this much above the average stable rate
+    pub rebalance_spread: FixedU128,
+}
+
+/// Storage layout versions of this pallet
+#[derive(Clone, Copy, Eq, PartialEq, RuntimeDebug, Encode, Decode, Default)]
+pub enum Releases {
+    /// Linear interest factors in each pool
+    #[default]
+    V0,
+    /// Interest rate model per pool, interest accrued by block number
+    V1,
+    /// Reserve factor and reserves per pool
+    V2,
+    /// Supply and borrow caps per pool
+    V3,
+    /// Collateral chosen per user
+    V4,
+    /// Interest accrued by timestamp
+    V5,
+    /// Collateral factor and liquidation threshold per pool
+    V6,
+    /// Supply positions backed by kTokens
+    V7,
+    /// Stable rate debt
+    V8,
+    /// User asset sets as bit sets of pool indices
+    V9,
+}
+
+/// User actions on a pool that can be paused
+#[derive(Clone, Copy, Eq, PartialEq, RuntimeDebug, Encode, Decode)]
+pub enum PoolAction {
+    Supply,
+    Withdraw,
+    Borrow,
+    Repay,
+    Liquidate,
+    FlashLoan,
+}
+
+/// Identifier of an efficiency mode category
+pub type EModeCategoryId = u8;
+
+/// Risk parameters of an efficiency mode category of correlated assets, e.g. stablecoins.
+/// They apply to users who opt in, whose debt must then all be in the category.
+#[derive(Clone, Eq, PartialEq, RuntimeDebug, Encode, Decode)]
+pub struct EModeCategory {
+    /// Replaces the collateral factor of the pools in the category, <=liquidation_threshold
+    pub collateral_factor: FixedU128,
+    /// Replaces the liquidation threshold of the pools in the category, <=1
+    pub liquidation_threshold: FixedU128,
+}
+
+/// Identifier of a liquidation auction
+pub type AuctionId = u32;
+
+/// Parameters of Dutch auction liquidations, prices are relative to the oracle price at the start
+#[derive(Clone, Eq, PartialEq, RuntimeDebug, Encode, Decode)]
+pub struct AuctionConfig {
+    /// Seconds for the price to descend from start to end, the auction expires afterwards
+    pub duration: u64,
+    /// Share of the oracle price the auction starts at, >=end_factor
+    pub start_factor: FixedU128,
+    /// Share of the oracle price the auction ends at, >0
+    pub end_factor: FixedU128,
+}
+
+impl Default for AuctionConfig {
+    fn default() -> Self {
+        AuctionConfig {
+            duration: 1800,
+            start_factor: FixedU128::saturating_from_rational(110, 100),
+            end_factor: FixedU128::saturating_from_rational(80, 100),
+        }
+    }
+}
+
+/// A descending price auction of the collateral of an unhealthy position
+#[derive(Clone, Eq, PartialEq, RuntimeDebug, Encode, Decode)]
+pub struct Auction<T: Trait> {
+    /// The user whose collateral is sold
+    pub target_user: T::AccountId,
+    /// The debt asset bids repay
+    pub pay_asset_id: T::AssetId,
+    /// The collateral asset sold
+    pub get_asset_id: T::AssetId,
+    /// Collateral left to sell, held by the pallet
+    pub collateral: T::Balance,
+    /// Debt left to repay
+    pub debt: T::Balance,
+    /// Price of the collateral in the debt asset at the start
+    pub start_price: FixedU128,
+    /// Price of the collateral in the debt asset at the end
+    pub end_price: FixedU128,
+    /// Timestamp (in seconds) of the start
+    pub started_at: u64,
+    /// Timestamp (in seconds) when the auction expires
+    pub ends_at: u64,
 }
 
 /// User supply information of a given pool
@@ -86,8 +285,12 @@ pub struct UserSupply<T: Trait> {
 pub struct UserDebt<T: Trait> {
 	/// Debt amount at index
 	pub amount: T::Balance,
-	/// Debt index
+	/// Debt index, variable debt grows with the pool's
 	pub index: FixedU128,
+	/// Rate locked at borrow, none for variable debt
+	pub stable_rate: Option<FixedU128>,
+	/// The latest timestamp (in seconds) that the debt has accrued interest
+	pub last_updated: u64,
 }
 
 decl_event!(
@@ -101,10 +304,69 @@ decl_event!(
         Withdrawn(AssetId, AccountId, Balance),
         /// Some asset borrow from a pool \[asset_id, user, amount\]
         Borrowed(AssetId, AccountId, Balance),
+        /// Some asset borrowed against the collateral of a delegator \[asset_id, delegator, delegatee, amount\]
+        BorrowedOnBehalf(AssetId, AccountId, AccountId, Balance),
+        /// A delegatee allowed to borrow against the collateral of a delegator \[asset_id, delegator, delegatee, amount\]
+        DelegationApproved(AssetId, AccountId, AccountId, Balance),
+        /// A delegatee no longer allowed to borrow against the collateral of a delegator \[asset_id, delegator, delegatee\]
+        DelegationRevoked(AssetId, AccountId, AccountId),
+        /// A stable rate locked by a borrow or a rebalance \[asset_id, user, rate\]
+        StableRateLocked(AssetId, AccountId, FixedU128),
         /// Some asset repaid to a pool \[asset_id, user, amount\]
         Repaid(AssetId, AccountId, Balance),
+        /// Some asset repaid to a pool for another user \[asset_id, payer, user, amount\]
+        RepaidOnBehalf(AssetId, AccountId, AccountId, Balance),
         /// Some asset liquidated \[pay_asset_id, seized_asset_id, arbitrager, target, amount_pay_asset, amount_seized_asset\]
         Liquidated(AssetId, AssetId, AccountId, AccountId, Balance, Balance),
+        /// Some assets liquidated in a batch \[arbitrager, target, \[(pay_asset_id, seized_asset_id, amount_pay_asset, amount_seized_asset)\]\]
+        BatchLiquidated(AccountId, AccountId, Vec<(AssetId, AssetId, Balance, Balance)>),
+        /// A pool created with its kToken \[asset_id, pool_index, ktoken_id\]
+        PoolCreated(AssetId, PoolIndex, AssetId),
+        /// Interest accrued to a pool, or its indices and totals otherwise changed
+        /// \[asset_id, supply_index, debt_index, supply, debt\]
+        InterestAccrued(AssetId, FixedU128, FixedU128, Balance, Balance),
+        /// A user's position in a pool changed, amounts are at the indices and a stable debt
+        /// grows at its rate from the block of the event
+        /// \[asset_id, user, supply, supply_index, debt, debt_index, stable_rate\]
+        UserPositionUpdated(AssetId, AccountId, Balance, FixedU128, Balance, FixedU128, Option<FixedU128>),
+        /// Interest rate model of a pool changed \[asset_id\]
+        InterestRateModelUpdated(AssetId),
+        /// Risk parameters of a pool changed \[asset_id\]
+        PoolParametersUpdated(AssetId),
+        /// Protocol reserves withdrawn from a pool \[asset_id, to, amount\]
+        ReservesWithdrawn(AssetId, AccountId, Balance),
+        /// The pause guardian changed \[guardian\]
+        PauseGuardianUpdated(Option<AccountId>),
+        /// An action on a pool is paused or unpaused \[asset_id, action, paused\]
+        ActionPausedUpdated(AssetId, PoolAction, bool),
+        /// User enabled a supplied asset as collateral \[asset_id, user\]
+        CollateralEnabled(AssetId, AccountId),
+        /// User disabled a supplied asset as collateral \[asset_id, user\]
+        CollateralDisabled(AssetId, AccountId),
+        /// The dust threshold of total liquidation changed \[dust_threshold\]
+        DustThresholdUpdated(Balance),
+        /// Debt without collateral written off \[asset_id, user, amount_from_reserves, amount_socialized\]
+        BadDebtWrittenOff(AssetId, AccountId, Balance, Balance),
+        /// A flash loan repaid with its fee \[asset_id, user, amount, fee\]
+        FlashLoaned(AssetId, AccountId, Balance, Balance),
+        /// The flash loan fee changed \[fee\]
+        FlashLoanFeeUpdated(FixedU128),
+        /// An efficiency mode category is set or removed \[category_id\]
+        EModeCategoryUpdated(EModeCategoryId),
+        /// The efficiency mode category of an asset changed \[asset_id, category_id\]
+        AssetEModeCategoryUpdated(AssetId, Option<EModeCategoryId>),
+        /// User entered or left an efficiency mode category \[user, category_id\]
+        UserEModeUpdated(AccountId, Option<EModeCategoryId>),
+        /// Collateral put up in an auction \[auction_id, target, pay_asset_id, get_asset_id, amount_collateral\]
+        AuctionStarted(AuctionId, AccountId, AssetId, AssetId, Balance),
+        /// Collateral bought in an auction \[auction_id, bidder, amount_pay_asset, amount_get_asset\]
+        AuctionBid(AuctionId, AccountId, Balance, Balance),
+        /// An auction ended, the unsold collateral went back to the target \[auction_id, amount_returned\]
+        AuctionClosed(AuctionId, Balance),
+        /// Parameters of liquidation auctions changed
+        AuctionConfigUpdated,
+        /// The max age of the oracle price of an asset changed \[asset_id, max_age\]
+        MaxPriceAgeUpdated(AssetId, Option<u64>),
     }
 );
 
@@ -121,13 +383,58 @@ decl_storage! {
             => Option<UserSupply<T>>;
         /// Information of all existing pools
         pub Pools get(fn pool): map hasher(twox_64_concat) T::AssetId => Option<Pool<T>>;
-        // TODO: use bit set
+        /// Index of each pool, its bit in the user asset sets
+        pub PoolIndices get(fn pool_index): map hasher(twox_64_concat) T::AssetId => Option<PoolIndex>;
+        /// The pool asset at each index
+        pub PoolAssets get(fn pool_asset): map hasher(twox_64_concat) PoolIndex => Option<T::AssetId>;
+        /// Number of pools created, the index of the next one
+        pub PoolCount get(fn pool_count): PoolIndex;
         /// The set of user's supply
-        pub UserSupplySet get(fn user_supply_set): map hasher(blake2_128_concat) T::AccountId => Vec<T::AssetId>;
+        pub UserSupplySet get(fn user_supply_set): map hasher(blake2_128_concat) T::AccountId => PoolSet;
         /// The set of user's debt
-        pub UserDebtSet get(fn user_debt_set): map hasher(blake2_128_concat) T::AccountId => Vec<T::AssetId>;
-        /// The threshold of liquidation
-        pub LiquidationThreshold get(fn get_liquidation_threshold): FixedU128 = FixedU128::one();
+        pub UserDebtSet get(fn user_debt_set): map hasher(blake2_128_concat) T::AccountId => PoolSet;
+        /// The set of user's supply enabled as collateral
+        pub UserCollateralSet get(fn user_collateral_set): map hasher(blake2_128_concat) T::AccountId => PoolSet;
+        /// Collateral worth less than this can be liquidated in total, regardless of close factor
+        pub DustThreshold get(fn dust_threshold): T::Balance;
+        /// Storage layout version, for runtime upgrades
+        pub StorageVersion get(fn storage_version) build(|_| Releases::V9): Releases;
+        /// The account that can pause actions on pools
+        pub PauseGuardian get(fn pause_guardian): Option<T::AccountId>;
+        /// If an action on a pool is paused
+        pub PausedActions get(fn is_paused): double_map
+            hasher(twox_64_concat) T::AssetId, hasher(twox_64_concat) PoolAction
+            => bool;
+        /// Share of a flash loan charged as fee, which goes to suppliers
+        pub FlashLoanFee get(fn flash_loan_fee): FixedU128;
+        /// Efficiency mode categories defined by governance
+        pub EModeCategories get(fn emode_category): map hasher(twox_64_concat) EModeCategoryId => Option<EModeCategory>;
+        /// The efficiency mode category of an asset
+        pub AssetEModeCategory get(fn asset_emode_category): map hasher(twox_64_concat) T::AssetId => Option<EModeCategoryId>;
+        /// The efficiency mode category a user opted in
+        pub UserEModeCategory get(fn user_emode_category): map hasher(blake2_128_concat) T::AccountId => Option<EModeCategoryId>;
+        /// Parameters of liquidation auctions
+        pub AuctionParameters get(fn auction_config): AuctionConfig;
+        /// Id of the next liquidation auction
+        pub NextAuctionId get(fn next_auction_id): AuctionId;
+        /// Running liquidation auctions
+        pub Auctions get(fn auction): map hasher(twox_64_concat) AuctionId => Option<Auction<T>>;
+        /// Running auctions by the timestamp (in seconds) they expire at
+        pub AuctionExpiries get(fn auction_expiries): map hasher(twox_64_concat) u64 => Vec<AuctionId>;
+        /// The timestamps running auctions expire at, in ascending order
+        pub AuctionExpiryTimes get(fn auction_expiry_times): Vec<u64>;
+        /// The running auction of a user's collateral
+        pub UserAuction get(fn user_auction): map hasher(blake2_128_concat) T::AccountId => Option<AuctionId>;
+        /// The receipt asset (kToken) of a pool's supply
+        pub KTokens get(fn ktoken): map hasher(twox_64_concat) T::AssetId => Option<T::AssetId>;
+        /// The pool asset of a kToken
+        pub KTokenUnderlying get(fn ktoken_underlying): map hasher(twox_64_concat) T::AssetId => Option<T::AssetId>;
+        /// How much a delegatee can still borrow of an asset against the collateral of a delegator
+        pub BorrowAllowances get(fn borrow_allowance): double_map
+            hasher(blake2_128_concat) T::AccountId, hasher(blake2_128_concat) (T::AccountId, T::AssetId)
+            => T::Balance;
+        /// Max age in seconds of the oracle price of an asset valuing positions, no limit if none
+        pub MaxPriceAge get(fn max_price_age): map hasher(twox_64_concat) T::AssetId => Option<u64>;
     }
 
     add_extra_genesis {
@@ -135,7 +442,7 @@ decl_storage! {
 
         build(|config: &GenesisConfig<T>| {
             for pool in config.pools.iter() {
-                <Module<T>>::_init_pool(*pool, true);
+                <Module<T>>::_init_pool(*pool, true, InterestRateModel::default());
             }
         })
     }
@@ -144,8 +451,6 @@ decl_storage! {
 
 decl_error! {
 	pub enum Error for Module<T: Trait> {
-        /// Asset transfer failed
-        TransferFailed,
         /// Over borrowed and no liquidity for withdraw or borrow
         NotEnoughLiquidity,
         /// Pool is not established yet
@@ -154,12 +459,74 @@ decl_error! {
         AssetNotCollateral,
         /// Try to liquidate while still above liquidation threshold
         AboveLiquidationThreshold,
-        /// Any actions resulting not enough collaterals
-        BelowLiquidationThreshold,
+        /// Any actions resulting debt over the collateral factors
+        BelowCollateralFactor,
         /// User have no supply yet
         UserNoSupply,
         /// User have no debt yet
         UserNoDebt, 
+        /// Parameters of the interest rate model are not valid
+        InvalidInterestRateModel,
+        /// Pool is already established
+        PoolAlreadyExists,
+        /// A risk parameter is out of its allowed range
+        ParameterOutOfBounds,
+        /// Not enough reserves in the pool
+        NotEnoughReserves,
+        /// Supply would exceed the supply cap of the pool
+        SupplyCapExceeded,
+        /// Borrow would exceed the borrow cap of the pool
+        BorrowCapExceeded,
+        /// Pool is disabled
+        PoolDisabled,
+        /// The action is paused on this pool
+        ActionPaused,
+        /// Only governance or the pause guardian can pause, only governance can unpause
+        NotPauseGuardian,
+        /// User still has collateral, debt is not bad yet
+        UserHasCollateral,
+        /// User still has supply that can be seized, debt is not bad yet
+        UserHasSupply,
+        /// Reserves and supply of the pool together can not absorb the bad debt
+        BadDebtExceedsSupply,
+        /// Flash loan plus fee is not paid back by the end of the call
+        FlashLoanNotRepaid,
+        /// Efficiency mode category is not defined
+        EModeCategoryNotExist,
+        /// The asset is not in the efficiency mode category of the user
+        AssetNotInEModeCategory,
+        /// Auction does not exist or has ended
+        AuctionNotExist,
+        /// Auction is past its end and waits to be closed
+        AuctionExpired,
+        /// The user's collateral is already in an auction
+        AuctionInProgress,
+        /// kTokens can not have pools of their own
+        PoolOfKToken,
+        /// kTokens are only burnt by withdrawing the supply they stand for
+        KTokenNotDestroyable,
+        /// Stable rate borrowing is not enabled on the pool
+        StableBorrowDisabled,
+        /// A user's debt of an asset is either all at a stable rate or all at the variable rate
+        RateModeMismatch,
+        /// Borrow exceeds the allowance approved by the delegator
+        DelegationExceeded,
+        /// No allowance approved for the delegatee
+        DelegationNotExist,
+        /// The variable rate is not far enough above the average stable rate, or the user's rate is not below the current one
+        RebalanceNotAllowed,
+        /// User already supplies or borrows the max number of assets
+        TooManyAssets,
+        /// No index left for a new pool
+        TooManyPools,
+        /// The oracle has no price for an asset, or a zero price
+        PriceMissing,
+        /// The oracle price of an asset is older than its max age
+        PriceStale,
+        /// A batch liquidation has more pairs than the max
+        TooManyLiquidations,
+        /// A batch liquidation repays no debt
+        NothingToLiquidate,
 	}
 }
 
@@ -171,228 +538,254 @@ decl_module! {
         // this is needed only if you are using events in your module
         fn deposit_event() = default;
 
+        /// Close the expired liquidation auctions
+        fn on_initialize(_n: T::BlockNumber) -> Weight {
+            let closed = Self::close_expired_auctions(Self::now());
+            T::WeightInfo::on_initialize(closed)
+        }
+
         // end user related
  
         /// Supply an asset to the pool
 		///
 		/// - `asset_id`: The asset that user wants to supply
 		/// - `amount`: The amount that user wants to supply
-        #[weight = 1]
+        #[weight = T::WeightInfo::supply()]
+        #[transactional]
         fn supply(
             origin,
             asset_id: T::AssetId,
             amount: T::Balance) -> Result {
-            debug::info!("Entering supply");
-            let account = ensure_signed(origin)?;
-
-            // check pool exists and get pool instance
-            let mut pool = Self::pool(asset_id).ok_or(Error::<T>::PoolNotExist)?;
-            // accrue pool interest
-            Self::accrue_interest(&mut pool);
-            // transfer asset
-            T::MultiAsset::transfer(
-                account.clone(),
-                asset_id,
-                Self::account_id(),
-                amount,
-            ).map_err(|_| Error::<T>::TransferFailed)?;
-
-            // update user supply
-            Self::update_user_supply(&pool, asset_id, account.clone(), amount, true);
-            // update pool supply
-            Self::update_pool_supply(&mut pool, amount, true);
-
-            Self::deposit_event(RawEvent::Supplied(asset_id, account.clone(), amount));
-
-            // update user's supply asset set
-            let mut assets = Self::user_supply_set(account.clone());
-            if !assets.iter().any(|x| *x == asset_id) {
-                assets.push(asset_id);
-                UserSupplySet::<T>::insert(account, assets);
-            }
-
-            // commit pool change to storage
-            Pools::<T>::insert(asset_id, pool);
-
-            debug::info!("Leaving supply");
-            Ok(())
+            Self::do_supply(origin, asset_id, amount)
         }
 
         /// Withdraw an asset from the pool
 		///
 		/// - `asset_id`: The asset that user wants to withdraw
 		/// - `amount`: The amount that user wants to withdraw
-        #[weight = 1]
+        #[weight = T::WeightInfo::withdraw(MAX_USER_ASSETS)]
+        #[transactional]
         fn withdraw(
             origin,
             asset_id: T::AssetId,
             amount: T::Balance) -> Result {
-            debug::info!("Entering withdraw");
+            Self::do_withdraw(origin, asset_id, amount)
+        }
 
+        /// Borrow an asset from the pool at the variable rate
+		///
+		/// - `asset_id`: The asset that user wants to borrow
+		/// - `amount`: The amount that user wants to borrow
+        #[weight = T::WeightInfo::borrow(MAX_USER_ASSETS)]
+        fn borrow(
+            origin,
+            asset_id: T::AssetId,
+            amount: T::Balance) -> Result {
             let account = ensure_signed(origin)?;
 
-            // check pool exists and get pool instance
-            let mut pool = Self::pool(asset_id).ok_or(Error::<T>::PoolNotExist)?;
-            // accrue pool interest
-            Self::accrue_interest(&mut pool);
+            Self::do_borrow(account.clone(), account, asset_id, amount, false)
+        }
+
+        /// Borrow an asset from the pool at a stable rate, the variable rate after the borrow plus the premium of the pool.
+        /// Borrowing more averages the locked rate.
+		///
+		/// - `asset_id`: The asset that user wants to borrow
+		/// - `amount`: The amount that user wants to borrow
+        #[weight = T::WeightInfo::borrow_stable(MAX_USER_ASSETS)]
+        fn borrow_stable(
+            origin,
+            asset_id: T::AssetId,
+            amount: T::Balance) -> Result {
+            let account = ensure_signed(origin)?;
 
-            // accrue user's interest
-            Self::accrue_supply_with_interest(&pool, asset_id, account.clone());
+            Self::do_borrow(account.clone(), account, asset_id, amount, true)
+        }
 
-            // pre-check amount
-            // supply can not be zero (if so it will be eliminated)
-            let mut amount = amount;
-            if let Some(user_supply) = Self::user_supply(asset_id, account.clone()) {
-                if user_supply.amount < amount {
-                    amount = user_supply.amount;
-                }
-            } else {
-                Err(Error::<T>::UserNoSupply)?
-            }
+        /// Borrow at the variable rate against the collateral of a delegator, within the allowance it approved.
+        /// The debt is the delegator's, the funds go to the caller.
+		///
+		/// - `delegator`: The user whose collateral backs the debt
+		/// - `asset_id`: The asset to borrow
+		/// - `amount`: The amount to borrow
+        #[weight = T::WeightInfo::borrow_on_behalf(MAX_USER_ASSETS)]
+        fn borrow_on_behalf(
+            origin,
+            delegator: T::AccountId,
+            asset_id: T::AssetId,
+            amount: T::Balance) -> Result {
+            let account = ensure_signed(origin)?;
 
-            // check collateral 
-            let (_, converted_supply, converted_borrow) = Self::get_user_info(account.clone());
-            let price = T::Oracle::get_rate(asset_id);
-            let converted_supply = converted_supply - (price * pool.safe_factor).saturating_mul_int(amount);
-            ensure!(Self::get_liquidation_threshold().saturating_mul_int(converted_borrow) <= converted_supply, Error::<T>::BelowLiquidationThreshold);
+            let allowance = Self::borrow_allowance(delegator.clone(), (account.clone(), asset_id));
+            ensure!(amount <= allowance, Error::<T>::DelegationExceeded);
 
-            // check pool cash = (deposit - borrow) > amount
-            if (pool.supply - pool.debt) < amount {
-                Err(Error::<T>::NotEnoughLiquidity)?
-            }
+            Self::do_borrow(delegator.clone(), account.clone(), asset_id, amount, false)?;
+            BorrowAllowances::<T>::insert(delegator.clone(), (account.clone(), asset_id), allowance - amount);
+            Self::deposit_event(RawEvent::BorrowedOnBehalf(asset_id, delegator, account, amount));
 
-            // transfer asset to user
-            T::MultiAsset::transfer(
-                Self::account_id(),
-                asset_id,
-                account.clone(),
-                amount,
-            ).map_err(|_| Error::<T>::TransferFailed)?;
+            Ok(())
+        }
+
+        /// Allow another account to borrow an asset against the caller's collateral, the debt is the caller's.
+        /// Replaces the allowance approved before.
+		///
+		/// - `delegatee`: The account that can borrow
+		/// - `asset_id`: The asset it can borrow
+		/// - `amount`: The most it can borrow
+        #[weight = T::WeightInfo::approve_delegation()]
+        fn approve_delegation(origin, delegatee: T::AccountId, asset_id: T::AssetId, amount: T::Balance) -> Result {
+            let account = ensure_signed(origin)?;
+            ensure!(Pools::<T>::contains_key(asset_id), Error::<T>::PoolNotExist);
 
-            // update user supply
-            Self::update_user_supply(&pool, asset_id, account.clone(), amount, false);
+            BorrowAllowances::<T>::insert(account.clone(), (delegatee.clone(), asset_id), amount);
+            Self::deposit_event(RawEvent::DelegationApproved(asset_id, account, delegatee, amount));
 
-            // update pool supply
-            Self::update_pool_supply(&mut pool, amount, false);
+            Ok(())
+        }
 
-            Self::deposit_event(RawEvent::Withdrawn(asset_id, account, amount));
+        /// Take back the allowance of another account, the debt it already borrowed stays the caller's
+		///
+		/// - `delegatee`: The account that can borrow
+		/// - `asset_id`: The asset it can borrow
+        #[weight = T::WeightInfo::revoke_delegation()]
+        fn revoke_delegation(origin, delegatee: T::AccountId, asset_id: T::AssetId) -> Result {
+            let account = ensure_signed(origin)?;
+            ensure!(BorrowAllowances::<T>::contains_key(account.clone(), (delegatee.clone(), asset_id)), Error::<T>::DelegationNotExist);
 
-            // commit pool change to storage
-            Pools::<T>::insert(asset_id, pool);
+            BorrowAllowances::<T>::remove(account.clone(), (delegatee.clone(), asset_id));
+            Self::deposit_event(RawEvent::DelegationRevoked(asset_id, account, delegatee));
 
-            debug::info!("Leaving withdraw");
             Ok(())
         }
 
-        /// Borrow an asset from the pool
+        /// Repay an asset to the pool
 		///
-		/// - `asset_id`: The asset that user wants to borrow
-		/// - `amount`: The amount that user wants to borrow
-        #[weight = 1]
-        fn borrow(
+		/// - `asset_id`: The asset that user wants to repay
+		/// - `amount`: The amount that user wants to repay
+        #[weight = T::WeightInfo::repay()]
+        fn repay(
             origin,
             asset_id: T::AssetId,
             amount: T::Balance) -> Result {
-            debug::info!("Entering borrow");
             let account = ensure_signed(origin)?;
 
-            // check pool exists and get pool instance
-            let mut pool = Self::pool(asset_id).ok_or(Error::<T>::PoolNotExist)?;
+            let amount = Self::do_repay(account.clone(), account.clone(), asset_id, amount)?;
+            Self::deposit_event(RawEvent::Repaid(asset_id, account, amount));
 
-            // accrue interest
-            Self::accrue_interest(&mut pool);
+            Ok(())
+        }
 
-            // check pool cash = (deposit - borrow) > amount
-            if (pool.supply - pool.debt) < amount {
-                Err(Error::<T>::NotEnoughLiquidity)?
-            }
+        /// Repay another user's debt, e.g. to keep it away from liquidation
+		///
+		/// - `target_user`: The user whose debt to repay
+		/// - `asset_id`: The asset to repay
+		/// - `amount`: The amount to repay, at most the debt with interest
+        #[weight = T::WeightInfo::repay_on_behalf()]
+        fn repay_on_behalf(
+            origin,
+            target_user: T::AccountId,
+            asset_id: T::AssetId,
+            amount: T::Balance) -> Result {
+            let account = ensure_signed(origin)?;
 
-            // need to accrue user interest first
-            Self::accrue_debt_with_interest(&pool, asset_id, account.clone());
+            let amount = Self::do_repay(account.clone(), target_user.clone(), asset_id, amount)?;
+            Self::deposit_event(RawEvent::RepaidOnBehalf(asset_id, account, target_user, amount));
 
-            // check collateral
-            let (_, converted_supply, converted_borrow) = Self::get_user_info(account.clone());
-            let price = T::Oracle::get_rate(asset_id);
-            let converted_borrow = converted_borrow + price.saturating_mul_int(amount);
-            ensure!(Self::get_liquidation_threshold().saturating_mul_int(converted_borrow) <= converted_supply, Error::<T>::BelowLiquidationThreshold);
+            Ok(())
+        }
 
-            // transfer asset to user
-            T::MultiAsset::transfer(
-                Self::account_id(),
-                asset_id,
-                account.clone(),
-                amount,
-            ).map_err(|_| Error::<T>::TransferFailed)?;
-            // update user Borrow
-            Self::update_user_debt(&pool, asset_id, account.clone(), amount, true);
-            // update pool borrow
-            Self::update_pool_debt(&mut pool, amount, true);
-
-            Self::deposit_event(RawEvent::Borrowed(asset_id, account.clone(), amount));
-
-            // update user's debt asset set
-            let mut assets = Self::user_debt_set(account.clone());
-            if !assets.iter().any(|x| *x == asset_id) {
-                assets.push(asset_id);
-                UserDebtSet::<T>::insert(account, assets);
-            }
+        /// Repay the whole debt of an asset, interest included
+		///
+		/// - `asset_id`: The asset to repay
+        #[weight = T::WeightInfo::repay_all()]
+        fn repay_all(origin, asset_id: T::AssetId) -> Result {
+            let account = ensure_signed(origin)?;
 
-            // commit pool change to storage
-            Pools::<T>::insert(asset_id, pool);
-            debug::info!("Leaving borrow");
+            let amount = Self::do_repay(account.clone(), account.clone(), asset_id, T::Balance::max_value())?;
+            Self::deposit_event(RawEvent::Repaid(asset_id, account, amount));
 
             Ok(())
         }
 
-        /// Repay an asset to the pool
+        /// Enable a supplied asset as collateral
 		///
-		/// - `asset_id`: The asset that user wants to repay
-		/// - `amount`: The amount that user wants to repay
-        #[weight = 1]
-        fn repay(
-            origin,
-            asset_id: T::AssetId,
-            amount: T::Balance) -> Result {
-            debug::info!("Entering repay");
-
+		/// - `asset_id`: The asset that user wants to use as collateral
+        #[weight = T::WeightInfo::enable_collateral()]
+        fn enable_collateral(origin, asset_id: T::AssetId) -> Result {
             let account = ensure_signed(origin)?;
 
-            // check pool exists and get pool instance
-            let mut pool = Self::pool(asset_id).ok_or(Error::<T>::PoolNotExist)?;
-            // accrue interest
-            Self::accrue_interest(&mut pool);
+            let pool = Self::pool(asset_id).ok_or(Error::<T>::PoolNotExist)?;
+            ensure!(pool.can_be_collateral, Error::<T>::AssetNotCollateral);
+            ensure!(UserSupplies::<T>::contains_key(asset_id, account.clone()), Error::<T>::UserNoSupply);
 
-            // accrue user's interest
-            Self::accrue_debt_with_interest(&pool, asset_id, account.clone());
+            let mut collaterals = Self::user_collateral_set(account.clone());
+            if Self::set_insert(&mut collaterals, asset_id) {
+                UserCollateralSet::<T>::insert(account.clone(), collaterals);
+                Self::deposit_event(RawEvent::CollateralEnabled(asset_id, account));
+            }
+
+            Ok(())
+        }
+
+        /// Disable a supplied asset as collateral, the debt must stay within the collateral factors of the remaining collaterals
+		///
+		/// - `asset_id`: The asset that user no longer wants to use as collateral
+        #[weight = T::WeightInfo::disable_collateral(MAX_USER_ASSETS)]
+        fn disable_collateral(origin, asset_id: T::AssetId) -> Result {
+            let account = ensure_signed(origin)?;
+
+            let pool = Self::pool(asset_id).ok_or(Error::<T>::PoolNotExist)?;
+            ensure!(Self::set_contains(&Self::user_collateral_set(account.clone()), asset_id), Error::<T>::AssetNotCollateral);
 
-            // pre-check amount
-            let mut amount = amount;
-            if let Some(user_debt) = Self::user_debt(asset_id, account.clone()) {
-                if user_debt.amount < amount {
-                    amount = user_debt.amount;
+            // check collateral without this asset
+            // nothing to check if the pool no longer counts as collateral
+            if pool.can_be_collateral {
+                // the position is only valued if there is debt
+                if !Self::user_debt_set(account.clone()).is_empty() {
+                    Self::ensure_fresh_prices(account.clone())?;
                 }
-            } else {
-                Err(Error::<T>::UserNoDebt)?
+                let (_, converted_supply, converted_borrow) = Self::get_user_info(account.clone());
+                let amount = Self::get_user_supply_with_interest(asset_id, account.clone());
+                let price = Self::oracle_price(asset_id);
+                let collateral_factor = Self::collateral_factor_in_emode(&pool, asset_id, &Self::user_emode(account.clone()));
+                let converted_supply = converted_supply.saturating_sub((price * collateral_factor).saturating_mul_int(amount));
+                ensure!(converted_borrow <= converted_supply, Error::<T>::BelowCollateralFactor);
             }
 
-            // transfer token from user
-            T::MultiAsset::transfer(
-                account.clone(),
-                asset_id,
-                Self::account_id(),
-                amount,
-            ).map_err(|_| Error::<T>::TransferFailed)?;
+            UserCollateralSet::<T>::mutate(account.clone(), |collaterals| Self::set_remove(collaterals, asset_id));
+            Self::deposit_event(RawEvent::CollateralDisabled(asset_id, account));
 
-            // update user Borrow
-            Self::update_user_debt(&pool, asset_id, account.clone(), amount, false);
-            // update pool borrow
-            Self::update_pool_debt(&mut pool, amount, false);
+            Ok(())
+        }
 
-            Self::deposit_event(RawEvent::Repaid(asset_id, account, amount));
+        /// Enter or leave an efficiency mode category, all debt must be in the category.
+        /// The debt must stay within the collateral factors under the new parameters.
+		///
+		/// - `category_id`: The category to enter, none to leave efficiency mode
+        #[weight = T::WeightInfo::set_user_emode(MAX_USER_ASSETS)]
+        fn set_user_emode(origin, category_id: Option<EModeCategoryId>) -> Result {
+            let account = ensure_signed(origin)?;
+
+            let emode = match category_id {
+                Some(id) => {
+                    let category = Self::emode_category(id).ok_or(Error::<T>::EModeCategoryNotExist)?;
+                    ensure!(
+                        Self::assets_in(Self::user_debt_set(account.clone())).iter().all(|asset| Self::asset_emode_category(asset) == Some(id)),
+                        Error::<T>::AssetNotInEModeCategory
+                    );
+                    Some((id, category))
+                }
+                None => None,
+            };
+            // the position is only valued if there is debt
+            if !Self::user_debt_set(account.clone()).is_empty() {
+                Self::ensure_fresh_prices(account.clone())?;
+            }
 
-            // commit pool change to storage
-            Pools::<T>::insert(asset_id, pool);
-            debug::info!("Leaving repay");
+            let (_, converted_supply, _, converted_borrow) = Self::get_user_info_in_emode(account.clone(), &emode);
+            ensure!(converted_borrow <= converted_supply, Error::<T>::BelowCollateralFactor);
+
+            UserEModeCategory::<T>::mutate(account.clone(), |c| *c = category_id);
+            Self::deposit_event(RawEvent::UserEModeUpdated(account, category_id));
 
             Ok(())
         }
@@ -405,7 +798,8 @@ decl_module! {
 		/// - `pay_asset_id`: The asset to repay for the target user
 		/// - `get_asset_id`: The asset to seize
 		/// - `pay_asset_amount`: Amount of debt to pay for target user
-        #[weight = 1]
+        #[weight = T::WeightInfo::liquidate(MAX_USER_ASSETS)]
+        #[transactional]
         fn liquidate(
             origin,
             target_user: T::AccountId,
@@ -413,197 +807,663 @@ decl_module! {
             get_asset_id: T::AssetId,
             pay_asset_amount: T::Balance
         ) -> Result {
-            debug::info!("Entering liquidate");
-            let account = ensure_signed(origin)?;
-
-            // check pool exists and get pool instances
-            // check if get_asset_id is enabled as collateral
-            let mut get_pool = Self::pool(get_asset_id).ok_or(Error::<T>::PoolNotExist)?;
-            ensure!(get_pool.can_be_collateral, Error::<T>::AssetNotCollateral);
-            
-            let mut pay_pool = Self::pool(pay_asset_id).ok_or(Error::<T>::PoolNotExist)?;
-
-            // 2 accrue interest of pay and get asset
-            Self::accrue_interest(&mut pay_pool);
-            Self::accrue_interest(&mut get_pool);
-
-            // accrue target user's interest
-            Self::accrue_supply_with_interest(&get_pool, get_asset_id, target_user.clone());
-            Self::accrue_debt_with_interest(&pay_pool, pay_asset_id, target_user.clone());
-            
-            // 3 check if target user is under liquidation condition
-            let (_, converted_supply, converted_borrow) = Self::get_user_info(target_user.clone());
-            ensure!(Self::get_liquidation_threshold().saturating_mul_int(converted_borrow) > converted_supply, Error::<T>::AboveLiquidationThreshold);
-        
-            // 4 check if liquidation % is more than threshold 
-            // TODO: if target user supply is too small, enable total liquidation
-            let target_user_supply = Self::user_supply(get_asset_id, target_user.clone()).ok_or(Error::<T>::UserNoSupply)?;
-            let get_limit = get_pool.close_factor.saturating_mul_int(target_user_supply.amount);
-            
-            let get_price = T::Oracle::get_rate(get_asset_id);
-            let pay_price = T::Oracle::get_rate(pay_asset_id);
-            let pay_limit = (get_price / pay_price * get_pool.discount_factor).saturating_mul_int(get_limit);
-            let target_user_debt = Self::user_debt(pay_asset_id, target_user.clone()).ok_or(Error::<T>::UserNoSupply)?;
-
-            let mut pay_asset_amount = pay_asset_amount;
-
-            if pay_asset_amount > pay_limit {
-                pay_asset_amount = pay_limit;
-            }
-
-            if pay_asset_amount > target_user_debt.amount {
-                pay_asset_amount = target_user_debt.amount;
-            }
+            Self::do_liquidate(origin, target_user, pay_asset_id, get_asset_id, pay_asset_amount)
+        }
 
-            // TODO: check rounding errors due to discount_factor
-            let get_asset_amount = (pay_price / get_price / get_pool.discount_factor ).saturating_mul_int(pay_asset_amount);
+        /// Liquidate several debt and collateral pairs of one target user at once.
+        /// Health is checked once before any pair, and each collateral can only be seized
+        /// up to its close factor across the whole batch
+		///
+		/// - `target_user`: Target user whose assets to seize
+		/// - `liquidations`: The pay asset, seized asset and amount of debt to pay of each pair
+        #[weight = T::WeightInfo::liquidate_batch(MAX_USER_ASSETS, (liquidations.len() as u32).min(MAX_BATCH_LIQUIDATIONS))]
+        #[transactional]
+        fn liquidate_batch(
+            origin,
+            target_user: T::AccountId,
+            liquidations: Vec<(T::AssetId, T::AssetId, T::Balance)>
+        ) -> Result {
+            Self::do_liquidate_batch(origin, target_user, liquidations)
+        }
 
-            // 5 transfer token from arbitrager
-            T::MultiAsset::transfer(
-                account.clone(),
-                pay_asset_id,
-                Self::account_id(),
-                pay_asset_amount,
-            ).map_err(|_| Error::<T>::TransferFailed)?;            
+        /// Put the collateral of an unhealthy position up in a descending price auction,
+        /// an alternative to `liquidate` with its fixed discount. As much collateral as
+        /// `liquidate` could seize is held until bought, and goes back to the target when
+        /// the debt is repaid or the auction expires.
+		///
+		/// - `target_user`: Target user whose collateral to auction
+		/// - `pay_asset_id`: The debt asset bids repay
+		/// - `get_asset_id`: The collateral asset to auction
+        #[weight = T::WeightInfo::start_auction(MAX_USER_ASSETS)]
+        #[transactional]
+        fn start_auction(
+            origin,
+            target_user: T::AccountId,
+            pay_asset_id: T::AssetId,
+            get_asset_id: T::AssetId
+        ) -> Result {
+            Self::do_start_auction(origin, target_user, pay_asset_id, get_asset_id)
+        }
 
-            // 6 transfer collateral to arbitrager
-            T::MultiAsset::transfer(
-                Self::account_id(),
-                get_asset_id,
-                account.clone(),
-                get_asset_amount,
-            ).map_err(|_| Error::<T>::TransferFailed)?;
-            // 7 recalculate target user's borrow and supply in 2 pools
-            Self::update_user_supply(&mut get_pool, get_asset_id, target_user.clone(), get_asset_amount, false);
-            Self::update_user_debt(&mut pay_pool, pay_asset_id, target_user, pay_asset_amount, false);
+        /// Buy collateral of an auction at its current price, repaying the target user's debt.
+        /// The auction ends once the debt is repaid or the collateral is sold out.
+		///
+		/// - `auction_id`: The auction
+		/// - `get_asset_amount`: The most collateral to buy
+        #[weight = T::WeightInfo::bid()]
+        #[transactional]
+        fn bid(origin, auction_id: AuctionId, get_asset_amount: T::Balance) -> Result {
+            Self::do_bid(origin, auction_id, get_asset_amount)
+        }
 
-            // update pools
-            Pools::<T>::insert(get_asset_id, get_pool);
-            Pools::<T>::insert(pay_asset_id, pay_pool);
+        /// Lock a user's stable debt at the current stable rate, once the variable rate is more than
+        /// the rebalance spread above the average stable rate and the user's rate is below the current one
+		///
+		/// - `target_user`: The user whose stable debt to rebalance
+		/// - `asset_id`: The debt asset
+        #[weight = T::WeightInfo::rebalance_stable_rate()]
+        fn rebalance_stable_rate(origin, target_user: T::AccountId, asset_id: T::AssetId) -> Result {
+            ensure_signed(origin)?;
 
-            debug::info!("Leaving liquidate");
+            let mut pool = Self::pool(asset_id).ok_or(Error::<T>::PoolNotExist)?;
+            let config = pool.stable_rate_config.clone().ok_or(Error::<T>::StableBorrowDisabled)?;
+            Self::accrue_interest(&mut pool);
+            Self::accrue_debt_with_interest(&pool, asset_id, target_user.clone());
+
+            let mut user_debt = Self::user_debt(asset_id, target_user.clone()).ok_or(Error::<T>::UserNoDebt)?;
+            let locked = user_debt.stable_rate.ok_or(Error::<T>::RateModeMismatch)?;
+            let rate = Self::debt_rate_internal(&pool).saturating_add(config.premium);
+            ensure!(
+                Self::debt_rate_internal(&pool) >= pool.average_stable_rate.saturating_add(config.rebalance_spread) && locked < rate,
+                Error::<T>::RebalanceNotAllowed
+            );
+
+            // the debt moves from the old rate to the new one
+            Self::update_pool_debt(&mut pool, user_debt.amount, Some(locked), false);
+            Self::update_pool_debt(&mut pool, user_debt.amount, Some(rate), true);
+            user_debt.stable_rate = Some(rate);
+            UserDebts::<T>::insert(asset_id, target_user.clone(), user_debt);
+            Self::deposit_position_event(&pool, asset_id, target_user.clone());
+            Self::put_pool(asset_id, pool);
+
+            Self::deposit_event(RawEvent::StableRateLocked(asset_id, target_user, rate));
 
             Ok(())
         }
+
+        /// Borrow from a pool and pay it back with a fee in the same extrinsic.
+        /// The amount is sent to the user, then `call` is dispatched from the user,
+        /// then amount plus fee is taken back. Everything is reverted if any step fails.
+		///
+		/// - `asset_id`: The asset to borrow
+		/// - `amount`: The amount to borrow
+		/// - `call`: The call that uses the loan, e.g. an arbitrage
+        #[weight = (call.get_dispatch_info().weight.saturating_add(T::WeightInfo::flash_loan()), call.get_dispatch_info().class)]
+        #[transactional]
+        fn flash_loan(
+            origin,
+            asset_id: T::AssetId,
+            amount: T::Balance,
+            call: Box<<T as Trait>::Call>
+        ) -> Result {
+            Self::do_flash_loan(origin, asset_id, amount, *call)
+        }
         
         // governance related
 
-        #[weight = 1]
+        /// Initialize a pool
+		///
+		/// - `id`: The underlying asset of the pool
+		/// - `can_be_collateral`: If the asset can be enabled as collateral
+		/// - `interest_rate_model`: The model deriving debt interest rate from utilization
+        #[weight = T::WeightInfo::init_pool()]
         fn init_pool(
             origin,
             id: T::AssetId,
-            can_be_collateral: bool
+            can_be_collateral: bool,
+            interest_rate_model: InterestRateModel
         ) -> Result {
+            ensure_root(origin)?;
+            ensure!(!Pools::<T>::contains_key(id), Error::<T>::PoolAlreadyExists);
+            ensure!(!KTokenUnderlying::<T>::contains_key(id), Error::<T>::PoolOfKToken);
+            ensure!(interest_rate_model.is_valid(), Error::<T>::InvalidInterestRateModel);
+            ensure!(Self::pool_count() < MAX_POOLS, Error::<T>::TooManyPools);
 
-            Self::_init_pool(id, can_be_collateral);
+            Self::_init_pool(id, can_be_collateral, interest_rate_model);
 
             Ok(())
         }
-    }
-}
 
-impl<T: Trait> Module<T> where
-{
+        /// Change the interest rate model of a pool
+		///
+		/// - `id`: The underlying asset of the pool
+		/// - `interest_rate_model`: The new interest rate model
+        #[weight = T::WeightInfo::set_interest_rate_model()]
+        fn set_interest_rate_model(
+            origin,
+            id: T::AssetId,
+            interest_rate_model: InterestRateModel
+        ) -> Result {
+            ensure_root(origin)?;
+            ensure!(interest_rate_model.is_valid(), Error::<T>::InvalidInterestRateModel);
 
-    fn account_id() -> T::AccountId {
-		PALLET_ID.into_account()
-    }
-    
-    fn accrue_interest(pool: &mut Pool<T>) {
-        debug::info!("Entering accrue_interest");
+            let mut pool = Self::pool(id).ok_or(Error::<T>::PoolNotExist)?;
+            // interest until now is accrued under the old model
+            Self::accrue_interest(&mut pool);
+            pool.interest_rate_model = interest_rate_model;
+            Self::put_pool(id, pool);
 
-        if pool.last_updated == <frame_system::Module<T>>::block_number() {
-            debug::info!("Leaving accrue_interest");
-            return
+            Self::deposit_event(RawEvent::InterestRateModelUpdated(id));
+
+            Ok(())
         }
 
-        // get time span
-        let interval_block_number = <frame_system::Module<T>>::block_number() - pool.last_updated;
-		let elapsed_time_u32 = TryInto::<u32>::try_into(interval_block_number)
-			.ok()
-			.expect("blockchain will not exceed 2^32 blocks; qed");
+        /// Set the share of the asset value that can be borrowed against, <=liquidation_threshold
+		///
+		/// - `id`: The underlying asset of the pool
+		/// - `collateral_factor`: The new collateral factor
+        #[weight = T::WeightInfo::set_collateral_factor()]
+        fn set_collateral_factor(origin, id: T::AssetId, collateral_factor: FixedU128) -> Result {
+            ensure_root(origin)?;
+            let pool = Self::pool(id).ok_or(Error::<T>::PoolNotExist)?;
+            ensure!(collateral_factor <= pool.liquidation_threshold, Error::<T>::ParameterOutOfBounds);
+
+            Self::update_pool_parameters(id, |pool| pool.collateral_factor = collateral_factor)
+        }
 
-        // get rates and calculate interest
-        let supply_multiplier = FixedU128::one() + Self::supply_rate_internal(pool) * FixedU128::saturating_from_integer(elapsed_time_u32);
-        let debt_multiplier = FixedU128::one() + Self::debt_rate_internal(pool) * FixedU128::saturating_from_integer(elapsed_time_u32);
+        /// Set the share of the asset value that debt can reach before liquidation, [collateral_factor, 1]
+		///
+		/// - `id`: The underlying asset of the pool
+		/// - `liquidation_threshold`: The new liquidation threshold
+        #[weight = T::WeightInfo::set_liquidation_threshold()]
+        fn set_liquidation_threshold(origin, id: T::AssetId, liquidation_threshold: FixedU128) -> Result {
+            ensure_root(origin)?;
+            let pool = Self::pool(id).ok_or(Error::<T>::PoolNotExist)?;
+            ensure!(
+                pool.collateral_factor <= liquidation_threshold && liquidation_threshold <= FixedU128::one(),
+                Error::<T>::ParameterOutOfBounds
+            );
+
+            Self::update_pool_parameters(id, |pool| pool.liquidation_threshold = liquidation_threshold)
+        }
 
-        pool.supply = supply_multiplier.saturating_mul_int(pool.supply);
-        pool.total_supply_index = pool.total_supply_index * supply_multiplier;
+        /// Set the share of a collateral one liquidation can seize, (0, 1]
+		///
+		/// - `id`: The underlying asset of the pool
+		/// - `close_factor`: The new close factor
+        #[weight = T::WeightInfo::set_close_factor()]
+        fn set_close_factor(origin, id: T::AssetId, close_factor: FixedU128) -> Result {
+            ensure_root(origin)?;
+            ensure!(!close_factor.is_zero() && close_factor <= FixedU128::one(), Error::<T>::ParameterOutOfBounds);
+
+            Self::update_pool_parameters(id, |pool| pool.close_factor = close_factor)
+        }
 
-        pool.debt = debt_multiplier.saturating_mul_int(pool.debt);
-        pool.total_debt_index = pool.total_debt_index * debt_multiplier;
+        /// Set the discount liquidators get on seized collateral, (0, 1]
+		///
+		/// - `id`: The underlying asset of the pool
+		/// - `discount_factor`: The new discount factor
+        #[weight = T::WeightInfo::set_discount_factor()]
+        fn set_discount_factor(origin, id: T::AssetId, discount_factor: FixedU128) -> Result {
+            ensure_root(origin)?;
+            ensure!(!discount_factor.is_zero() && discount_factor <= FixedU128::one(), Error::<T>::ParameterOutOfBounds);
+
+            Self::update_pool_parameters(id, |pool| pool.discount_factor = discount_factor)
+        }
 
-        pool.last_updated = <frame_system::Module<T>>::block_number();
-        debug::info!("Leaving accrue_interest");
+        /// Set if the asset can be enabled as collateral
+		///
+		/// - `id`: The underlying asset of the pool
+		/// - `can_be_collateral`: If the asset can be enabled as collateral
+        #[weight = T::WeightInfo::set_can_be_collateral()]
+        fn set_can_be_collateral(origin, id: T::AssetId, can_be_collateral: bool) -> Result {
+            ensure_root(origin)?;
 
-    }
+            Self::update_pool_parameters(id, |pool| pool.can_be_collateral = can_be_collateral)
+        }
 
-    /// amount is pre-checked so will no be negative
-    fn update_user_supply(pool: &Pool<T>, asset_id: T::AssetId, account: T::AccountId, amount: T::Balance, positive: bool) {
-        debug::info!("Entering update_user_supply");
-        if let Some(mut user_supply) = Self::user_supply(asset_id, account.clone()) {
+        /// Set the max total supply of a pool
+		///
+		/// - `id`: The underlying asset of the pool
+		/// - `supply_cap`: The new supply cap, no limit if none
+        #[weight = T::WeightInfo::set_supply_cap()]
+        fn set_supply_cap(origin, id: T::AssetId, supply_cap: Option<T::Balance>) -> Result {
+            ensure_root(origin)?;
 
-            user_supply.amount = (pool.total_supply_index / user_supply.index).saturating_mul_int(user_supply.amount);
+            Self::update_pool_parameters(id, |pool| pool.supply_cap = supply_cap)
+        }
 
-            user_supply.index = pool.total_supply_index;
+        /// Set the max total debt of a pool
+		///
+		/// - `id`: The underlying asset of the pool
+		/// - `borrow_cap`: The new borrow cap, no limit if none
+        #[weight = T::WeightInfo::set_borrow_cap()]
+        fn set_borrow_cap(origin, id: T::AssetId, borrow_cap: Option<T::Balance>) -> Result {
+            ensure_root(origin)?;
 
-            if positive {
-                user_supply.amount += amount;
-            } else {
-                user_supply.amount -= amount;
-            }
-            if user_supply.amount != T::Balance::zero() {
-                UserSupplies::<T>::insert(asset_id, account, user_supply);
-            } else {
-                UserSupplies::<T>::remove(asset_id, account.clone());
-                // update user's supply asset set
-                let mut assets = Self::user_supply_set(account.clone());
-                assets.retain(|x| *x != asset_id);
-                UserSupplySet::<T>::insert(account, assets);
-            }
-        } else if amount != T::Balance::zero() {
-            let user_supply = UserSupply::<T> {
-                amount,
-                index: pool.total_supply_index,
-            };
-            UserSupplies::<T>::insert(asset_id, account, user_supply);
+            Self::update_pool_parameters(id, |pool| pool.borrow_cap = borrow_cap)
         }
-        debug::info!("Leaving update_user_supply");
 
-    }
+        /// Enable or disable stable rate borrowing on a pool, stable debt already borrowed keeps its rate
+		///
+		/// - `id`: The underlying asset of the pool
+		/// - `config`: The premium over the variable rate and the rebalance spread, none to disable it
+        #[weight = T::WeightInfo::set_stable_rate_config()]
+        fn set_stable_rate_config(origin, id: T::AssetId, config: Option<StableRateConfig>) -> Result {
+            ensure_root(origin)?;
 
-    /// amount is pre-checked so will no be negative
-    fn update_user_debt(pool: &Pool<T>, asset_id: T::AssetId, account: T::AccountId, amount: T::Balance, positive: bool) {
-        debug::info!("Entering update_user_debt");
+            Self::update_pool_parameters(id, |pool| pool.stable_rate_config = config)
+        }
 
-        if let Some(mut user_debt) = Self::user_debt(asset_id, account.clone()) {
-            user_debt.amount = (pool.total_debt_index / user_debt.index).saturating_mul_int(user_debt.amount);
+        /// Enable or disable a pool, a disabled pool accepts no new supply or borrow
+		///
+		/// - `id`: The underlying asset of the pool
+		/// - `enabled`: If the pool is enabled
+        #[weight = T::WeightInfo::set_pool_enabled()]
+        fn set_pool_enabled(origin, id: T::AssetId, enabled: bool) -> Result {
+            ensure_root(origin)?;
 
-            user_debt.index = pool.total_debt_index;
+            Self::update_pool_parameters(id, |pool| pool.enabled = enabled)
+        }
 
-            if positive {
+        /// Set the collateral value below which a liquidation can seize all of it
+		///
+		/// - `dust_threshold`: The new dust threshold, in the same unit as prices
+        #[weight = T::WeightInfo::set_dust_threshold()]
+        fn set_dust_threshold(origin, dust_threshold: T::Balance) -> Result {
+            ensure_root(origin)?;
+
+            DustThreshold::<T>::put(dust_threshold);
+            Self::deposit_event(RawEvent::DustThresholdUpdated(dust_threshold));
+
+            Ok(())
+        }
+
+        /// Write off debt of a user who has no supply left and no auction running.
+        /// The debt is absorbed by protocol reserves first, and the rest is socialized across suppliers
+		///
+		/// - `target_user`: The user whose debt is bad
+		/// - `asset_id`: The debt asset to write off
+        #[weight = T::WeightInfo::write_off_bad_debt()]
+        fn write_off_bad_debt(origin, target_user: T::AccountId, asset_id: T::AssetId) -> Result {
+            ensure_root(origin)?;
+            ensure!(Self::user_collateral_set(target_user.clone()).is_empty(), Error::<T>::UserHasCollateral);
+            ensure!(Self::user_supply_set(target_user.clone()).is_empty(), Error::<T>::UserHasSupply);
+            ensure!(Self::user_auction(target_user.clone()).is_none(), Error::<T>::AuctionInProgress);
+
+            let mut pool = Self::pool(asset_id).ok_or(Error::<T>::PoolNotExist)?;
+            Self::accrue_interest(&mut pool);
+            let user_debt = Self::user_debt(asset_id, target_user.clone()).ok_or(Error::<T>::UserNoDebt)?;
+            let amount = Self::debt_with_interest(&user_debt, pool.total_debt_index, Self::now());
+
+            // absorb from reserves, and socialize the rest by shrinking every supply proportionally
+            let from_reserves = amount.min(pool.reserves);
+            let socialized = amount - from_reserves;
+            // wiping out all supply would zero the supply index
+            ensure!(socialized.is_zero() || socialized < pool.supply, Error::<T>::BadDebtExceedsSupply);
+
+            Self::accrue_debt_with_interest(&pool, asset_id, target_user.clone());
+            let stable_rate = Self::update_user_debt(&pool, asset_id, target_user.clone(), amount, false, None);
+            Self::update_pool_debt(&mut pool, amount, stable_rate, false);
+
+            pool.reserves -= from_reserves;
+            if !socialized.is_zero() {
+                let remaining = pool.supply - socialized;
+                pool.total_supply_index = pool.total_supply_index * FixedU128::saturating_from_rational(remaining, pool.supply);
+                pool.supply = remaining;
+            }
+
+            Self::put_pool(asset_id, pool);
+
+            Self::deposit_event(RawEvent::BadDebtWrittenOff(asset_id, target_user, from_reserves, socialized));
+
+            Ok(())
+        }
+
+        /// Set the pause guardian
+		///
+		/// - `guardian`: The account that can pause actions on pools, none to remove it
+        #[weight = T::WeightInfo::set_pause_guardian()]
+        fn set_pause_guardian(origin, guardian: Option<T::AccountId>) -> Result {
+            ensure_root(origin)?;
+
+            PauseGuardian::<T>::set(guardian.clone());
+            Self::deposit_event(RawEvent::PauseGuardianUpdated(guardian));
+
+            Ok(())
+        }
+
+        /// Pause or unpause an action on a pool
+        /// The pause guardian can only pause, unpausing needs governance
+		///
+		/// - `id`: The underlying asset of the pool
+		/// - `action`: The action to pause or unpause
+		/// - `paused`: If the action is paused
+        #[weight = T::WeightInfo::set_action_paused()]
+        fn set_action_paused(origin, id: T::AssetId, action: PoolAction, paused: bool) -> Result {
+            if ensure_root(origin.clone()).is_err() {
+                let account = ensure_signed(origin)?;
+                ensure!(paused && Self::pause_guardian() == Some(account), Error::<T>::NotPauseGuardian);
+            }
+            ensure!(Pools::<T>::contains_key(id), Error::<T>::PoolNotExist);
+
+            PausedActions::<T>::insert(id, action, paused);
+            Self::deposit_event(RawEvent::ActionPausedUpdated(id, action, paused));
+
+            Ok(())
+        }
+
+        /// Set the share of debt interest that goes to protocol reserves, <=1
+		///
+		/// - `id`: The underlying asset of the pool
+		/// - `reserve_factor`: The new reserve factor
+        #[weight = T::WeightInfo::set_reserve_factor()]
+        fn set_reserve_factor(origin, id: T::AssetId, reserve_factor: FixedU128) -> Result {
+            ensure_root(origin)?;
+            ensure!(reserve_factor <= FixedU128::one(), Error::<T>::ParameterOutOfBounds);
+
+            Self::update_pool_parameters(id, |pool| {
+                // interest until now is split under the old factor
+                Self::accrue_interest(pool);
+                pool.reserve_factor = reserve_factor;
+            })
+        }
+
+        /// Define, change or remove an efficiency mode category
+		///
+		/// - `id`: The category
+		/// - `category`: Risk parameters of the category, none to remove it
+        #[weight = T::WeightInfo::set_emode_category()]
+        fn set_emode_category(origin, id: EModeCategoryId, category: Option<EModeCategory>) -> Result {
+            ensure_root(origin)?;
+            if let Some(category) = &category {
+                ensure!(
+                    category.collateral_factor <= category.liquidation_threshold && category.liquidation_threshold <= FixedU128::one(),
+                    Error::<T>::ParameterOutOfBounds
+                );
+            }
+
+            EModeCategories::mutate(id, |c| *c = category);
+            Self::deposit_event(RawEvent::EModeCategoryUpdated(id));
+
+            Ok(())
+        }
+
+        /// Put an asset into an efficiency mode category
+		///
+		/// - `id`: The underlying asset of the pool
+		/// - `category_id`: The category of the asset, none to take it out of efficiency mode
+        #[weight = T::WeightInfo::set_asset_emode_category()]
+        fn set_asset_emode_category(origin, id: T::AssetId, category_id: Option<EModeCategoryId>) -> Result {
+            ensure_root(origin)?;
+            ensure!(Pools::<T>::contains_key(id), Error::<T>::PoolNotExist);
+            if let Some(category_id) = category_id {
+                ensure!(EModeCategories::contains_key(category_id), Error::<T>::EModeCategoryNotExist);
+            }
+
+            AssetEModeCategory::<T>::mutate(id, |c| *c = category_id);
+            Self::deposit_event(RawEvent::AssetEModeCategoryUpdated(id, category_id));
+
+            Ok(())
+        }
+
+        /// Set the parameters of liquidation auctions, running auctions keep theirs
+		///
+		/// - `config`: The new auction parameters
+        #[weight = T::WeightInfo::set_auction_config()]
+        fn set_auction_config(origin, config: AuctionConfig) -> Result {
+            ensure_root(origin)?;
+            ensure!(
+                config.duration > 0 && !config.end_factor.is_zero() && config.end_factor <= config.start_factor,
+                Error::<T>::ParameterOutOfBounds
+            );
+
+            AuctionParameters::put(config);
+            Self::deposit_event(RawEvent::AuctionConfigUpdated);
+
+            Ok(())
+        }
+
+        /// Set the share of a flash loan charged as fee, <=1
+		///
+		/// - `fee`: The new flash loan fee
+        #[weight = T::WeightInfo::set_flash_loan_fee()]
+        fn set_flash_loan_fee(origin, fee: FixedU128) -> Result {
+            ensure_root(origin)?;
+            ensure!(fee <= FixedU128::one(), Error::<T>::ParameterOutOfBounds);
+
+            FlashLoanFee::put(fee);
+            Self::deposit_event(RawEvent::FlashLoanFeeUpdated(fee));
+
+            Ok(())
+        }
+
+        /// Set how old the oracle price of an asset can be when borrowing, withdrawing or liquidating
+		///
+		/// - `id`: The underlying asset of the pool
+		/// - `max_age`: The max age in seconds, none for no limit
+        #[weight = T::WeightInfo::set_max_price_age()]
+        fn set_max_price_age(origin, id: T::AssetId, max_age: Option<u64>) -> Result {
+            ensure_root(origin)?;
+            ensure!(Pools::<T>::contains_key(id), Error::<T>::PoolNotExist);
+
+            MaxPriceAge::<T>::mutate(id, |age| *age = max_age);
+            Self::deposit_event(RawEvent::MaxPriceAgeUpdated(id, max_age));
+
+            Ok(())
+        }
+
+        /// Withdraw protocol reserves of a pool to an account
+		///
+		/// - `id`: The underlying asset of the pool
+		/// - `to`: The account receiving the reserves, e.g. treasury
+		/// - `amount`: The amount to withdraw
+        #[weight = T::WeightInfo::withdraw_reserves()]
+        fn withdraw_reserves(origin, id: T::AssetId, to: T::AccountId, amount: T::Balance) -> Result {
+            ensure_root(origin)?;
+
+            let mut pool = Self::pool(id).ok_or(Error::<T>::PoolNotExist)?;
+            Self::accrue_interest(&mut pool);
+
+            ensure!(amount <= pool.reserves, Error::<T>::NotEnoughReserves);
+            // pool cash = deposit + reserves - borrow
+            ensure!(amount <= (pool.supply + pool.reserves).saturating_sub(pool.debt), Error::<T>::NotEnoughLiquidity);
+
+            T::MultiAsset::transfer(
+                Self::account_id(),
+                id,
+                to.clone(),
+                amount,
+            )?;
+
+            pool.reserves -= amount;
+            Self::put_pool(id, pool);
+
+            Self::deposit_event(RawEvent::ReservesWithdrawn(id, to, amount));
+
+            Ok(())
+        }
+    }
+}
+
+impl<T: Trait> Module<T> where
+{
+
+    fn account_id() -> T::AccountId {
+		PALLET_ID.into_account()
+    }
+
+    
+    /// current timestamp in seconds
+    fn now() -> u64 {
+        T::UnixTime::now().as_secs()
+    }
+
+    /// oracle price of an asset, zero if it has none
+    pub fn oracle_price(asset_id: T::AssetId) -> FixedU128 {
+        T::Oracle::get_rate(asset_id).map_or_else(FixedU128::zero, |(price, _)| price)
+    }
+
+    /// oracle price of an asset, which must be set and no older than the max age of the asset
+    fn fresh_price(asset_id: T::AssetId) -> sp_std::result::Result<FixedU128, DispatchError> {
+        let (price, updated_at) = T::Oracle::get_rate(asset_id).ok_or(Error::<T>::PriceMissing)?;
+        ensure!(!price.is_zero(), Error::<T>::PriceMissing);
+        if let Some(max_age) = Self::max_price_age(asset_id) {
+            ensure!(Self::now().saturating_sub(updated_at) <= max_age, Error::<T>::PriceStale);
+        }
+        Ok(price)
+    }
+
+    /// the prices valuing a user's collaterals and debts must all be fresh
+    fn ensure_fresh_prices(user: T::AccountId) -> Result {
+        let mut assets = Self::user_collateral_set(user.clone());
+        for index in Self::user_debt_set(user).iter() {
+            assets.insert(index);
+        }
+        for asset_id in Self::assets_in(assets) {
+            Self::fresh_price(asset_id)?;
+        }
+        Ok(())
+    }
+
+    /// seconds since the pool accrued interest
+    fn elapsed_time(pool: &Pool<T>) -> u32 {
+        // pools created at genesis have no valid timestamp yet
+        if pool.last_updated == 0 {
+            return 0
+        }
+        Self::now().saturating_sub(pool.last_updated).saturated_into::<u32>()
+    }
+
+    fn accrue_interest(pool: &mut Pool<T>) {
+        debug::info!("Entering accrue_interest");
+
+        let now = Self::now();
+        if pool.last_updated == now {
+            debug::info!("Leaving accrue_interest");
+            return
+        }
+
+        // get time span
+        let elapsed_time_u32 = Self::elapsed_time(pool);
+        Self::accrue_interest_for(pool, elapsed_time_u32);
+
+        pool.last_updated = now;
+        debug::info!("Leaving accrue_interest");
+
+    }
+
+    /// accrue interest of `elapsed` periods, each period compounds at the pool rates
+    fn accrue_interest_for(pool: &mut Pool<T>, elapsed: u32) {
+        // get rates and calculate interest, compounded every second
+        let supply_multiplier = interest_rate::compound(Self::supply_rate_internal(pool), elapsed);
+        let debt_multiplier = interest_rate::compound(Self::debt_rate_internal(pool), elapsed);
+        let stable_multiplier = interest_rate::compound(pool.average_stable_rate, elapsed);
+
+        pool.supply = supply_multiplier.saturating_mul_int(pool.supply);
+        pool.total_supply_index = pool.total_supply_index * supply_multiplier;
+
+        // variable and stable debt grow at their own rates
+        let stable_debt = stable_multiplier.saturating_mul_int(pool.stable_debt);
+        let debt = debt_multiplier.saturating_mul_int(pool.debt.saturating_sub(pool.stable_debt)) + stable_debt;
+        pool.reserves += pool.reserve_factor.saturating_mul_int(debt.saturating_sub(pool.debt));
+        pool.debt = debt;
+        pool.stable_debt = stable_debt;
+        pool.total_debt_index = pool.total_debt_index * debt_multiplier;
+    }
+
+    /// amount is pre-checked so will no be negative
+    fn update_user_supply(pool: &Pool<T>, asset_id: T::AssetId, account: T::AccountId, amount: T::Balance, positive: bool) -> Result {
+        debug::info!("Entering update_user_supply");
+        if let Some(mut user_supply) = Self::user_supply(asset_id, account.clone()) {
+
+            user_supply.amount = (pool.total_supply_index / user_supply.index).saturating_mul_int(user_supply.amount);
+
+            user_supply.index = pool.total_supply_index;
+
+            if positive {
+                user_supply.amount += amount;
+            } else {
+                user_supply.amount -= amount;
+            }
+            if user_supply.amount != T::Balance::zero() {
+                UserSupplies::<T>::insert(asset_id, account.clone(), user_supply);
+            } else {
+                UserSupplies::<T>::remove(asset_id, account.clone());
+                // update user's supply asset set
+                UserSupplySet::<T>::mutate(account.clone(), |assets| Self::set_remove(assets, asset_id));
+                // nothing left to use as collateral
+                UserCollateralSet::<T>::mutate(account.clone(), |collaterals| Self::set_remove(collaterals, asset_id));
+            }
+        } else if amount != T::Balance::zero() {
+            let user_supply = UserSupply::<T> {
+                amount,
+                index: pool.total_supply_index,
+            };
+            UserSupplies::<T>::insert(asset_id, account.clone(), user_supply);
+        }
+        Self::sync_ktokens(asset_id, account.clone())?;
+        Self::deposit_position_event(pool, asset_id, account);
+        debug::info!("Leaving update_user_supply");
+
+        Ok(())
+    }
+
+    /// amount is pre-checked so will no be negative.
+    /// A borrow at `stable_rate` averages it into the locked rate, a new debt is variable if none.
+    /// Returns the stable rate the amount moves at, none for variable debt
+    fn update_user_debt(pool: &Pool<T>, asset_id: T::AssetId, account: T::AccountId, amount: T::Balance, positive: bool, stable_rate: Option<FixedU128>) -> Option<FixedU128> {
+        debug::info!("Entering update_user_debt");
+        let now = Self::now();
+
+        let moved_rate = if let Some(mut user_debt) = Self::user_debt(asset_id, account.clone()) {
+            user_debt.amount = Self::debt_with_interest(&user_debt, pool.total_debt_index, now);
+
+            user_debt.index = pool.total_debt_index;
+            user_debt.last_updated = now;
+
+            let moved_rate = if positive { stable_rate } else { user_debt.stable_rate };
+            if positive {
+                if let (Some(locked), Some(rate)) = (user_debt.stable_rate, stable_rate) {
+                    user_debt.stable_rate = Some(Self::average_rate(user_debt.amount, locked, amount, rate));
+                }
                 user_debt.amount += amount;
             } else {
                 user_debt.amount -= amount;
             }
             if user_debt.amount != T::Balance::zero() {
-                UserDebts::<T>::insert(asset_id, account, user_debt);
+                UserDebts::<T>::insert(asset_id, account.clone(), user_debt);
             } else {
                 UserDebts::<T>::remove(asset_id, account.clone());
                 // update user's debt asset set
-                let mut assets = Self::user_debt_set(account.clone());
-                assets.retain(|x| *x != asset_id);
-                UserDebtSet::<T>::insert(account, assets);
+                UserDebtSet::<T>::mutate(account.clone(), |assets| Self::set_remove(assets, asset_id));
             }
+            moved_rate
         } else if amount != T::Balance::zero() {
             let user_debt = UserDebt::<T> {
                 amount,
                 index: pool.total_debt_index,
+                stable_rate,
+                last_updated: now,
             };
-            UserDebts::<T>::insert(asset_id, account, user_debt);
-        }
+            UserDebts::<T>::insert(asset_id, account.clone(), user_debt);
+            stable_rate
+        } else {
+            None
+        };
+        Self::deposit_position_event(pool, asset_id, account);
         debug::info!("Leaving update_user_debt");
 
+        moved_rate
+    }
+
+    /// the position of a user in a pool after it changed, enough to follow positions from events
+    fn deposit_position_event(pool: &Pool<T>, asset_id: T::AssetId, account: T::AccountId) {
+        let (supply, supply_index) = Self::user_supply(asset_id, account.clone())
+            .map_or((Zero::zero(), pool.total_supply_index), |user_supply| (user_supply.amount, user_supply.index));
+        let (debt, debt_index, stable_rate) = Self::user_debt(asset_id, account.clone())
+            .map_or((Zero::zero(), pool.total_debt_index, None), |user_debt| (user_debt.amount, user_debt.index, user_debt.stable_rate));
+        Self::deposit_event(RawEvent::UserPositionUpdated(asset_id, account, supply, supply_index, debt, debt_index, stable_rate));
     }
 
     fn update_pool_supply(pool: &mut Pool<T>, amount: T::Balance, positive: bool) {
@@ -617,18 +1477,741 @@ impl<T: Trait> Module<T> where
         debug::info!("Leaving update_pool_supply");
     }
 
-    fn update_pool_debt(pool: &mut Pool<T>, amount: T::Balance, positive: bool) {
+    /// amount at `stable_rate` also moves the stable debt and its average rate
+    fn update_pool_debt(pool: &mut Pool<T>, amount: T::Balance, stable_rate: Option<FixedU128>, positive: bool) {
         debug::info!("Entering update_pool_debt");
         if positive {
             pool.debt += amount;
         } else {
-            pool.debt -= amount;
+            // stable debts compounding at their own rates can sum up to a bit more than the pool's
+            pool.debt = pool.debt.saturating_sub(amount);
+        }
+        if let Some(rate) = stable_rate {
+            if positive {
+                pool.average_stable_rate = Self::average_rate(pool.stable_debt, pool.average_stable_rate, amount, rate);
+                pool.stable_debt += amount;
+            } else {
+                let stable_debt = pool.stable_debt.saturating_sub(amount);
+                pool.average_stable_rate = if stable_debt.is_zero() {
+                    FixedU128::zero()
+                } else {
+                    (pool.average_stable_rate * FixedU128::saturating_from_rational(pool.stable_debt, stable_debt))
+                        .saturating_sub(rate * FixedU128::saturating_from_rational(amount, stable_debt))
+                };
+                pool.stable_debt = stable_debt;
+            }
+            pool.stable_debt = pool.stable_debt.min(pool.debt);
         }
         debug::info!("Leaving update_pool_debt");
 
     }
 
-    fn _init_pool(id: T::AssetId, can_be_collateral: bool) {
+    /// average of two rates weighted by amount
+    fn average_rate(amount: T::Balance, rate: FixedU128, other_amount: T::Balance, other_rate: FixedU128) -> FixedU128 {
+        let total = amount.saturating_add(other_amount);
+        if total.is_zero() {
+            return FixedU128::zero()
+        }
+        rate * FixedU128::saturating_from_rational(amount, total) + other_rate * FixedU128::saturating_from_rational(other_amount, total)
+    }
+
+    /// supply `amount` of an asset from the signer of `origin`
+    fn do_supply(origin: T::Origin, asset_id: T::AssetId, amount: T::Balance) -> Result {
+        debug::info!("Entering supply");
+        let account = ensure_signed(origin)?;
+
+        // check pool exists and get pool instance
+        let mut pool = Self::pool(asset_id).ok_or(Error::<T>::PoolNotExist)?;
+        ensure!(pool.enabled, Error::<T>::PoolDisabled);
+        ensure!(!Self::is_paused(asset_id, PoolAction::Supply), Error::<T>::ActionPaused);
+        // accrue pool interest
+        Self::accrue_interest(&mut pool);
+        // check supply cap
+        if let Some(supply_cap) = pool.supply_cap {
+            ensure!(pool.supply.saturating_add(amount) <= supply_cap, Error::<T>::SupplyCapExceeded);
+        }
+        Self::ensure_room(&Self::user_supply_set(account.clone()), asset_id)?;
+        // transfer asset
+        T::MultiAsset::transfer(
+            account.clone(),
+            asset_id,
+            Self::account_id(),
+            amount,
+        )?;
+
+        // update user supply
+        Self::update_user_supply(&pool, asset_id, account.clone(), amount, true)?;
+        // update pool supply
+        Self::update_pool_supply(&mut pool, amount, true);
+
+        Self::deposit_event(RawEvent::Supplied(asset_id, account.clone(), amount));
+
+        // update user's supply asset set
+        Self::add_to_supply_set(&pool, asset_id, account);
+
+        // commit pool change to storage
+        Self::put_pool(asset_id, pool);
+
+        debug::info!("Leaving supply");
+        Ok(())
+    }
+
+    /// withdraw up to `amount` of an asset to the signer of `origin`
+    fn do_withdraw(origin: T::Origin, asset_id: T::AssetId, amount: T::Balance) -> Result {
+        debug::info!("Entering withdraw");
+
+        let account = ensure_signed(origin)?;
+
+        // check pool exists and get pool instance
+        let mut pool = Self::pool(asset_id).ok_or(Error::<T>::PoolNotExist)?;
+        ensure!(!Self::is_paused(asset_id, PoolAction::Withdraw), Error::<T>::ActionPaused);
+        // the position is only valued if there is debt
+        if !Self::user_debt_set(account.clone()).is_empty() {
+            Self::ensure_fresh_prices(account.clone())?;
+        }
+        // accrue pool interest
+        Self::accrue_interest(&mut pool);
+
+        // accrue user's interest
+        Self::accrue_supply_with_interest(&pool, asset_id, account.clone())?;
+
+        // pre-check amount
+        // supply can not be zero (if so it will be eliminated)
+        let mut amount = amount;
+        if let Some(user_supply) = Self::user_supply(asset_id, account.clone()) {
+            if user_supply.amount < amount {
+                amount = user_supply.amount;
+            }
+        } else {
+            Err(Error::<T>::UserNoSupply)?
+        }
+
+        // check collateral 
+        let (_, mut converted_supply, converted_borrow) = Self::get_user_info(account.clone());
+        if Self::is_collateral(&pool, asset_id, account.clone()) {
+            let price = Self::oracle_price(asset_id);
+            let collateral_factor = Self::collateral_factor_in_emode(&pool, asset_id, &Self::user_emode(account.clone()));
+            converted_supply = converted_supply.saturating_sub((price * collateral_factor).saturating_mul_int(amount));
+        }
+        ensure!(converted_borrow <= converted_supply, Error::<T>::BelowCollateralFactor);
+
+        // check pool cash = (deposit - borrow) > amount
+        if pool.supply.saturating_sub(pool.debt) < amount {
+            Err(Error::<T>::NotEnoughLiquidity)?
+        }
+
+        // transfer asset to user
+        T::MultiAsset::transfer(
+            Self::account_id(),
+            asset_id,
+            account.clone(),
+            amount,
+        )?;
+
+        // update user supply
+        Self::update_user_supply(&pool, asset_id, account.clone(), amount, false)?;
+
+        // update pool supply
+        Self::update_pool_supply(&mut pool, amount, false);
+
+        Self::deposit_event(RawEvent::Withdrawn(asset_id, account, amount));
+
+        // commit pool change to storage
+        Self::put_pool(asset_id, pool);
+
+        debug::info!("Leaving withdraw");
+        Ok(())
+    }
+
+    /// repay debt of `target_user` for the signer of `origin` and seize its collateral at a discount
+    fn do_liquidate(origin: T::Origin, target_user: T::AccountId, pay_asset_id: T::AssetId, get_asset_id: T::AssetId, pay_asset_amount: T::Balance) -> Result {
+        debug::info!("Entering liquidate");
+        let account = ensure_signed(origin)?;
+
+        // check pool exists and get pool instances
+        // check if get_asset_id is enabled as collateral
+        let mut get_pool = Self::pool(get_asset_id).ok_or(Error::<T>::PoolNotExist)?;
+        ensure!(Self::is_collateral(&get_pool, get_asset_id, target_user.clone()), Error::<T>::AssetNotCollateral);
+
+        let mut pay_pool = Self::pool(pay_asset_id).ok_or(Error::<T>::PoolNotExist)?;
+        ensure!(
+            !Self::is_paused(get_asset_id, PoolAction::Liquidate) && !Self::is_paused(pay_asset_id, PoolAction::Liquidate),
+            Error::<T>::ActionPaused
+        );
+        Self::ensure_fresh_prices(target_user.clone())?;
+
+        // 2 accrue interest of pay and get asset
+        Self::accrue_interest(&mut pay_pool);
+        Self::accrue_interest(&mut get_pool);
+
+        // accrue target user's interest
+        Self::accrue_supply_with_interest(&get_pool, get_asset_id, target_user.clone())?;
+        Self::accrue_debt_with_interest(&pay_pool, pay_asset_id, target_user.clone());
+
+        // 3 check if target user is under liquidation condition
+        let (_, _, liquidation_supply, converted_borrow) = Self::get_user_info_in_emode(target_user.clone(), &Self::user_emode(target_user.clone()));
+        ensure!(converted_borrow > liquidation_supply, Error::<T>::AboveLiquidationThreshold);
+
+        // 4 check if liquidation % is more than threshold 
+        // if target user supply is too small, enable total liquidation
+        let target_user_supply = Self::user_supply(get_asset_id, target_user.clone()).ok_or(Error::<T>::UserNoSupply)?;
+        let get_price = Self::oracle_price(get_asset_id);
+        let pay_price = Self::oracle_price(pay_asset_id);
+
+        let get_limit = Self::seizable_collateral(&get_pool, get_price, target_user_supply.amount);
+        let target_user_debt = Self::user_debt(pay_asset_id, target_user.clone()).ok_or(Error::<T>::UserNoSupply)?;
+
+        let (pay_asset_amount, get_asset_amount) = Self::liquidation_amounts(
+            &get_pool, get_price, pay_price, get_limit, target_user_debt.amount, pay_asset_amount,
+        );
+
+        // 5 transfer token from arbitrager
+        T::MultiAsset::transfer(
+            account.clone(),
+            pay_asset_id,
+            Self::account_id(),
+            pay_asset_amount,
+        )?;            
+
+        // 6 transfer collateral to arbitrager
+        T::MultiAsset::transfer(
+            Self::account_id(),
+            get_asset_id,
+            account.clone(),
+            get_asset_amount,
+        )?;
+        // 7 recalculate target user's borrow and supply in 2 pools
+        Self::update_user_supply(&get_pool, get_asset_id, target_user.clone(), get_asset_amount, false)?;
+        let stable_rate = Self::update_user_debt(&pay_pool, pay_asset_id, target_user.clone(), pay_asset_amount, false, None);
+        Self::update_pool_supply(&mut get_pool, get_asset_amount, false);
+        Self::update_pool_debt(&mut pay_pool, pay_asset_amount, stable_rate, false);
+
+        // update pools
+        Self::put_pool(get_asset_id, get_pool);
+        Self::put_pool(pay_asset_id, pay_pool);
+
+        Self::deposit_event(RawEvent::Liquidated(pay_asset_id, get_asset_id, account, target_user, pay_asset_amount, get_asset_amount));
+
+        debug::info!("Leaving liquidate");
+
+        Ok(())
+    }
+
+    /// put the collateral of an unhealthy `target_user` up in an auction
+    fn do_start_auction(origin: T::Origin, target_user: T::AccountId, pay_asset_id: T::AssetId, get_asset_id: T::AssetId) -> Result {
+        debug::info!("Entering start_auction");
+        ensure_signed(origin)?;
+
+        ensure!(Self::user_auction(target_user.clone()).is_none(), Error::<T>::AuctionInProgress);
+
+        let mut get_pool = Self::pool(get_asset_id).ok_or(Error::<T>::PoolNotExist)?;
+        ensure!(Self::is_collateral(&get_pool, get_asset_id, target_user.clone()), Error::<T>::AssetNotCollateral);
+        let mut pay_pool = Self::pool(pay_asset_id).ok_or(Error::<T>::PoolNotExist)?;
+        ensure!(
+            !Self::is_paused(get_asset_id, PoolAction::Liquidate) && !Self::is_paused(pay_asset_id, PoolAction::Liquidate),
+            Error::<T>::ActionPaused
+        );
+        Self::ensure_fresh_prices(target_user.clone())?;
+
+        Self::accrue_interest(&mut pay_pool);
+        Self::accrue_interest(&mut get_pool);
+        Self::accrue_supply_with_interest(&get_pool, get_asset_id, target_user.clone())?;
+        Self::accrue_debt_with_interest(&pay_pool, pay_asset_id, target_user.clone());
+
+        let (_, _, liquidation_supply, converted_borrow) = Self::get_user_info_in_emode(target_user.clone(), &Self::user_emode(target_user.clone()));
+        ensure!(converted_borrow > liquidation_supply, Error::<T>::AboveLiquidationThreshold);
+
+        let target_user_supply = Self::user_supply(get_asset_id, target_user.clone()).ok_or(Error::<T>::UserNoSupply)?;
+        let target_user_debt = Self::user_debt(pay_asset_id, target_user.clone()).ok_or(Error::<T>::UserNoDebt)?;
+        let get_price = Self::oracle_price(get_asset_id);
+        let pay_price = Self::oracle_price(pay_asset_id);
+        let collateral = Self::seizable_collateral(&get_pool, get_price, target_user_supply.amount);
+
+        // the pallet holds the collateral until it is sold or returned
+        Self::update_user_supply(&get_pool, get_asset_id, target_user.clone(), collateral, false)?;
+        Self::update_pool_supply(&mut get_pool, collateral, false);
+        Self::put_pool(get_asset_id, get_pool);
+        Self::put_pool(pay_asset_id, pay_pool);
+
+        let config = Self::auction_config();
+        let oracle_price = get_price / pay_price;
+        let now = Self::now();
+        let auction = Auction::<T> {
+            target_user: target_user.clone(),
+            pay_asset_id,
+            get_asset_id,
+            collateral,
+            debt: target_user_debt.amount,
+            start_price: oracle_price * config.start_factor,
+            end_price: oracle_price * config.end_factor,
+            started_at: now,
+            ends_at: now.saturating_add(config.duration),
+        };
+        let auction_id = Self::next_auction_id();
+        NextAuctionId::put(auction_id.wrapping_add(1));
+        AuctionExpiries::append(auction.ends_at, auction_id);
+        AuctionExpiryTimes::mutate(|times| if let Err(index) = times.binary_search(&auction.ends_at) {
+            times.insert(index, auction.ends_at);
+        });
+        Auctions::<T>::insert(auction_id, auction);
+        UserAuction::<T>::insert(target_user.clone(), auction_id);
+
+        Self::deposit_event(RawEvent::AuctionStarted(auction_id, target_user, pay_asset_id, get_asset_id, collateral));
+        debug::info!("Leaving start_auction");
+
+        Ok(())
+    }
+
+    /// buy collateral of an auction for the signer of `origin`
+    fn do_bid(origin: T::Origin, auction_id: AuctionId, get_asset_amount: T::Balance) -> Result {
+        debug::info!("Entering bid");
+        let account = ensure_signed(origin)?;
+
+        let mut auction = Self::auction(auction_id).ok_or(Error::<T>::AuctionNotExist)?;
+        let now = Self::now();
+        ensure!(now < auction.ends_at, Error::<T>::AuctionExpired);
+        ensure!(
+            !Self::is_paused(auction.get_asset_id, PoolAction::Liquidate) && !Self::is_paused(auction.pay_asset_id, PoolAction::Liquidate),
+            Error::<T>::ActionPaused
+        );
+
+        let mut pay_pool = Self::pool(auction.pay_asset_id).ok_or(Error::<T>::PoolNotExist)?;
+        Self::accrue_interest(&mut pay_pool);
+        Self::accrue_debt_with_interest(&pay_pool, auction.pay_asset_id, auction.target_user.clone());
+
+        // the target may have repaid part of the debt meanwhile
+        let debt = Self::user_debt(auction.pay_asset_id, auction.target_user.clone())
+            .map_or_else(Zero::zero, |user_debt| user_debt.amount)
+            .min(auction.debt);
+        let price = Self::auction_price(&auction, now);
+
+        let mut get_asset_amount = get_asset_amount.min(auction.collateral);
+        let mut pay_asset_amount = price.saturating_mul_int(get_asset_amount);
+        if pay_asset_amount > debt {
+            pay_asset_amount = debt;
+            get_asset_amount = (FixedU128::one() / price).saturating_mul_int(debt).min(auction.collateral);
+        }
+
+        T::MultiAsset::transfer(
+            account.clone(),
+            auction.pay_asset_id,
+            Self::account_id(),
+            pay_asset_amount,
+        )?;
+        T::MultiAsset::transfer(
+            Self::account_id(),
+            auction.get_asset_id,
+            account.clone(),
+            get_asset_amount,
+        )?;
+
+        let stable_rate = Self::update_user_debt(&pay_pool, auction.pay_asset_id, auction.target_user.clone(), pay_asset_amount, false, None);
+        Self::update_pool_debt(&mut pay_pool, pay_asset_amount, stable_rate, false);
+        Self::put_pool(auction.pay_asset_id, pay_pool);
+
+        auction.collateral -= get_asset_amount;
+        auction.debt -= pay_asset_amount;
+        Self::deposit_event(RawEvent::AuctionBid(auction_id, account, pay_asset_amount, get_asset_amount));
+
+        if auction.collateral.is_zero() || pay_asset_amount == debt {
+            Self::remove_auction_expiry(auction_id, auction.ends_at);
+            Self::close_auction(auction_id, auction)?;
+        } else {
+            Auctions::<T>::insert(auction_id, auction);
+        }
+        debug::info!("Leaving bid");
+
+        Ok(())
+    }
+
+    /// borrow against the collateral of `account` to `receiver`, at the variable rate or at a stable rate locked now
+    fn do_borrow(account: T::AccountId, receiver: T::AccountId, asset_id: T::AssetId, amount: T::Balance, stable: bool) -> Result {
+        debug::info!("Entering borrow");
+
+        // check pool exists and get pool instance
+        let mut pool = Self::pool(asset_id).ok_or(Error::<T>::PoolNotExist)?;
+        ensure!(pool.enabled, Error::<T>::PoolDisabled);
+        ensure!(!Self::is_paused(asset_id, PoolAction::Borrow), Error::<T>::ActionPaused);
+        ensure!(!stable || pool.stable_rate_config.is_some(), Error::<T>::StableBorrowDisabled);
+        if let Some(user_debt) = Self::user_debt(asset_id, account.clone()) {
+            ensure!(user_debt.stable_rate.is_some() == stable, Error::<T>::RateModeMismatch);
+        }
+
+        // users in efficiency mode only borrow within the category
+        let emode = Self::user_emode(account.clone());
+        if let Some((category_id, _)) = emode {
+            ensure!(Self::asset_emode_category(asset_id) == Some(category_id), Error::<T>::AssetNotInEModeCategory);
+        }
+
+        // accrue interest
+        Self::accrue_interest(&mut pool);
+
+        // check pool cash = (deposit - borrow) > amount
+        if pool.supply.saturating_sub(pool.debt) < amount {
+            Err(Error::<T>::NotEnoughLiquidity)?
+        }
+
+        // check borrow cap
+        if let Some(borrow_cap) = pool.borrow_cap {
+            ensure!(pool.debt.saturating_add(amount) <= borrow_cap, Error::<T>::BorrowCapExceeded);
+        }
+        Self::ensure_room(&Self::user_debt_set(account.clone()), asset_id)?;
+        let price = Self::fresh_price(asset_id)?;
+        Self::ensure_fresh_prices(account.clone())?;
+
+        // need to accrue user interest first
+        Self::accrue_debt_with_interest(&pool, asset_id, account.clone());
+
+        // check collateral
+        let (_, converted_supply, converted_borrow) = Self::get_user_info(account.clone());
+        let converted_borrow = converted_borrow + price.saturating_mul_int(amount);
+        ensure!(converted_borrow <= converted_supply, Error::<T>::BelowCollateralFactor);
+
+        // the rate is locked after the borrow moves utilization
+        let stable_rate = if stable {
+            Self::stable_rate_internal(&pool, amount)
+        } else {
+            None
+        };
+
+        // transfer asset to receiver
+        T::MultiAsset::transfer(
+            Self::account_id(),
+            asset_id,
+            receiver,
+            amount,
+        )?;
+        // update user Borrow
+        Self::update_user_debt(&pool, asset_id, account.clone(), amount, true, stable_rate);
+        // update pool borrow
+        Self::update_pool_debt(&mut pool, amount, stable_rate, true);
+
+        Self::deposit_event(RawEvent::Borrowed(asset_id, account.clone(), amount));
+        if let Some(user_rate) = Self::user_debt(asset_id, account.clone()).and_then(|user_debt| user_debt.stable_rate) {
+            Self::deposit_event(RawEvent::StableRateLocked(asset_id, account.clone(), user_rate));
+        }
+
+        // update user's debt asset set
+        let mut assets = Self::user_debt_set(account.clone());
+        if Self::set_insert(&mut assets, asset_id) {
+            UserDebtSet::<T>::insert(account, assets);
+        }
+
+        // commit pool change to storage
+        Self::put_pool(asset_id, pool);
+        debug::info!("Leaving borrow");
+
+        Ok(())
+    }
+
+    /// pay back a user's debt, at most the debt with interest; returns the amount repaid
+    fn do_repay(payer: T::AccountId, account: T::AccountId, asset_id: T::AssetId, amount: T::Balance) -> sp_std::result::Result<T::Balance, DispatchError> {
+        debug::info!("Entering repay");
+
+        // check pool exists and get pool instance
+        let mut pool = Self::pool(asset_id).ok_or(Error::<T>::PoolNotExist)?;
+        ensure!(!Self::is_paused(asset_id, PoolAction::Repay), Error::<T>::ActionPaused);
+        // accrue interest
+        Self::accrue_interest(&mut pool);
+
+        // accrue user's interest
+        Self::accrue_debt_with_interest(&pool, asset_id, account.clone());
+
+        // pre-check amount
+        let mut amount = amount;
+        if let Some(user_debt) = Self::user_debt(asset_id, account.clone()) {
+            if user_debt.amount < amount {
+                amount = user_debt.amount;
+            }
+        } else {
+            Err(Error::<T>::UserNoDebt)?
+        }
+
+        // transfer token from payer
+        T::MultiAsset::transfer(
+            payer,
+            asset_id,
+            Self::account_id(),
+            amount,
+        )?;
+
+        // update user Borrow
+        let stable_rate = Self::update_user_debt(&pool, asset_id, account, amount, false, None);
+        // update pool borrow
+        Self::update_pool_debt(&mut pool, amount, stable_rate, false);
+
+        // commit pool change to storage
+        Self::put_pool(asset_id, pool);
+        debug::info!("Leaving repay");
+
+        Ok(amount)
+    }
+
+    fn do_liquidate_batch(origin: T::Origin, target_user: T::AccountId, liquidations: Vec<(T::AssetId, T::AssetId, T::Balance)>) -> Result {
+        let account = ensure_signed(origin)?;
+        ensure!(liquidations.len() as u32 <= MAX_BATCH_LIQUIDATIONS, Error::<T>::TooManyLiquidations);
+        // every pool of the batch is accrued once, with the target's position in it
+        let mut assets: Vec<T::AssetId> = Vec::new();
+        for (pay_asset_id, get_asset_id, _) in liquidations.iter() {
+            let get_pool = Self::pool(get_asset_id).ok_or(Error::<T>::PoolNotExist)?;
+            ensure!(Self::is_collateral(&get_pool, *get_asset_id, target_user.clone()), Error::<T>::AssetNotCollateral);
+            ensure!(Pools::<T>::contains_key(pay_asset_id), Error::<T>::PoolNotExist);
+            ensure!(
+                !Self::is_paused(*get_asset_id, PoolAction::Liquidate) && !Self::is_paused(*pay_asset_id, PoolAction::Liquidate),
+                Error::<T>::ActionPaused
+            );
+            for asset_id in &[*pay_asset_id, *get_asset_id] {
+                if !assets.contains(asset_id) {
+                    assets.push(*asset_id);
+                }
+            }
+        }
+        Self::ensure_fresh_prices(target_user.clone())?;
+        for asset_id in assets {
+            let mut pool = Self::pool(asset_id).ok_or(Error::<T>::PoolNotExist)?;
+            Self::accrue_interest(&mut pool);
+            Self::accrue_supply_with_interest(&pool, asset_id, target_user.clone())?;
+            Self::accrue_debt_with_interest(&pool, asset_id, target_user.clone());
+            Self::put_pool(asset_id, pool);
+        }
+
+        let (_, _, liquidation_supply, converted_borrow) = Self::get_user_info_in_emode(target_user.clone(), &Self::user_emode(target_user.clone()));
+        ensure!(converted_borrow > liquidation_supply, Error::<T>::AboveLiquidationThreshold);
+
+        // collateral still seizable in the batch, under the close factor of the supply before it
+        let mut get_limits: Vec<(T::AssetId, T::Balance)> = Vec::new();
+        let mut liquidated = Vec::new();
+        for (pay_asset_id, get_asset_id, pay_asset_amount) in liquidations {
+            let mut get_pool = Self::pool(get_asset_id).ok_or(Error::<T>::PoolNotExist)?;
+            let get_price = Self::oracle_price(get_asset_id);
+            let pay_price = Self::oracle_price(pay_asset_id);
+            let limit_index = match get_limits.iter().position(|(asset_id, _)| *asset_id == get_asset_id) {
+                Some(index) => index,
+                None => {
+                    let supply = Self::user_supply(get_asset_id, target_user.clone()).ok_or(Error::<T>::UserNoSupply)?;
+                    get_limits.push((get_asset_id, Self::seizable_collateral(&get_pool, get_price, supply.amount)));
+                    get_limits.len() - 1
+                }
+            };
+            let debt = Self::user_debt(pay_asset_id, target_user.clone()).map_or_else(Zero::zero, |user_debt| user_debt.amount);
+
+            let (pay_asset_amount, get_asset_amount) = Self::liquidation_amounts(
+                &get_pool, get_price, pay_price, get_limits[limit_index].1, debt, pay_asset_amount,
+            );
+            if pay_asset_amount.is_zero() {
+                continue
+            }
+            get_limits[limit_index].1 -= get_asset_amount;
+
+            T::MultiAsset::transfer(account.clone(), pay_asset_id, Self::account_id(), pay_asset_amount)?;
+            T::MultiAsset::transfer(Self::account_id(), get_asset_id, account.clone(), get_asset_amount)?;
+
+            Self::update_user_supply(&get_pool, get_asset_id, target_user.clone(), get_asset_amount, false)?;
+            Self::update_pool_supply(&mut get_pool, get_asset_amount, false);
+            Self::put_pool(get_asset_id, get_pool);
+            // the pay pool is read after the supply update, in case both are the same
+            let mut pay_pool = Self::pool(pay_asset_id).ok_or(Error::<T>::PoolNotExist)?;
+            let stable_rate = Self::update_user_debt(&pay_pool, pay_asset_id, target_user.clone(), pay_asset_amount, false, None);
+            Self::update_pool_debt(&mut pay_pool, pay_asset_amount, stable_rate, false);
+            Self::put_pool(pay_asset_id, pay_pool);
+
+            liquidated.push((pay_asset_id, get_asset_id, pay_asset_amount, get_asset_amount));
+        }
+        ensure!(!liquidated.is_empty(), Error::<T>::NothingToLiquidate);
+
+        Self::deposit_event(RawEvent::BatchLiquidated(account, target_user, liquidated));
+
+        Ok(())
+    }
+
+    fn do_flash_loan(origin: T::Origin, asset_id: T::AssetId, amount: T::Balance, call: <T as Trait>::Call) -> Result {
+        let account = ensure_signed(origin)?;
+        debug::info!("Entering flash_loan");
+
+        let mut pool = Self::pool(asset_id).ok_or(Error::<T>::PoolNotExist)?;
+        ensure!(pool.enabled, Error::<T>::PoolDisabled);
+        ensure!(!Self::is_paused(asset_id, PoolAction::FlashLoan), Error::<T>::ActionPaused);
+
+        Self::accrue_interest(&mut pool);
+
+        // pool cash = deposit + reserves - borrow
+        ensure!(amount <= (pool.supply + pool.reserves).saturating_sub(pool.debt), Error::<T>::NotEnoughLiquidity);
+        let fee = Self::flash_loan_fee().saturating_mul_int(amount);
+
+        // the call may use the pool, so it must see the accrued pool
+        Self::put_pool(asset_id, pool);
+
+        T::MultiAsset::transfer(
+            Self::account_id(),
+            asset_id,
+            account.clone(),
+            amount,
+        )?;
+
+        call.dispatch(frame_system::RawOrigin::Signed(account.clone()).into())
+            .map_err(|e| e.error)?;
+
+        T::MultiAsset::transfer(
+            account.clone(),
+            asset_id,
+            Self::account_id(),
+            amount.saturating_add(fee),
+        ).map_err(|_| Error::<T>::FlashLoanNotRepaid)?;
+
+        // the fee is extra yield of suppliers
+        let mut pool = Self::pool(asset_id).ok_or(Error::<T>::PoolNotExist)?;
+        Self::distribute_to_suppliers(&mut pool, fee);
+        Self::put_pool(asset_id, pool);
+
+        Self::deposit_event(RawEvent::FlashLoaned(asset_id, account, amount, fee));
+        debug::info!("Leaving flash_loan");
+
+        Ok(())
+    }
+
+    /// collateral one liquidation can seize from a supply, all of it if worth less than the dust threshold
+    fn seizable_collateral(pool: &Pool<T>, price: FixedU128, supply: T::Balance) -> T::Balance {
+        if price.saturating_mul_int(supply) < Self::dust_threshold() {
+            supply
+        } else {
+            pool.close_factor.saturating_mul_int(supply)
+        }
+    }
+
+    /// debt repaid and collateral seized by a liquidation paying up to `amount`,
+    /// within the `debt` and the `get_limit` of collateral that can be seized
+    fn liquidation_amounts(
+        get_pool: &Pool<T>,
+        get_price: FixedU128,
+        pay_price: FixedU128,
+        get_limit: T::Balance,
+        debt: T::Balance,
+        amount: T::Balance,
+    ) -> (T::Balance, T::Balance) {
+        let pay_limit = (get_price / pay_price * get_pool.discount_factor).saturating_mul_int(get_limit);
+        let pay_amount = amount.min(pay_limit).min(debt);
+
+        // TODO: check rounding errors due to discount_factor
+        // paying the limit seizes the limit, so no dust is left by rounding
+        let get_amount = if pay_amount == pay_limit {
+            get_limit
+        } else {
+            (pay_price / get_price / get_pool.discount_factor).saturating_mul_int(pay_amount)
+        };
+        (pay_amount, get_amount.min(get_limit))
+    }
+
+    /// price of an auction's collateral in its debt asset, descending linearly to the end price
+    fn auction_price(auction: &Auction<T>, now: u64) -> FixedU128 {
+        let duration = auction.ends_at.saturating_sub(auction.started_at);
+        if duration == 0 {
+            return auction.end_price
+        }
+        let elapsed = now.saturating_sub(auction.started_at).min(duration);
+        let progress = FixedU128::saturating_from_rational(elapsed, duration);
+        auction.start_price.saturating_sub(auction.start_price.saturating_sub(auction.end_price).saturating_mul(progress))
+    }
+
+    /// close the auctions expired by `now` in order of expiry, up to `MAX_AUCTION_CLOSES`.
+    /// returns the number of auctions closed
+    fn close_expired_auctions(now: u64) -> u32 {
+        let mut times = Self::auction_expiry_times();
+        let (mut expired, mut closed) = (0, 0);
+        while expired < times.len() && times[expired] <= now && closed < MAX_AUCTION_CLOSES {
+            let at = times[expired];
+            let mut auction_ids = AuctionExpiries::take(at);
+            while closed < MAX_AUCTION_CLOSES && !auction_ids.is_empty() {
+                let auction_id = auction_ids.remove(0);
+                if let Some(auction) = Self::auction(auction_id) {
+                    if let Err(e) = Self::close_expired_auction(auction_id, auction) {
+                        debug::error!("Failed to close auction {}: {:?}", auction_id, e);
+                    }
+                }
+                closed += 1;
+            }
+            if !auction_ids.is_empty() {
+                // the rest waits for the next block
+                AuctionExpiries::insert(at, auction_ids);
+                break
+            }
+            expired += 1;
+        }
+        if expired > 0 {
+            times.drain(..expired);
+            AuctionExpiryTimes::put(times);
+        }
+        closed
+    }
+
+    /// take an auction out of the expiry queue, and its expiry time too once no auction is left at it
+    fn remove_auction_expiry(auction_id: AuctionId, ends_at: u64) {
+        let mut auction_ids = AuctionExpiries::take(ends_at);
+        auction_ids.retain(|id| *id != auction_id);
+        if auction_ids.is_empty() {
+            AuctionExpiryTimes::mutate(|times| times.retain(|at| *at != ends_at));
+        } else {
+            AuctionExpiries::insert(ends_at, auction_ids);
+        }
+    }
+
+    /// close an auction past its end, its writes are reverted alone if it fails
+    #[transactional]
+    fn close_expired_auction(auction_id: AuctionId, auction: Auction<T>) -> Result {
+        Self::close_auction(auction_id, auction)
+    }
+
+    /// end an auction, the unsold collateral goes back to the target user
+    fn close_auction(auction_id: AuctionId, auction: Auction<T>) -> Result {
+        Auctions::<T>::remove(auction_id);
+        UserAuction::<T>::remove(auction.target_user.clone());
+
+        if !auction.collateral.is_zero() {
+            if let Some(mut pool) = Self::pool(auction.get_asset_id) {
+                Self::accrue_interest(&mut pool);
+                Self::update_user_supply(&pool, auction.get_asset_id, auction.target_user.clone(), auction.collateral, true)?;
+                Self::update_pool_supply(&mut pool, auction.collateral, true);
+                Self::put_pool(auction.get_asset_id, pool);
+
+                // it was a collateral when the auction started, so it goes back in the sets regardless of their cap
+                let asset_id = auction.get_asset_id;
+                UserSupplySet::<T>::mutate(auction.target_user.clone(), |assets| Self::set_insert(assets, asset_id));
+                UserCollateralSet::<T>::mutate(auction.target_user.clone(), |collaterals| Self::set_insert(collaterals, asset_id));
+            }
+        }
+
+        Self::deposit_event(RawEvent::AuctionClosed(auction_id, auction.collateral));
+        Ok(())
+    }
+
+    /// raise the supply index so that `amount` is shared by all suppliers,
+    /// it goes to reserves if nobody supplies
+    fn distribute_to_suppliers(pool: &mut Pool<T>, amount: T::Balance) {
+        if pool.supply.is_zero() {
+            pool.reserves += amount;
+            return
+        }
+        let multiplier = FixedU128::saturating_from_rational(pool.supply + amount, pool.supply);
+        pool.total_supply_index = pool.total_supply_index * multiplier;
+        pool.supply += amount;
+    }
+
+    /// commit a pool change to storage, with its indices and totals for indexers
+    fn put_pool(id: T::AssetId, pool: Pool<T>) {
+        Self::deposit_event(RawEvent::InterestAccrued(id, pool.total_supply_index, pool.total_debt_index, pool.supply, pool.debt));
+        Pools::<T>::insert(id, pool);
+    }
+
+    fn update_pool_parameters(id: T::AssetId, update: impl FnOnce(&mut Pool<T>)) -> Result {
+        let mut pool = Self::pool(id).ok_or(Error::<T>::PoolNotExist)?;
+        update(&mut pool);
+        Self::put_pool(id, pool);
+
+        Self::deposit_event(RawEvent::PoolParametersUpdated(id));
+
+        Ok(())
+    }
+
+    fn _init_pool(id: T::AssetId, can_be_collateral: bool, interest_rate_model: InterestRateModel) {
 
         let pool = Pool::<T> {
             enabled: true,
@@ -636,17 +2219,122 @@ impl<T: Trait> Module<T> where
             asset: id,
             supply: T::Balance::zero(),
             debt: T::Balance::zero(),
-            safe_factor: FixedU128::saturating_from_rational(7, 10),
+            collateral_factor: FixedU128::saturating_from_rational(7, 10),
+            liquidation_threshold: FixedU128::saturating_from_rational(8, 10),
             close_factor: FixedU128::one(),
             discount_factor: FixedU128::saturating_from_rational(95, 100),
             total_supply_index: FixedU128::one(),
             total_debt_index: FixedU128::one(),
-            last_updated: <frame_system::Module<T>>::block_number(),
-            utilization_factor: FixedU128::saturating_from_rational(385, 10000000000u64),
-            initial_interest_rate: FixedU128::saturating_from_rational(385, 100000000000u64),
+            last_updated: Self::now(),
+            interest_rate_model,
+            reserve_factor: FixedU128::zero(),
+            reserves: T::Balance::zero(),
+            supply_cap: None,
+            borrow_cap: None,
+            stable_debt: T::Balance::zero(),
+            average_stable_rate: FixedU128::zero(),
+            stable_rate_config: None,
         };
 
         Pools::<T>::insert(id, pool);
+        let index = Self::index_pool(id);
+        let ktoken = Self::create_ktoken(id);
+        if let Some(index) = index {
+            Self::deposit_event(RawEvent::PoolCreated(id, index, ktoken));
+        }
+    }
+
+    /// give a pool the next index, none if all indices are taken
+    pub(crate) fn index_pool(id: T::AssetId) -> Option<PoolIndex> {
+        let index = Self::pool_count();
+        if index >= MAX_POOLS {
+            return None
+        }
+        PoolIndices::<T>::insert(id, index);
+        PoolAssets::<T>::insert(index, id);
+        PoolCount::put(index + 1);
+        Some(index)
+    }
+
+    /// the assets of the pools in a set
+    pub fn assets_in(set: PoolSet) -> Vec<T::AssetId> {
+        set.iter().filter_map(Self::pool_asset).collect()
+    }
+
+    /// if the pool of an asset is in a set
+    fn set_contains(set: &PoolSet, asset_id: T::AssetId) -> bool {
+        match Self::pool_index(asset_id) {
+            Some(index) => set.contains(index),
+            None => false,
+        }
+    }
+
+    /// add the pool of an asset to a set, false if it was already there
+    fn set_insert(set: &mut PoolSet, asset_id: T::AssetId) -> bool {
+        match Self::pool_index(asset_id) {
+            Some(index) if !set.contains(index) => set.insert(index),
+            _ => false,
+        }
+    }
+
+    fn set_remove(set: &mut PoolSet, asset_id: T::AssetId) {
+        if let Some(index) = Self::pool_index(asset_id) {
+            set.remove(index);
+        }
+    }
+
+    /// a user can enter at most `MAX_USER_ASSETS` assets of a set, assets already in it are fine
+    fn ensure_room(set: &PoolSet, asset_id: T::AssetId) -> Result {
+        ensure!(Self::set_contains(set, asset_id) || set.len() < MAX_USER_ASSETS, Error::<T>::TooManyAssets);
+        Ok(())
+    }
+
+    /// add an asset to a user's supply set, the first supply of an asset is enabled as collateral by default
+    fn add_to_supply_set(pool: &Pool<T>, asset_id: T::AssetId, account: T::AccountId) {
+        let mut assets = Self::user_supply_set(account.clone());
+        if Self::set_insert(&mut assets, asset_id) {
+            UserSupplySet::<T>::insert(account.clone(), assets);
+
+            if pool.can_be_collateral {
+                UserCollateralSet::<T>::mutate(account.clone(), |collaterals| Self::set_insert(collaterals, asset_id));
+                Self::deposit_event(RawEvent::CollateralEnabled(asset_id, account));
+            }
+        }
+    }
+
+    /// create the kToken of a pool
+    fn create_ktoken(id: T::AssetId) -> T::AssetId {
+        let ktoken = T::MultiAsset::create_asset();
+        KTokens::<T>::insert(id, ktoken);
+        KTokenUnderlying::<T>::insert(ktoken, id);
+        ktoken
+    }
+
+    /// kTokens standing for a supply, at the exchange rate of the supply index
+    fn to_ktokens(amount: T::Balance, index: FixedU128) -> T::Balance {
+        if index.is_zero() {
+            return T::Balance::zero()
+        }
+        multiply_by_rational(amount.saturated_into(), FixedU128::accuracy(), index.into_inner())
+            .unwrap_or(u128::MAX)
+            .saturated_into()
+    }
+
+    /// mint or burn kTokens of a user until they match its supply
+    fn sync_ktokens(asset_id: T::AssetId, user: T::AccountId) -> Result {
+        let ktoken = match Self::ktoken(asset_id) {
+            Some(ktoken) => ktoken,
+            None => return Ok(()),
+        };
+        let target = Self::user_supply(asset_id, user.clone())
+            .map_or_else(T::Balance::zero, |user_supply| Self::to_ktokens(user_supply.amount, user_supply.index));
+        let balance = T::MultiAsset::balance(ktoken, user.clone());
+        if target > balance {
+            T::MultiAsset::mint(ktoken, user, target - balance)?;
+        } else if target < balance {
+            T::MultiAsset::burn(ktoken, user, balance - target)?;
+        }
+        Ok(())
     }
 
     fn supply_rate_internal(pool: &Pool<T>) -> FixedU128 {
@@ -660,7 +2348,8 @@ impl<T: Trait> Module<T> where
 
         let utilization_ratio = FixedU128::saturating_from_rational(pool.debt, pool.supply);
         debug::info!("Leaving supply_rate_internal");
-        Self::debt_rate_internal(pool) * utilization_ratio
+        // the reserve share of debt interest does not go to suppliers
+        Self::average_debt_rate(pool) * utilization_ratio * (FixedU128::one() - pool.reserve_factor)
 
     }
 
@@ -669,15 +2358,33 @@ impl<T: Trait> Module<T> where
 
         if pool.supply == T::Balance::zero() {
             debug::info!("Leaving debt_rate_internal");
-            return pool.initial_interest_rate;
+            return pool.interest_rate_model.debt_rate(FixedU128::zero());
         } 
 
         let utilization_ratio = FixedU128::saturating_from_rational(pool.debt, pool.supply);
         debug::info!("Leaving debt_rate_internal");
-        pool.initial_interest_rate + pool.utilization_factor * utilization_ratio
+        pool.interest_rate_model.debt_rate(utilization_ratio)
 
     }
 
+    /// rate of all debt, the variable and the stable rates weighted by amount
+    fn average_debt_rate(pool: &Pool<T>) -> FixedU128 {
+        let debt_rate = Self::debt_rate_internal(pool);
+        if pool.stable_debt.is_zero() {
+            return debt_rate
+        }
+        Self::average_rate(pool.debt.saturating_sub(pool.stable_debt), debt_rate, pool.stable_debt, pool.average_stable_rate)
+    }
+
+    /// rate a stable borrow of `amount` locks, the variable rate once it is borrowed plus the premium;
+    /// none if stable borrowing is disabled
+    fn stable_rate_internal(pool: &Pool<T>, amount: T::Balance) -> Option<FixedU128> {
+        let premium = pool.stable_rate_config.as_ref()?.premium;
+        let mut pool = pool.clone();
+        pool.debt = pool.debt.saturating_add(amount);
+        Some(Self::debt_rate_internal(&pool).saturating_add(premium))
+    }
+
     /// runtime apis
 
     pub fn supply_rate(id: T::AssetId) -> FixedU128 {
@@ -713,28 +2420,182 @@ impl<T: Trait> Module<T> where
         Self::debt_rate_internal(&pool)
     }
 
-    /// total supply balance; total converted supply balance; total debt balance;
+    /// rate a stable borrow locks now, none if the pool does not exist or stable borrowing is disabled
+    pub fn stable_debt_rate(id: T::AssetId) -> Option<FixedU128> {
+        Self::pool(id).and_then(|pool| Self::stable_rate_internal(&pool, Zero::zero()))
+    }
+
+    /// protocol reserves of a pool, with interest up to now
+    pub fn get_reserves(id: T::AssetId) -> T::Balance {
+        debug::info!("Entering get_reserves");
+
+        let reserves = Self::pool(id).map(|mut pool| {
+            Self::accrue_interest(&mut pool);
+            pool.reserves
+        }).unwrap_or_else(Zero::zero);
+
+        debug::info!("Leaving get_reserves");
+        reserves
+    }
+
+    /// max total supply of a pool, no limit if none
+    pub fn supply_cap(id: T::AssetId) -> Option<T::Balance> {
+        Self::pool(id).and_then(|pool| pool.supply_cap)
+    }
+
+    /// max total debt of a pool, no limit if none
+    pub fn borrow_cap(id: T::AssetId) -> Option<T::Balance> {
+        Self::pool(id).and_then(|pool| pool.borrow_cap)
+    }
+
+    /// total supply balance; total converted supply balance of collaterals; total debt balance;
+    /// total supply, supply weighted by collateral factors and total debt of a user
     pub fn get_user_info(user: T::AccountId) -> (T::Balance, T::Balance, T::Balance) {
+        let (supply_balance, supply_converted, _, debt_balance) = Self::get_user_info_in_emode(user.clone(), &Self::user_emode(user));
+        (supply_balance, supply_converted, debt_balance)
+    }
+
+    /// every supply and debt of a user with interest, and the values of the position at current prices
+    pub fn get_user_position(user: T::AccountId) -> UserPosition<T::AssetId, T::Balance, FixedU128> {
+        let emode = Self::user_emode(user.clone());
+        let collaterals = Self::user_collateral_set(user.clone());
+        let supplies = Self::user_supply_set(user.clone());
+        let debts = Self::user_debt_set(user.clone()).iter().filter(|index| !supplies.contains(*index));
+
+        let mut position = UserPosition::default();
+        for index in supplies.iter().chain(debts) {
+            let (asset_id, pool) = match Self::pool_asset(index).and_then(|asset_id| Self::pool(asset_id).map(|pool| (asset_id, pool))) {
+                Some(found) => found,
+                None => continue,
+            };
+            let supply = Self::get_user_supply_with_interest(asset_id, user.clone());
+            let debt = Self::get_user_debt_with_interest(asset_id, user.clone());
+            let price = Self::oracle_price(asset_id);
+            let is_collateral = pool.can_be_collateral && collaterals.contains(index);
+            let collateral_factor = Self::collateral_factor_in_emode(&pool, asset_id, &emode);
+            let liquidation_threshold = Self::liquidation_threshold_in_emode(&pool, asset_id, &emode);
+
+            // summed the same way as `get_user_info`, so the values match the risk checks
+            position.supply_value += price.saturating_mul_int(supply);
+            if is_collateral {
+                position.collateral_value += price.saturating_mul_int(supply);
+                position.borrow_limit += (price * collateral_factor).saturating_mul_int(supply);
+                position.liquidation_threshold_value += (price * liquidation_threshold).saturating_mul_int(supply);
+            }
+            position.debt_value += price.saturating_mul_int(debt);
+
+            position.assets.push(AssetPosition {
+                asset_id,
+                supply,
+                debt,
+                price,
+                is_collateral,
+                collateral_factor,
+                liquidation_threshold,
+            });
+        }
+        position.health_factor = if position.debt_value.is_zero() {
+            None
+        } else {
+            Some(FixedU128::saturating_from_rational(position.liquidation_threshold_value, position.debt_value))
+        };
+
+        position
+    }
+
+    /// if the debt of a user is over the liquidation threshold of its collaterals
+    pub fn is_liquidatable(user: T::AccountId) -> bool {
+        let (_, _, liquidation_supply, converted_borrow) = Self::get_user_info_in_emode(user.clone(), &Self::user_emode(user));
+        converted_borrow > liquidation_supply
+    }
+
+    /// most of a debt `liquidate` would repay for a target user right now, and the collateral it seizes for it;
+    /// none if the pair can not be liquidated
+    pub fn max_liquidation(target_user: T::AccountId, pay_asset_id: T::AssetId, get_asset_id: T::AssetId) -> Option<(T::Balance, T::Balance)> {
+        let get_pool = Self::pool(get_asset_id)?;
+        Self::pool(pay_asset_id)?;
+        if !Self::is_collateral(&get_pool, get_asset_id, target_user.clone())
+            || Self::is_paused(get_asset_id, PoolAction::Liquidate)
+            || Self::is_paused(pay_asset_id, PoolAction::Liquidate)
+            || Self::ensure_fresh_prices(target_user.clone()).is_err() {
+            return None
+        }
+
+        let supply = Self::get_user_supply_with_interest(get_asset_id, target_user.clone());
+        let debt = Self::get_user_debt_with_interest(pay_asset_id, target_user);
+        let get_price = Self::oracle_price(get_asset_id);
+        let pay_price = Self::oracle_price(pay_asset_id);
+
+        let get_limit = Self::seizable_collateral(&get_pool, get_price, supply);
+        let (pay_amount, get_amount) = Self::liquidation_amounts(&get_pool, get_price, pay_price, get_limit, debt, debt);
+        if pay_amount.is_zero() {
+            return None
+        }
+
+        Some((pay_amount, get_amount))
+    }
+
+    /// total supply, supply weighted by collateral factors, supply weighted by liquidation thresholds
+    /// and total debt of a user, under the parameters of an efficiency mode category
+    fn get_user_info_in_emode(user: T::AccountId, emode: &Option<(EModeCategoryId, EModeCategory)>) -> (T::Balance, T::Balance, T::Balance, T::Balance) {
         debug::info!("Entering get_user_info");
         let mut supply_balance = T::Balance::zero();
         let mut supply_converted = T::Balance::zero();
-        for asset in Self::user_supply_set(user.clone()).into_iter() {
+        let mut supply_liquidation = T::Balance::zero();
+        let collaterals = Self::user_collateral_set(user.clone());
+        for index in Self::user_supply_set(user.clone()).iter() {
+            let asset = match Self::pool_asset(index) {
+                Some(asset) => asset,
+                None => continue,
+            };
             let amount = Self::get_user_supply_with_interest(asset, user.clone());
-            let price = T::Oracle::get_rate(asset);
+            let price = Self::oracle_price(asset);
             supply_balance += price.saturating_mul_int(amount);
+            // only collaterals count toward the converted supply
             // TODO: optimize this
-            supply_converted += (price * Self::pool(asset).unwrap().safe_factor).saturating_mul_int(amount);
+            let pool = Self::pool(asset).unwrap();
+            if pool.can_be_collateral && collaterals.contains(index) {
+                supply_converted += (price * Self::collateral_factor_in_emode(&pool, asset, emode)).saturating_mul_int(amount);
+                supply_liquidation += (price * Self::liquidation_threshold_in_emode(&pool, asset, emode)).saturating_mul_int(amount);
+            }
         }
 
         let mut debt_balance = T::Balance::zero();
-        for asset in Self::user_debt_set(user.clone()).into_iter() {
+        for asset in Self::assets_in(Self::user_debt_set(user.clone())) {
             let amount = Self::get_user_debt_with_interest(asset, user.clone());
-            let price = T::Oracle::get_rate(asset);
+            let price = Self::oracle_price(asset);
             debt_balance += price.saturating_mul_int(amount);
         }
         debug::info!("Leaving get_user_info");
 
-        (supply_balance, supply_converted, debt_balance)
+        (supply_balance, supply_converted, supply_liquidation, debt_balance)
+    }
+
+    /// the efficiency mode category a user is in, if it is still defined
+    fn user_emode(user: T::AccountId) -> Option<(EModeCategoryId, EModeCategory)> {
+        let id = Self::user_emode_category(user)?;
+        Self::emode_category(id).map(|category| (id, category))
+    }
+
+    /// collateral factor of a collateral, enhanced if it is in the efficiency mode category
+    fn collateral_factor_in_emode(pool: &Pool<T>, asset_id: T::AssetId, emode: &Option<(EModeCategoryId, EModeCategory)>) -> FixedU128 {
+        match emode {
+            Some((id, category)) if Self::asset_emode_category(asset_id) == Some(*id) => category.collateral_factor,
+            _ => pool.collateral_factor,
+        }
+    }
+
+    /// liquidation threshold of a collateral, enhanced if it is in the efficiency mode category
+    fn liquidation_threshold_in_emode(pool: &Pool<T>, asset_id: T::AssetId, emode: &Option<(EModeCategoryId, EModeCategory)>) -> FixedU128 {
+        match emode {
+            Some((id, category)) if Self::asset_emode_category(asset_id) == Some(*id) => category.liquidation_threshold,
+            _ => pool.liquidation_threshold,
+        }
+    }
+
+    /// if user's supply of an asset counts as collateral
+    fn is_collateral(pool: &Pool<T>, asset_id: T::AssetId, user: T::AccountId) -> bool {
+        pool.can_be_collateral && Self::set_contains(&Self::user_collateral_set(user), asset_id)
     }
 
     pub fn get_user_debt_with_interest(asset_id: T::AssetId, user: T::AccountId) -> T::Balance {
@@ -742,12 +2603,8 @@ impl<T: Trait> Module<T> where
         let total_debt_index;
 
         if let Some(pool) = Self::pool(asset_id) {
-            let interval_block_number = <frame_system::Module<T>>::block_number() - pool.last_updated;
-            let elapsed_time_u32 = TryInto::<u32>::try_into(interval_block_number)
-                .ok()
-                .expect("blockchain will not exceed 2^32 blocks; qed");
-    
-            let debt_multiplier = FixedU128::one() + Self::debt_rate_internal(&pool) * FixedU128::saturating_from_integer(elapsed_time_u32);
+            let elapsed_time_u32 = Self::elapsed_time(&pool);
+            let debt_multiplier = interest_rate::compound(Self::debt_rate_internal(&pool), elapsed_time_u32);
             total_debt_index = pool.total_debt_index * debt_multiplier;
 
         } else {
@@ -757,7 +2614,7 @@ impl<T: Trait> Module<T> where
 
         debug::info!("Leaving get_user_debt_with_interest");
         if let Some(user_debt) = Self::user_debt(asset_id, user) {
-            (total_debt_index / user_debt.index).saturating_mul_int(user_debt.amount)
+            Self::debt_with_interest(&user_debt, total_debt_index, Self::now())
         } else {
             T::Balance::zero()
         }
@@ -769,12 +2626,8 @@ impl<T: Trait> Module<T> where
         let total_supply_index;
 
         if let Some(pool) = Self::pool(asset_id) {
-            let interval_block_number = <frame_system::Module<T>>::block_number() - pool.last_updated;
-            let elapsed_time_u32 = TryInto::<u32>::try_into(interval_block_number)
-                .ok()
-                .expect("blockchain will not exceed 2^32 blocks; qed");
-    
-            let supply_multiplier = FixedU128::one() + Self::supply_rate_internal(&pool) * FixedU128::saturating_from_integer(elapsed_time_u32);
+            let elapsed_time_u32 = Self::elapsed_time(&pool);
+            let supply_multiplier = interest_rate::compound(Self::supply_rate_internal(&pool), elapsed_time_u32);
             total_supply_index = pool.total_supply_index * supply_multiplier;
 
         } else {
@@ -795,25 +2648,108 @@ impl<T: Trait> Module<T> where
         debug::info!("Entering accrue_debt_with_interest");
 
         if let Some(mut user_debt) = Self::user_debt(asset_id, user.clone()) {
-            user_debt.amount = (pool.total_debt_index / user_debt.index).saturating_mul_int(user_debt.amount);
+            let now = Self::now();
+            user_debt.amount = Self::debt_with_interest(&user_debt, pool.total_debt_index, now);
             user_debt.index = pool.total_debt_index;
+            user_debt.last_updated = now;
             UserDebts::<T>::insert(asset_id, user, user_debt);
         } 
         debug::info!("Leaving accrue_debt_with_interest");
 
     }
 
+    /// a user's debt with interest, variable debt up to a pool debt index and stable debt up to a timestamp
+    fn debt_with_interest(user_debt: &UserDebt<T>, total_debt_index: FixedU128, now: u64) -> T::Balance {
+        match user_debt.stable_rate {
+            Some(rate) => {
+                let elapsed = now.saturating_sub(user_debt.last_updated).saturated_into::<u32>();
+                interest_rate::compound(rate, elapsed).saturating_mul_int(user_debt.amount)
+            }
+            None => (total_debt_index / user_debt.index).saturating_mul_int(user_debt.amount),
+        }
+    }
+
     // pool interest is already accrued
-    fn accrue_supply_with_interest(pool: &Pool<T>, asset_id: T::AssetId, user: T::AccountId) {
+    fn accrue_supply_with_interest(pool: &Pool<T>, asset_id: T::AssetId, user: T::AccountId) -> Result {
         debug::info!("Entering accrue_supply_with_interest");
 
         if let Some(mut user_supply) = Self::user_supply(asset_id, user.clone()) {
             user_supply.amount = (pool.total_supply_index / user_supply.index).saturating_mul_int(user_supply.amount);
             user_supply.index = pool.total_supply_index;
-            UserSupplies::<T>::insert(asset_id, user, user_supply);
+            UserSupplies::<T>::insert(asset_id, user.clone(), user_supply);
+            Self::sync_ktokens(asset_id, user)?;
         }
         debug::info!("Leaving accrue_supply_with_interest");
 
+        Ok(())
+    }
+
+}
+
+/// kToken transfers move the supply they stand for, and must leave the sender above its collateral factors
+impl<T: Trait> OnAssetTransfer<T::AccountId, T::AssetId, T::Balance> for Module<T> {
+    fn can_transfer(from: &T::AccountId, id: T::AssetId, to: &T::AccountId, amount: T::Balance) -> Result {
+        let asset_id = match Self::ktoken_underlying(id) {
+            Some(asset_id) => asset_id,
+            None => return Ok(()),
+        };
+        let pool = Self::pool(asset_id).ok_or(Error::<T>::PoolNotExist)?;
+        ensure!(!Self::is_paused(asset_id, PoolAction::Withdraw), Error::<T>::ActionPaused);
+        if from != to {
+            Self::ensure_room(&Self::user_supply_set(to.clone()), asset_id)?;
+        }
+
+        if Self::is_collateral(&pool, asset_id, from.clone()) {
+            // the position is only valued if there is debt
+            if !Self::user_debt_set(from.clone()).is_empty() {
+                Self::ensure_fresh_prices(from.clone())?;
+            }
+            let (_, converted_supply, converted_borrow) = Self::get_user_info(from.clone());
+            let supply_index = pool.total_supply_index * interest_rate::compound(Self::supply_rate_internal(&pool), Self::elapsed_time(&pool));
+            let price = Self::oracle_price(asset_id);
+            let collateral_factor = Self::collateral_factor_in_emode(&pool, asset_id, &Self::user_emode(from.clone()));
+            let converted_supply = converted_supply.saturating_sub((price * collateral_factor * supply_index).saturating_mul_int(amount));
+            ensure!(converted_borrow <= converted_supply, Error::<T>::BelowCollateralFactor);
+        }
+
+        Ok(())
     }
 
+    fn on_transfer(from: &T::AccountId, id: T::AssetId, to: &T::AccountId, amount: T::Balance) -> Result {
+        let asset_id = match Self::ktoken_underlying(id) {
+            Some(asset_id) => asset_id,
+            None => return Ok(()),
+        };
+        let mut pool = match Self::pool(asset_id) {
+            Some(pool) => pool,
+            None => return Ok(()),
+        };
+        if from == to {
+            return Ok(())
+        }
+
+        Self::accrue_interest(&mut pool);
+        Self::accrue_supply_with_interest(&pool, asset_id, from.clone())?;
+        let supply = Self::user_supply(asset_id, from.clone()).map_or_else(T::Balance::zero, |user_supply| user_supply.amount);
+        let amount = pool.total_supply_index.saturating_mul_int(amount).min(supply);
+
+        Self::update_user_supply(&pool, asset_id, from.clone(), amount, false)?;
+        Self::update_user_supply(&pool, asset_id, to.clone(), amount, true)?;
+        if !amount.is_zero() {
+            // anyone can send kTokens, so they are not collateral of the receiver until it enables them
+            UserSupplySet::<T>::mutate(to.clone(), |assets| Self::set_insert(assets, asset_id));
+        }
+
+        Self::put_pool(asset_id, pool);
+        Ok(())
+    }
+
+    fn can_destroy(_: &T::AccountId, id: T::AssetId) -> Result {
+        ensure!(Self::ktoken_underlying(id).is_none(), Error::<T>::KTokenNotDestroyable);
+        Ok(())
+    }
+
+    fn transfer_weight() -> Weight {
+        T::WeightInfo::transfer_ktoken(MAX_USER_ASSETS)
+    }
 }
@@ -0,0 +1,491 @@
+//! Storage migrations of the lending pallet
+
+use super::*;
+use frame_support::{
+    traits::Get, weights::Weight, IterableStorageMap, IterableStorageDoubleMap,
+    storage::migration::{StorageIterator, put_storage_value, take_storage_value},
+};
+use sp_runtime::traits::CheckedDiv;
+
+/// Pool layout of `Releases::V0`, a linear interest model with per block rates
+#[derive(Clone, Eq, PartialEq, RuntimeDebug, Encode, Decode)]
+pub struct PoolV0<T: Trait> {
+    pub enabled: bool,
+    pub can_be_collateral: bool,
+    pub asset: T::AssetId,
+    pub supply: T::Balance,
+    pub debt: T::Balance,
+    pub safe_factor: FixedU128,
+    pub close_factor: FixedU128,
+    pub discount_factor: FixedU128,
+    pub total_supply_index: FixedU128,
+    pub total_debt_index: FixedU128,
+    pub last_updated: T::BlockNumber,
+    pub utilization_factor: FixedU128,
+    pub initial_interest_rate: FixedU128,
+}
+
+/// Pool layout of `Releases::V1`, an interest rate model with per block rates
+#[derive(Clone, Eq, PartialEq, RuntimeDebug, Encode, Decode)]
+pub struct PoolV1<T: Trait> {
+    pub enabled: bool,
+    pub can_be_collateral: bool,
+    pub asset: T::AssetId,
+    pub supply: T::Balance,
+    pub debt: T::Balance,
+    pub safe_factor: FixedU128,
+    pub close_factor: FixedU128,
+    pub discount_factor: FixedU128,
+    pub total_supply_index: FixedU128,
+    pub total_debt_index: FixedU128,
+    pub last_updated: T::BlockNumber,
+    pub interest_rate_model: InterestRateModel,
+}
+
+/// Pool layout of `Releases::V2`, reserves set aside from interest without caps
+#[derive(Clone, Eq, PartialEq, RuntimeDebug, Encode, Decode)]
+pub struct PoolV2<T: Trait> {
+    pub enabled: bool,
+    pub can_be_collateral: bool,
+    pub asset: T::AssetId,
+    pub supply: T::Balance,
+    pub debt: T::Balance,
+    pub safe_factor: FixedU128,
+    pub close_factor: FixedU128,
+    pub discount_factor: FixedU128,
+    pub total_supply_index: FixedU128,
+    pub total_debt_index: FixedU128,
+    pub last_updated: T::BlockNumber,
+    pub interest_rate_model: InterestRateModel,
+    pub reserve_factor: FixedU128,
+    pub reserves: T::Balance,
+}
+
+/// Pool layout of `Releases::V3`, the last one accruing interest by block number at per block rates
+#[derive(Clone, Eq, PartialEq, RuntimeDebug, Encode, Decode)]
+pub struct PoolV3<T: Trait> {
+    pub enabled: bool,
+    pub can_be_collateral: bool,
+    pub asset: T::AssetId,
+    pub supply: T::Balance,
+    pub debt: T::Balance,
+    pub safe_factor: FixedU128,
+    pub close_factor: FixedU128,
+    pub discount_factor: FixedU128,
+    pub total_supply_index: FixedU128,
+    pub total_debt_index: FixedU128,
+    pub last_updated: T::BlockNumber,
+    pub interest_rate_model: InterestRateModel,
+    pub reserve_factor: FixedU128,
+    pub reserves: T::Balance,
+    pub supply_cap: Option<T::Balance>,
+    pub borrow_cap: Option<T::Balance>,
+}
+
+/// Pool layout of `Releases::V5`, one safe factor for both borrowing and liquidation
+#[derive(Clone, Eq, PartialEq, RuntimeDebug, Encode, Decode)]
+pub struct PoolV5<T: Trait> {
+    pub enabled: bool,
+    pub can_be_collateral: bool,
+    pub asset: T::AssetId,
+    pub supply: T::Balance,
+    pub debt: T::Balance,
+    pub safe_factor: FixedU128,
+    pub close_factor: FixedU128,
+    pub discount_factor: FixedU128,
+    pub total_supply_index: FixedU128,
+    pub total_debt_index: FixedU128,
+    pub last_updated: u64,
+    pub interest_rate_model: InterestRateModel,
+    pub reserve_factor: FixedU128,
+    pub reserves: T::Balance,
+    pub supply_cap: Option<T::Balance>,
+    pub borrow_cap: Option<T::Balance>,
+}
+
+/// Efficiency mode category layout of `Releases::V5`, the liquidation threshold multiplies debt
+#[derive(Clone, Eq, PartialEq, RuntimeDebug, Encode, Decode)]
+pub struct EModeCategoryV5 {
+    pub safe_factor: FixedU128,
+    pub liquidation_threshold: FixedU128,
+}
+
+impl<T: Trait> PoolV5<T> {
+    /// The pool with both collateral factor and liquidation threshold set to `factor`
+    fn upgrade(self, factor: FixedU128) -> Pool<T> {
+        Pool::<T> {
+            enabled: self.enabled,
+            can_be_collateral: self.can_be_collateral,
+            asset: self.asset,
+            supply: self.supply,
+            debt: self.debt,
+            collateral_factor: factor,
+            liquidation_threshold: factor,
+            close_factor: self.close_factor,
+            discount_factor: self.discount_factor,
+            total_supply_index: self.total_supply_index,
+            total_debt_index: self.total_debt_index,
+            last_updated: self.last_updated,
+            interest_rate_model: self.interest_rate_model,
+            reserve_factor: self.reserve_factor,
+            reserves: self.reserves,
+            supply_cap: self.supply_cap,
+            borrow_cap: self.borrow_cap,
+            stable_debt: Zero::zero(),
+            average_stable_rate: FixedU128::zero(),
+            stable_rate_config: None,
+        }
+    }
+}
+
+/// Pool layout of `Releases::V6` and `Releases::V7`, all debt at the variable rate.
+/// The later fields are appended, so a pool of the current layout decodes as this too.
+#[derive(Clone, Eq, PartialEq, RuntimeDebug, Encode, Decode)]
+pub struct PoolV7<T: Trait> {
+    pub enabled: bool,
+    pub can_be_collateral: bool,
+    pub asset: T::AssetId,
+    pub supply: T::Balance,
+    pub debt: T::Balance,
+    pub collateral_factor: FixedU128,
+    pub liquidation_threshold: FixedU128,
+    pub close_factor: FixedU128,
+    pub discount_factor: FixedU128,
+    pub total_supply_index: FixedU128,
+    pub total_debt_index: FixedU128,
+    pub last_updated: u64,
+    pub interest_rate_model: InterestRateModel,
+    pub reserve_factor: FixedU128,
+    pub reserves: T::Balance,
+    pub supply_cap: Option<T::Balance>,
+    pub borrow_cap: Option<T::Balance>,
+}
+
+/// User debt layout before `Releases::V8`, all debt at the variable rate
+#[derive(Clone, Eq, PartialEq, RuntimeDebug, Encode, Decode)]
+pub struct UserDebtV7<T: Trait> {
+    pub amount: T::Balance,
+    pub index: FixedU128,
+}
+
+/// Run every migration the storage has not gone through yet, in order
+///
+/// - `millisecs_per_block`: The block time the per block rates before `Releases::V5` were set for
+pub fn migrate<T: Trait>(millisecs_per_block: u64) -> Weight {
+    migrate_to_v1::<T>()
+        .saturating_add(migrate_to_v2::<T>())
+        .saturating_add(migrate_to_v3::<T>())
+        .saturating_add(migrate_to_v4::<T>())
+        .saturating_add(migrate_to_time_based::<T>(millisecs_per_block))
+        .saturating_add(migrate_to_v6::<T>())
+        .saturating_add(migrate_to_v7::<T>())
+        .saturating_add(migrate_to_v8::<T>())
+        .saturating_add(migrate_to_v9::<T>())
+}
+
+/// Translate every pool from one layout to another.
+/// `Pools` can only be translated into the current layout, so older ones are written by raw key.
+fn translate_pools<T: Trait, O: Decode, N: Encode>(f: impl Fn(O) -> N) -> Weight {
+    let old_pools: Vec<(Vec<u8>, O)> = StorageIterator::<O>::new(b"Lending", b"Pools").collect();
+    let count = old_pools.len() as Weight;
+    for (key, old) in old_pools {
+        put_storage_value(b"Lending", b"Pools", &key, f(old));
+    }
+    T::DbWeight::get().reads_writes(count, count)
+}
+
+/// Move the linear interest factors of every pool into an interest rate model
+pub fn migrate_to_v1<T: Trait>() -> Weight {
+    if Module::<T>::storage_version() != Releases::V0 {
+        return 0
+    }
+    debug::info!("Migrating lending pools to interest rate models");
+
+    let weight = translate_pools::<T, _, _>(|old: PoolV0<T>| PoolV1::<T> {
+        enabled: old.enabled,
+        can_be_collateral: old.can_be_collateral,
+        asset: old.asset,
+        supply: old.supply,
+        debt: old.debt,
+        safe_factor: old.safe_factor,
+        close_factor: old.close_factor,
+        discount_factor: old.discount_factor,
+        total_supply_index: old.total_supply_index,
+        total_debt_index: old.total_debt_index,
+        last_updated: old.last_updated,
+        interest_rate_model: InterestRateModel::Linear {
+            initial_interest_rate: old.initial_interest_rate,
+            utilization_factor: old.utilization_factor,
+        },
+    });
+    StorageVersion::put(Releases::V1);
+
+    weight.saturating_add(T::DbWeight::get().reads_writes(1, 1))
+}
+
+/// Start every pool with no reserves, and none of its interest set aside for them
+pub fn migrate_to_v2<T: Trait>() -> Weight {
+    if Module::<T>::storage_version() != Releases::V1 {
+        return 0
+    }
+    debug::info!("Migrating lending pools to reserves");
+
+    let weight = translate_pools::<T, _, _>(|old: PoolV1<T>| PoolV2::<T> {
+        enabled: old.enabled,
+        can_be_collateral: old.can_be_collateral,
+        asset: old.asset,
+        supply: old.supply,
+        debt: old.debt,
+        safe_factor: old.safe_factor,
+        close_factor: old.close_factor,
+        discount_factor: old.discount_factor,
+        total_supply_index: old.total_supply_index,
+        total_debt_index: old.total_debt_index,
+        last_updated: old.last_updated,
+        interest_rate_model: old.interest_rate_model,
+        reserve_factor: FixedU128::zero(),
+        reserves: Zero::zero(),
+    });
+    StorageVersion::put(Releases::V2);
+
+    weight.saturating_add(T::DbWeight::get().reads_writes(1, 1))
+}
+
+/// Leave the supply and borrow of every pool uncapped
+pub fn migrate_to_v3<T: Trait>() -> Weight {
+    if Module::<T>::storage_version() != Releases::V2 {
+        return 0
+    }
+    debug::info!("Migrating lending pools to supply and borrow caps");
+
+    let weight = translate_pools::<T, _, _>(|old: PoolV2<T>| PoolV3::<T> {
+        enabled: old.enabled,
+        can_be_collateral: old.can_be_collateral,
+        asset: old.asset,
+        supply: old.supply,
+        debt: old.debt,
+        safe_factor: old.safe_factor,
+        close_factor: old.close_factor,
+        discount_factor: old.discount_factor,
+        total_supply_index: old.total_supply_index,
+        total_debt_index: old.total_debt_index,
+        last_updated: old.last_updated,
+        interest_rate_model: old.interest_rate_model,
+        reserve_factor: old.reserve_factor,
+        reserves: old.reserves,
+        supply_cap: None,
+        borrow_cap: None,
+    });
+    StorageVersion::put(Releases::V3);
+
+    weight.saturating_add(T::DbWeight::get().reads_writes(1, 1))
+}
+
+/// Enable as collateral every supplied asset that can be one,
+/// as all of them counted as collateral before users could choose
+pub fn migrate_to_v4<T: Trait>() -> Weight {
+    if Module::<T>::storage_version() != Releases::V3 {
+        return 0
+    }
+    debug::info!("Migrating lending users to chosen collateral");
+
+    let collaterals: Vec<T::AssetId> = StorageIterator::<PoolV3<T>>::new(b"Lending", b"Pools")
+        .filter(|(_, pool)| pool.can_be_collateral)
+        .map(|(_, pool)| pool.asset)
+        .collect();
+    // both sets are keyed by account with the same hasher
+    let supply_sets: Vec<(Vec<u8>, Vec<T::AssetId>)> = StorageIterator::<Vec<T::AssetId>>::new(b"Lending", b"UserSupplySet").collect();
+    let users = supply_sets.len() as Weight;
+    for (key, assets) in supply_sets {
+        let assets: Vec<T::AssetId> = assets.into_iter().filter(|id| collaterals.contains(id)).collect();
+        if !assets.is_empty() {
+            put_storage_value(b"Lending", b"UserCollateralSet", &key, assets);
+        }
+    }
+    StorageVersion::put(Releases::V4);
+
+    T::DbWeight::get().reads_writes(collaterals.len() as Weight + users + 1, users + 1)
+}
+
+/// Accrue every pool up to the current block at its per block rates,
+/// then switch it to timestamps and per second rates.
+///
+/// - `millisecs_per_block`: The block time the per block rates were set for
+pub fn migrate_to_time_based<T: Trait>(millisecs_per_block: u64) -> Weight {
+    if Module::<T>::storage_version() != Releases::V4 {
+        return 0
+    }
+    debug::info!("Migrating lending pools to time based interest");
+
+    let current_block = <frame_system::Module<T>>::block_number();
+    let now = Module::<T>::now();
+    let per_second = FixedU128::saturating_from_rational(1000, millisecs_per_block);
+
+    let weight = translate_pools::<T, _, _>(|old: PoolV3<T>| {
+        let elapsed_blocks = current_block.saturating_sub(old.last_updated).saturated_into::<u32>();
+        let mut pool = PoolV5::<T> {
+            enabled: old.enabled,
+            can_be_collateral: old.can_be_collateral,
+            asset: old.asset,
+            supply: old.supply,
+            debt: old.debt,
+            safe_factor: old.safe_factor,
+            close_factor: old.close_factor,
+            discount_factor: old.discount_factor,
+            total_supply_index: old.total_supply_index,
+            total_debt_index: old.total_debt_index,
+            last_updated: now,
+            interest_rate_model: old.interest_rate_model,
+            reserve_factor: old.reserve_factor,
+            reserves: old.reserves,
+            supply_cap: old.supply_cap,
+            borrow_cap: old.borrow_cap,
+        };
+        // interest until now accrues at the old per block rates
+        let mut accrued = pool.clone().upgrade(pool.safe_factor);
+        Module::<T>::accrue_interest_for(&mut accrued, elapsed_blocks);
+        pool.supply = accrued.supply;
+        pool.debt = accrued.debt;
+        pool.total_supply_index = accrued.total_supply_index;
+        pool.total_debt_index = accrued.total_debt_index;
+        pool.reserves = accrued.reserves;
+
+        pool.interest_rate_model = pool.interest_rate_model.scale_rates(per_second);
+        pool
+    });
+    StorageVersion::put(Releases::V5);
+
+    weight.saturating_add(T::DbWeight::get().reads_writes(1, 1))
+}
+
+/// Split the safe factor of every pool into a collateral factor and a liquidation threshold.
+/// Both start at the safe factor divided by the removed global liquidation threshold,
+/// so no position turns from healthy to liquidatable or back.
+pub fn migrate_to_v6<T: Trait>() -> Weight {
+    if Module::<T>::storage_version() != Releases::V5 {
+        return 0
+    }
+    debug::info!("Migrating lending pools to collateral factor and liquidation threshold");
+
+    // debt was checked against `safe_factor / LiquidationThreshold` of the collateral value
+    let global_threshold: FixedU128 = take_storage_value(b"Lending", b"LiquidationThreshold", &[])
+        .unwrap_or_else(FixedU128::one);
+
+    Pools::<T>::translate(|_, old: PoolV5<T>| {
+        let factor = old.safe_factor.checked_div(&global_threshold).unwrap_or(old.safe_factor);
+        Some(old.upgrade(factor))
+    });
+    EModeCategories::translate(|_, old: EModeCategoryV5| {
+        let factor = old.safe_factor.checked_div(&old.liquidation_threshold).unwrap_or(old.safe_factor);
+        Some(EModeCategory {
+            collateral_factor: factor,
+            liquidation_threshold: factor,
+        })
+    });
+    StorageVersion::put(Releases::V6);
+
+    let count = (Pools::<T>::iter().count() + EModeCategories::iter().count()) as Weight;
+    T::DbWeight::get().reads_writes(count * 2 + 2, count + 2)
+}
+
+/// Create the kToken of every pool, and mint kTokens for every supply
+pub fn migrate_to_v7<T: Trait>() -> Weight {
+    if Module::<T>::storage_version() != Releases::V6 {
+        return 0
+    }
+    debug::info!("Migrating lending supplies to kTokens");
+
+    let pools: Vec<T::AssetId> = Pools::<T>::iter().map(|(id, _)| id).collect();
+    for id in pools.iter() {
+        Module::<T>::create_ktoken(*id);
+    }
+    let supplies: Vec<(T::AssetId, T::AccountId)> = UserSupplies::<T>::iter().map(|(id, user, _)| (id, user)).collect();
+    for (id, user) in supplies.iter() {
+        if let Err(e) = Module::<T>::sync_ktokens(*id, user.clone()) {
+            debug::error!("Failed to mint kTokens of asset {:?}: {:?}", id, e);
+        }
+    }
+    StorageVersion::put(Releases::V7);
+
+    let count = (pools.len() + supplies.len()) as Weight;
+    T::DbWeight::get().reads_writes(count * 2 + 1, count * 3 + 1)
+}
+
+/// Add stable rate accounting to every pool and user debt, all existing debt stays variable
+pub fn migrate_to_v8<T: Trait>() -> Weight {
+    if Module::<T>::storage_version() != Releases::V7 {
+        return 0
+    }
+    debug::info!("Migrating lending debts to stable rate accounting");
+
+    let now = Module::<T>::now();
+    Pools::<T>::translate(|_, old: PoolV7<T>| {
+        Some(Pool::<T> {
+            enabled: old.enabled,
+            can_be_collateral: old.can_be_collateral,
+            asset: old.asset,
+            supply: old.supply,
+            debt: old.debt,
+            collateral_factor: old.collateral_factor,
+            liquidation_threshold: old.liquidation_threshold,
+            close_factor: old.close_factor,
+            discount_factor: old.discount_factor,
+            total_supply_index: old.total_supply_index,
+            total_debt_index: old.total_debt_index,
+            last_updated: old.last_updated,
+            interest_rate_model: old.interest_rate_model,
+            reserve_factor: old.reserve_factor,
+            reserves: old.reserves,
+            supply_cap: old.supply_cap,
+            borrow_cap: old.borrow_cap,
+            stable_debt: Zero::zero(),
+            average_stable_rate: FixedU128::zero(),
+            stable_rate_config: None,
+        })
+    });
+    UserDebts::<T>::translate(|_, _, old: UserDebtV7<T>| {
+        Some(UserDebt::<T> {
+            amount: old.amount,
+            index: old.index,
+            stable_rate: None,
+            last_updated: now,
+        })
+    });
+    StorageVersion::put(Releases::V8);
+
+    let count = (Pools::<T>::iter().count() + UserDebts::<T>::iter().count()) as Weight;
+    T::DbWeight::get().reads_writes(count * 2 + 1, count + 1)
+}
+
+/// Index every pool in the order of asset ids, and turn the asset lists of every user into bit sets of pool indices.
+/// Users already over `MAX_USER_ASSETS` keep all their assets, the cap only stops them from entering more.
+pub fn migrate_to_v9<T: Trait>() -> Weight {
+    if Module::<T>::storage_version() != Releases::V8 {
+        return 0
+    }
+    debug::info!("Migrating lending user asset sets to bit sets");
+
+    let mut pools: Vec<T::AssetId> = Pools::<T>::iter().map(|(id, _)| id).collect();
+    pools.sort();
+    for id in pools.iter().filter(|id| Module::<T>::pool_index(**id).is_none()) {
+        if Module::<T>::index_pool(*id).is_none() {
+            debug::error!("No pool index left for asset {:?}", id);
+        }
+    }
+
+    let to_set = |assets: Vec<T::AssetId>| {
+        let mut set = PoolSet::default();
+        for index in assets.into_iter().filter_map(Module::<T>::pool_index) {
+            set.insert(index);
+        }
+        if set.is_empty() { None } else { Some(set) }
+    };
+    UserSupplySet::<T>::translate(|_, assets: Vec<T::AssetId>| to_set(assets));
+    UserDebtSet::<T>::translate(|_, assets: Vec<T::AssetId>| to_set(assets));
+    UserCollateralSet::<T>::translate(|_, assets: Vec<T::AssetId>| to_set(assets));
+    StorageVersion::put(Releases::V9);
+
+    let pools = pools.len() as Weight;
+    let users = (UserSupplySet::<T>::iter().count() + UserDebtSet::<T>::iter().count() + UserCollateralSet::<T>::iter().count()) as Weight;
+    T::DbWeight::get().reads_writes(pools + users * 2 + 1, pools * 2 + users + 2)
+}
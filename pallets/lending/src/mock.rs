@@ -1,16 +1,33 @@
 use crate::{Trait, Module};
 use sp_core::H256;
-use frame_support::{impl_outer_origin, parameter_types, weights::Weight};
+use frame_support::{impl_outer_origin, impl_outer_dispatch, impl_outer_event, parameter_types, weights::Weight};
 use sp_runtime::{
 	traits::{BlakeTwo256, IdentityLookup}, testing::Header, Perbill,
 };
 use frame_system as system;
 use pallet_assets as assets;
+use crate as lending;
 
 impl_outer_origin! {
 	pub enum Origin for Test {}
 }
 
+impl_outer_event! {
+	pub enum TestEvent for Test {
+		system<T>,
+		assets<T>,
+		lending<T>,
+	}
+}
+
+impl_outer_dispatch! {
+	pub enum Call for Test where origin: Origin {
+		frame_system::System,
+		pallet_assets::Assets,
+		lending::Lending,
+	}
+}
+
 type Balance = u128;
 type AccountId = u64;
 type AssetId = u64;
@@ -23,12 +40,13 @@ parameter_types! {
 	pub const MaximumBlockWeight: Weight = 1024;
 	pub const MaximumBlockLength: u32 = 2 * 1024;
 	pub const AvailableBlockRatio: Perbill = Perbill::from_percent(75);
+	pub const MinimumPeriod: u64 = 1;
 }
 
 impl system::Trait for Test {
 	type BaseCallFilter = ();
 	type Origin = Origin;
-	type Call = ();
+	type Call = Call;
 	type Index = u64;
 	type BlockNumber = u64;
 	type Hash = H256;
@@ -36,7 +54,7 @@ impl system::Trait for Test {
 	type AccountId = AccountId;
 	type Lookup = IdentityLookup<Self::AccountId>;
 	type Header = Header;
-	type Event = ();
+	type Event = TestEvent;
 	type BlockHashCount = BlockHashCount;
 	type MaximumBlockWeight = MaximumBlockWeight;
 	type DbWeight = ();
@@ -54,11 +72,21 @@ impl system::Trait for Test {
 }
 
 impl assets::Trait for Test {
-    type Event = ();
+    type Event = TestEvent;
     /// The units in which we record balances.
     type Balance = Balance;
     /// The arithmetic type of asset identifier.
     type AssetId = AssetId;
+    type OnAssetTransfer = Lending;
+    type UnixTime = Timestamp;
+    type WeightInfo = ();
+}
+
+impl pallet_timestamp::Trait for Test {
+	type Moment = u64;
+	type OnTimestampSet = ();
+	type MinimumPeriod = MinimumPeriod;
+	type WeightInfo = ();
 }
 
 impl Trait for Test {
@@ -66,16 +94,22 @@ impl Trait for Test {
     /// The arithmetic type of asset identifier.
     type AssetId =  AssetId;
 
-    type Event = ();
+    type Event = TestEvent;
 
     type Oracle = assets::Module<Test>;
 
     type MultiAsset = assets::Module<Test>;
 
+    type UnixTime = Timestamp;
+
+    type Call = Call;
+
+    type WeightInfo = ();
 }
 
 pub type System = system::Module<Test>;
 pub type Assets = assets::Module<Test>;
+pub type Timestamp = pallet_timestamp::Module<Test>;
 pub type Lending = Module<Test>;
 
 pub struct ExtBuilder {
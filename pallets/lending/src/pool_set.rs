@@ -0,0 +1,47 @@
+use sp_runtime::RuntimeDebug;
+use codec::{Encode, Decode};
+
+/// Index of a pool, in the order pools were created
+pub type PoolIndex = u32;
+
+/// Max number of pools, a `PoolSet` has a bit for each
+pub const MAX_POOLS: PoolIndex = 128;
+
+/// A set of pools, one bit per pool index
+#[derive(Clone, Copy, Default, Eq, PartialEq, RuntimeDebug, Encode, Decode)]
+pub struct PoolSet(u128);
+
+impl PoolSet {
+    pub fn contains(&self, index: PoolIndex) -> bool {
+        index < MAX_POOLS && self.0 & (1 << index) != 0
+    }
+
+    /// add a pool, false if the index is out of range
+    pub fn insert(&mut self, index: PoolIndex) -> bool {
+        if index >= MAX_POOLS {
+            return false
+        }
+        self.0 |= 1 << index;
+        true
+    }
+
+    pub fn remove(&mut self, index: PoolIndex) {
+        if index < MAX_POOLS {
+            self.0 &= !(1 << index);
+        }
+    }
+
+    pub fn len(&self) -> u32 {
+        self.0.count_ones()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// indices in the set, in increasing order
+    pub fn iter(&self) -> impl Iterator<Item = PoolIndex> {
+        let bits = self.0;
+        (0..MAX_POOLS).filter(move |index| bits & (1 << index) != 0)
+    }
+}
@@ -1,8 +1,8 @@
 use super::*;
 use crate::mock::*;
 use frame_support::sp_runtime::traits::Hash;
-use frame_support::traits::OnFinalize;
-use frame_support::{assert_noop, assert_ok};
+use frame_support::traits::{OnFinalize, OnInitialize};
+use frame_support::{assert_noop, assert_ok, IterableStorageMap};
 use frame_system::InitKind;
 use sp_runtime::{FixedU128, DispatchError, FixedPointNumber};
 
@@ -14,7 +14,10 @@ const ASSET2: u64 = 1;
 
 fn new_test_ext() -> sp_io::TestExternalities {
 	let mut ext = ExtBuilder::default().build();
-	ext.execute_with(|| System::set_block_number(1));
+	ext.execute_with(|| {
+		System::set_block_number(1);
+		Timestamp::set_timestamp(6000);
+	});
 	ext
 }
 
@@ -42,9 +45,9 @@ fn can_supply() {
 
 		assert_eq!(pool_supply.supply, 100000);
 		assert_eq!(pool_supply.total_supply_index, FixedU128::one());
-		assert_eq!(pool_supply.last_updated, System::block_number());
+		assert_eq!(pool_supply.last_updated, 6);
 
-		let user_supply_set = Lending::user_supply_set(USER1);
+		let user_supply_set = Lending::assets_in(Lending::user_supply_set(USER1));
 		assert_eq!(user_supply_set, vec![ASSET1]);
 
 		let user_balance_after = Assets::get_asset_balance((ASSET1, USER1));
@@ -62,23 +65,23 @@ fn check_accrue_interest() {
 		let supply_amount = 2 * point_one;
 		let borrow_amount: u128 = point_one;
 
-		System::set_block_number(1);
 		Lending::supply(Origin::signed(USER1), ASSET1, supply_amount);
 		Lending::borrow(Origin::signed(USER1), ASSET1, borrow_amount);
 
-		System::set_block_number(11);
+		Timestamp::set_timestamp(66000);
 		Lending::supply(Origin::signed(USER1), ASSET1, supply_amount);
 
 		// At this point:
 		// utilization_ratio: 0.5
-		// debt_interest_rate: 2.31e-8
-		// supply_interest_rate: 1.155e-8
+		// debt_interest_rate: 3.85e-9 per second
+		// supply_interest_rate: 1.925e-9 per second
 
 		// These two values are derived from the protocol before hand.
-		let mut debt_multiplier = FixedU128::from_fraction(1.000000231000000000);
-		let mut supply_multiplier = FixedU128::from_fraction(1.000000115500000000);
+		// Interest compounds every second: (1 + rate) ^ 60
+		let mut debt_multiplier = FixedU128::from_inner(1000000231000026141);
+		let mut supply_multiplier = FixedU128::from_inner(1000000115500006461);
 
-		// an extra `supply_amount` is added due to previous supply at block 11
+		// an extra `supply_amount` is added due to previous supply a minute later
 		let mut expected_supply = supply_multiplier.saturating_mul_int(supply_amount)
 			+ supply_amount;
 		let mut expected_borrow = debt_multiplier.saturating_mul_int(borrow_amount);
@@ -86,12 +89,12 @@ fn check_accrue_interest() {
 		assert_eq!(expected_supply, total.supply);
 		assert_eq!(expected_borrow, total.debt);
 
-		System::set_block_number(21);
+		Timestamp::set_timestamp(126000);
 		Lending::borrow(Origin::signed(USER1), ASSET1, borrow_amount);
 
 		total = Lending::pool(ASSET1).unwrap();
-		assert_eq!(400000036575004778, total.supply);
-		assert_eq!(200000036575004779, total.debt);
+		assert_eq!(400000036575006262, total.supply);
+		assert_eq!(200000036575008278, total.debt);
 
 	});
 }
@@ -100,8 +103,6 @@ fn check_accrue_interest() {
 fn can_borrow() {
 	new_test_ext().execute_with(|| {
 
-		System::set_block_number(1);
-
 		// setup pool
 		let first_asset_amount = 100000;
 		let first_price: FixedU128 = FixedU128::from_fraction(1.25);
@@ -131,7 +132,7 @@ fn can_borrow() {
 		));
 
 
-		System::set_block_number(100000);
+		Timestamp::set_timestamp(600000000);
 
 		// update the index
 		assert_ok!(Lending::supply(
@@ -178,6 +179,49 @@ fn can_repay() {
 	});
 }
 
+#[test]
+fn can_repay_on_behalf_and_repay_all() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Lending::supply(Origin::signed(USER1), ASSET1, 100000));
+		assert_ok!(Lending::supply(Origin::signed(USER2), ASSET2, 100000));
+		assert_ok!(Lending::borrow(Origin::signed(USER2), ASSET1, 10000));
+
+		let payer_balance = Assets::get_asset_balance((ASSET1, USER1));
+		let user_balance = Assets::get_asset_balance((ASSET1, USER2));
+		assert_ok!(Lending::repay_on_behalf(Origin::signed(USER1), USER2, ASSET1, 4000));
+		assert_eq!(Assets::get_asset_balance((ASSET1, USER1)), payer_balance - 4000);
+		assert_eq!(Assets::get_asset_balance((ASSET1, USER2)), user_balance);
+		assert_eq!(Lending::user_debt(ASSET1, USER2).unwrap().amount, 6000);
+		assert_noop!(
+			Lending::repay_on_behalf(Origin::signed(USER2), USER1, ASSET1, 4000),
+			Error::<Test>::UserNoDebt,
+		);
+
+		// a year of interest is repaid exactly
+		Timestamp::set_timestamp(6000 + 1000 * 86400 * 365);
+		let debt = Lending::get_user_debt_with_interest(ASSET1, USER2);
+		assert!(debt > 6000);
+		assert_ok!(Lending::repay_all(Origin::signed(USER2), ASSET1));
+		assert_eq!(Assets::get_asset_balance((ASSET1, USER2)), user_balance - debt);
+		assert!(Lending::user_debt(ASSET1, USER2).is_none());
+		assert!(Lending::user_debt_set(USER2).is_empty());
+	});
+}
+
+#[test]
+fn transfer_errors_reach_the_caller() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Lending::supply(Origin::signed(USER2), ASSET1, 500001),
+			pallet_assets::Error::<Test>::InsufficientBalance,
+		);
+		assert_noop!(
+			Lending::supply(Origin::signed(USER2), ASSET1, 0),
+			pallet_assets::Error::<Test>::ZeroAmount,
+		);
+	});
+}
+
 #[test]
 fn can_withdraw() {
 	new_test_ext().execute_with(|| {
@@ -248,3 +292,1458 @@ fn can_liquidate() {
 
 	});
 }
+
+#[test]
+fn jump_rate_model_kinks() {
+	let model = InterestRateModel::JumpRate {
+		base_rate: FixedU128::saturating_from_rational(1, 100),
+		multiplier: FixedU128::saturating_from_rational(10, 100),
+		kink: FixedU128::saturating_from_rational(80, 100),
+		jump_multiplier: FixedU128::saturating_from_integer(2),
+	};
+	assert!(model.is_valid());
+
+	// below the kink: 0.01 + 0.1 * 0.5
+	assert_eq!(model.debt_rate(FixedU128::saturating_from_rational(50, 100)), FixedU128::saturating_from_rational(6, 100));
+	// at the kink: 0.01 + 0.1 * 0.8
+	assert_eq!(model.debt_rate(FixedU128::saturating_from_rational(80, 100)), FixedU128::saturating_from_rational(9, 100));
+	// above the kink: 0.01 + 0.1 * 0.8 + 2 * 0.2
+	assert_eq!(model.debt_rate(FixedU128::one()), FixedU128::saturating_from_rational(49, 100));
+
+	let invalid = InterestRateModel::JumpRate {
+		base_rate: FixedU128::zero(),
+		multiplier: FixedU128::zero(),
+		kink: FixedU128::saturating_from_rational(11, 10),
+		jump_multiplier: FixedU128::zero(),
+	};
+	assert!(!invalid.is_valid());
+}
+
+#[test]
+fn curve_model_interpolates() {
+	let model = InterestRateModel::Curve(vec![
+		(FixedU128::saturating_from_rational(20, 100), FixedU128::saturating_from_rational(2, 100)),
+		(FixedU128::saturating_from_rational(60, 100), FixedU128::saturating_from_rational(10, 100)),
+		(FixedU128::one(), FixedU128::one()),
+	]);
+	assert!(model.is_valid());
+
+	// flat before the first point
+	assert_eq!(model.debt_rate(FixedU128::zero()), FixedU128::saturating_from_rational(2, 100));
+	// half way between the first two points
+	assert_eq!(model.debt_rate(FixedU128::saturating_from_rational(40, 100)), FixedU128::saturating_from_rational(6, 100));
+	// on a point
+	assert_eq!(model.debt_rate(FixedU128::saturating_from_rational(60, 100)), FixedU128::saturating_from_rational(10, 100));
+	// flat after the last point
+	assert_eq!(model.debt_rate(FixedU128::saturating_from_integer(2)), FixedU128::one());
+
+	assert!(!InterestRateModel::Curve(vec![]).is_valid());
+	assert!(!InterestRateModel::Curve(vec![
+		(FixedU128::one(), FixedU128::one()),
+		(FixedU128::zero(), FixedU128::one()),
+	]).is_valid());
+}
+
+#[test]
+fn can_set_interest_rate_model() {
+	new_test_ext().execute_with(|| {
+		let model = InterestRateModel::JumpRate {
+			base_rate: FixedU128::zero(),
+			multiplier: FixedU128::saturating_from_rational(1, 1000000000),
+			kink: FixedU128::saturating_from_rational(80, 100),
+			jump_multiplier: FixedU128::saturating_from_rational(1, 10000000),
+		};
+
+		assert_noop!(
+			Lending::set_interest_rate_model(Origin::signed(USER1), ASSET1, model.clone()),
+			DispatchError::BadOrigin,
+		);
+		assert_noop!(
+			Lending::set_interest_rate_model(Origin::root(), ASSET1, InterestRateModel::Curve(vec![])),
+			Error::<Test>::InvalidInterestRateModel,
+		);
+
+		assert_ok!(Lending::set_interest_rate_model(Origin::root(), ASSET1, model.clone()));
+		assert_eq!(Lending::pool(ASSET1).unwrap().interest_rate_model, model);
+		assert_eq!(Lending::debt_rate(ASSET1), FixedU128::zero());
+	});
+}
+
+#[test]
+fn init_pool_needs_root() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Lending::init_pool(Origin::signed(USER1), 5, true, InterestRateModel::default()),
+			DispatchError::BadOrigin,
+		);
+		assert_noop!(
+			Lending::init_pool(Origin::root(), ASSET1, true, InterestRateModel::default()),
+			Error::<Test>::PoolAlreadyExists,
+		);
+
+		assert_ok!(Lending::init_pool(Origin::root(), 5, false, InterestRateModel::default()));
+		assert!(!Lending::pool(5).unwrap().can_be_collateral);
+	});
+}
+
+#[test]
+fn can_update_risk_parameters() {
+	new_test_ext().execute_with(|| {
+		let half = FixedU128::saturating_from_rational(1, 2);
+
+		assert_noop!(Lending::set_close_factor(Origin::signed(USER1), ASSET1, half), DispatchError::BadOrigin);
+		assert_noop!(Lending::set_close_factor(Origin::root(), 5, half), Error::<Test>::PoolNotExist);
+		assert_noop!(
+			Lending::set_close_factor(Origin::root(), ASSET1, FixedU128::saturating_from_integer(2)),
+			Error::<Test>::ParameterOutOfBounds,
+		);
+		assert_noop!(
+			Lending::set_discount_factor(Origin::root(), ASSET1, FixedU128::zero()),
+			Error::<Test>::ParameterOutOfBounds,
+		);
+		// collateral factor 0.7 <= liquidation threshold 0.8 <= 1
+		assert_noop!(
+			Lending::set_collateral_factor(Origin::root(), ASSET1, FixedU128::saturating_from_rational(9, 10)),
+			Error::<Test>::ParameterOutOfBounds,
+		);
+		assert_noop!(
+			Lending::set_liquidation_threshold(Origin::root(), ASSET1, FixedU128::saturating_from_integer(2)),
+			Error::<Test>::ParameterOutOfBounds,
+		);
+		assert_noop!(
+			Lending::set_liquidation_threshold(Origin::root(), ASSET1, half),
+			Error::<Test>::ParameterOutOfBounds,
+		);
+
+		assert_ok!(Lending::set_close_factor(Origin::root(), ASSET1, half));
+		assert_ok!(Lending::set_discount_factor(Origin::root(), ASSET1, half));
+		assert_ok!(Lending::set_collateral_factor(Origin::root(), ASSET1, half));
+		assert_ok!(Lending::set_can_be_collateral(Origin::root(), ASSET1, false));
+		assert_ok!(Lending::set_liquidation_threshold(Origin::root(), ASSET1, FixedU128::saturating_from_rational(6, 10)));
+
+		let pool = Lending::pool(ASSET1).unwrap();
+		assert_eq!(pool.close_factor, half);
+		assert_eq!(pool.discount_factor, half);
+		assert_eq!(pool.collateral_factor, half);
+		assert_eq!(pool.liquidation_threshold, FixedU128::saturating_from_rational(6, 10));
+		assert!(!pool.can_be_collateral);
+	});
+}
+
+#[test]
+fn reserves_accrue_and_can_be_withdrawn() {
+	new_test_ext().execute_with(|| {
+		let point_one: u128 = 100000000000000000;
+		let treasury: u64 = 3;
+
+		assert_ok!(Lending::set_reserve_factor(Origin::root(), ASSET1, FixedU128::saturating_from_rational(1, 10)));
+
+		assert_ok!(Lending::supply(Origin::signed(USER1), ASSET1, 2 * point_one));
+		assert_ok!(Lending::borrow(Origin::signed(USER1), ASSET1, point_one));
+
+		Timestamp::set_timestamp(66000);
+		assert_eq!(Lending::get_reserves(ASSET1), 2310000261);
+		assert_ok!(Lending::supply(Origin::signed(USER1), ASSET1, 1));
+
+		// debt interest is point_one * ((1 + 3.85e-9) ^ 60 - 1), a tenth of it goes to reserves
+		let pool = Lending::pool(ASSET1).unwrap();
+		assert_eq!(pool.debt, point_one + 23100002614);
+		assert_eq!(pool.reserves, 2310000261);
+		// suppliers get the rest
+		assert_eq!(pool.supply, 2 * point_one + 20790001050 + 1);
+
+		assert_noop!(
+			Lending::withdraw_reserves(Origin::signed(USER1), ASSET1, treasury, 1),
+			DispatchError::BadOrigin,
+		);
+		assert_noop!(
+			Lending::withdraw_reserves(Origin::root(), ASSET1, treasury, pool.reserves + 1),
+			Error::<Test>::NotEnoughReserves,
+		);
+
+		assert_ok!(Lending::withdraw_reserves(Origin::root(), ASSET1, treasury, pool.reserves));
+		assert_eq!(Assets::get_asset_balance((ASSET1, treasury)), 2310000261);
+		assert_eq!(Lending::pool(ASSET1).unwrap().reserves, 0);
+	});
+}
+
+#[test]
+fn supply_and_borrow_caps_are_enforced() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(Lending::set_supply_cap(Origin::signed(USER1), ASSET1, Some(1)), DispatchError::BadOrigin);
+
+		assert_ok!(Lending::set_supply_cap(Origin::root(), ASSET1, Some(100000)));
+		assert_ok!(Lending::set_borrow_cap(Origin::root(), ASSET1, Some(10000)));
+		assert_eq!(Lending::supply_cap(ASSET1), Some(100000));
+		assert_eq!(Lending::borrow_cap(ASSET1), Some(10000));
+		assert_eq!(Lending::supply_cap(ASSET2), None);
+
+		assert_ok!(Lending::supply(Origin::signed(USER1), ASSET1, 60000));
+		assert_noop!(
+			Lending::supply(Origin::signed(USER1), ASSET1, 40001),
+			Error::<Test>::SupplyCapExceeded,
+		);
+		assert_ok!(Lending::supply(Origin::signed(USER1), ASSET1, 40000));
+
+		assert_ok!(Lending::supply(Origin::signed(USER2), ASSET2, 100000));
+		assert_noop!(
+			Lending::borrow(Origin::signed(USER2), ASSET1, 10001),
+			Error::<Test>::BorrowCapExceeded,
+		);
+		assert_ok!(Lending::borrow(Origin::signed(USER2), ASSET1, 10000));
+
+		// lifting the cap allows more
+		assert_ok!(Lending::set_borrow_cap(Origin::root(), ASSET1, None));
+		assert_ok!(Lending::borrow(Origin::signed(USER2), ASSET1, 1));
+	});
+}
+
+#[test]
+fn pause_guardian_can_pause_actions() {
+	new_test_ext().execute_with(|| {
+		let guardian: u64 = 3;
+
+		assert_noop!(Lending::set_pause_guardian(Origin::signed(USER1), Some(guardian)), DispatchError::BadOrigin);
+		assert_ok!(Lending::set_pause_guardian(Origin::root(), Some(guardian)));
+		assert_eq!(Lending::pause_guardian(), Some(guardian));
+
+		assert_ok!(Lending::supply(Origin::signed(USER2), ASSET2, 100000));
+		assert_ok!(Lending::supply(Origin::signed(USER1), ASSET1, 100000));
+
+		// only the guardian can pause
+		assert_noop!(
+			Lending::set_action_paused(Origin::signed(USER1), ASSET1, PoolAction::Borrow, true),
+			Error::<Test>::NotPauseGuardian,
+		);
+		assert_ok!(Lending::set_action_paused(Origin::signed(guardian), ASSET1, PoolAction::Borrow, true));
+		assert!(Lending::is_paused(ASSET1, PoolAction::Borrow));
+
+		assert_noop!(
+			Lending::borrow(Origin::signed(USER2), ASSET1, 10000),
+			Error::<Test>::ActionPaused,
+		);
+		// other actions and pools are not affected
+		assert_ok!(Lending::supply(Origin::signed(USER1), ASSET1, 1));
+		assert_ok!(Lending::borrow(Origin::signed(USER1), ASSET2, 10000));
+
+		// the guardian can not unpause, governance can
+		assert_noop!(
+			Lending::set_action_paused(Origin::signed(guardian), ASSET1, PoolAction::Borrow, false),
+			Error::<Test>::NotPauseGuardian,
+		);
+		assert_ok!(Lending::set_action_paused(Origin::root(), ASSET1, PoolAction::Borrow, false));
+		assert_ok!(Lending::borrow(Origin::signed(USER2), ASSET1, 10000));
+	});
+}
+
+#[test]
+fn disabled_pool_rejects_new_positions() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Lending::supply(Origin::signed(USER1), ASSET1, 100000));
+		assert_ok!(Lending::supply(Origin::signed(USER2), ASSET2, 100000));
+		assert_ok!(Lending::borrow(Origin::signed(USER2), ASSET1, 10000));
+
+		assert_ok!(Lending::set_pool_enabled(Origin::root(), ASSET1, false));
+
+		assert_noop!(Lending::supply(Origin::signed(USER1), ASSET1, 1), Error::<Test>::PoolDisabled);
+		assert_noop!(Lending::borrow(Origin::signed(USER2), ASSET1, 1), Error::<Test>::PoolDisabled);
+		// existing positions can still be closed
+		assert_ok!(Lending::repay(Origin::signed(USER2), ASSET1, 10000));
+		assert_ok!(Lending::withdraw(Origin::signed(USER1), ASSET1, 100000));
+	});
+}
+
+#[test]
+fn can_enable_and_disable_collateral() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Lending::supply(Origin::signed(USER1), ASSET1, 100000));
+		// first supply is enabled as collateral by default
+		assert_ok!(Lending::supply(Origin::signed(USER2), ASSET2, 100000));
+		assert_eq!(Lending::assets_in(Lending::user_collateral_set(USER2)), vec![ASSET2]);
+
+		// 100000 * 60 * 0.7
+		assert_eq!(Lending::get_user_info(USER2), (6000000, 4200000, 0));
+		assert_ok!(Lending::disable_collateral(Origin::signed(USER2), ASSET2));
+		assert_eq!(Lending::get_user_info(USER2), (6000000, 0, 0));
+		assert_noop!(
+			Lending::disable_collateral(Origin::signed(USER2), ASSET2),
+			Error::<Test>::AssetNotCollateral,
+		);
+
+		// supply not enabled as collateral can not be borrowed against or seized
+		assert_noop!(
+			Lending::borrow(Origin::signed(USER2), ASSET1, 10000),
+			Error::<Test>::BelowCollateralFactor,
+		);
+
+		assert_noop!(
+			Lending::enable_collateral(Origin::signed(USER2), ASSET1),
+			Error::<Test>::UserNoSupply,
+		);
+		assert_ok!(Lending::enable_collateral(Origin::signed(USER2), ASSET2));
+		assert_ok!(Lending::borrow(Origin::signed(USER2), ASSET1, 10000));
+
+		// disabling would leave the debt without collateral
+		assert_noop!(
+			Lending::disable_collateral(Origin::signed(USER2), ASSET2),
+			Error::<Test>::BelowCollateralFactor,
+		);
+
+		// pool that can not be collateral can not be enabled
+		assert_ok!(Lending::set_can_be_collateral(Origin::root(), ASSET1, false));
+		assert_noop!(
+			Lending::enable_collateral(Origin::signed(USER1), ASSET1),
+			Error::<Test>::AssetNotCollateral,
+		);
+	});
+}
+
+#[test]
+fn dust_position_is_liquidated_in_total() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Lending::supply(Origin::signed(USER1), ASSET1, 100000));
+		assert_ok!(Lending::supply(Origin::signed(USER2), ASSET2, 100000));
+		assert_ok!(Lending::borrow(Origin::signed(USER2), ASSET1, 10000));
+
+		assert_ok!(Lending::set_close_factor(Origin::root(), ASSET2, FixedU128::saturating_from_rational(1, 2)));
+		assert_ok!(Assets::set_price(Origin::root(), ASSET2, FixedU128::saturating_from_integer(5)));
+
+		// above dust, close factor applies
+		assert_ok!(Lending::liquidate(Origin::signed(USER1), USER2, ASSET1, ASSET2, 10000));
+		assert_eq!(Lending::user_supply(ASSET2, USER2).unwrap().amount, 50000);
+		assert_eq!(Lending::pool(ASSET2).unwrap().supply, 50000);
+
+		// 50000 * 5 is below dust, all of it can be seized
+		assert_ok!(Lending::set_dust_threshold(Origin::root(), 1000000));
+		assert_ok!(Lending::liquidate(Origin::signed(USER1), USER2, ASSET1, ASSET2, 10000));
+		assert!(Lending::user_supply(ASSET2, USER2).is_none());
+		assert!(Lending::user_collateral_set(USER2).is_empty());
+		assert_eq!(Lending::pool(ASSET2).unwrap().supply, 0);
+	});
+}
+
+#[test]
+fn bad_debt_is_written_off() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Lending::supply(Origin::signed(USER1), ASSET1, 100000));
+		assert_ok!(Lending::supply(Origin::signed(USER2), ASSET2, 100000));
+		assert_ok!(Lending::borrow(Origin::signed(USER2), ASSET1, 10000));
+
+		assert_noop!(
+			Lending::write_off_bad_debt(Origin::root(), USER2, ASSET1),
+			Error::<Test>::UserHasCollateral,
+		);
+
+		// collateral crashes, liquidation seizes all of it and leaves debt behind
+		assert_ok!(Assets::set_price(Origin::root(), ASSET2, FixedU128::one()));
+		assert_ok!(Lending::set_dust_threshold(Origin::root(), 1000000));
+		assert_ok!(Lending::liquidate(Origin::signed(USER1), USER2, ASSET1, ASSET2, 10000));
+		// 100000 * 1 / 100 * 0.95
+		assert_eq!(Lending::user_debt(ASSET1, USER2).unwrap().amount, 10000 - 950);
+
+		// supply that is not collateral can still be seized
+		assert_ok!(Lending::set_can_be_collateral(Origin::root(), ASSET2, false));
+		assert_ok!(Lending::supply(Origin::signed(USER2), ASSET2, 100));
+		assert_noop!(
+			Lending::write_off_bad_debt(Origin::root(), USER2, ASSET1),
+			Error::<Test>::UserHasSupply,
+		);
+		UserSupplies::<Test>::remove(ASSET2, USER2);
+		UserSupplySet::<Test>::remove(USER2);
+
+		// suppliers can not absorb all of it
+		Pools::<Test>::mutate(ASSET1, |pool| pool.as_mut().unwrap().supply = 9050);
+		assert_noop!(
+			Lending::write_off_bad_debt(Origin::root(), USER2, ASSET1),
+			Error::<Test>::BadDebtExceedsSupply,
+		);
+		Pools::<Test>::mutate(ASSET1, |pool| {
+			let pool = pool.as_mut().unwrap();
+			pool.supply = 100000;
+			pool.reserves = 5000;
+		});
+
+		assert_noop!(
+			Lending::write_off_bad_debt(Origin::signed(USER1), USER2, ASSET1),
+			DispatchError::BadOrigin,
+		);
+		assert_ok!(Lending::write_off_bad_debt(Origin::root(), USER2, ASSET1));
+
+		assert!(Lending::user_debt(ASSET1, USER2).is_none());
+		assert!(Lending::user_debt_set(USER2).is_empty());
+		let pool = Lending::pool(ASSET1).unwrap();
+		assert_eq!(pool.debt, 0);
+		assert_eq!(pool.reserves, 0);
+		// 9050 - 5000 is taken from suppliers
+		assert_eq!(pool.supply, 100000 - 4050);
+		assert_eq!(Lending::get_user_supply_with_interest(ASSET1, USER1), 100000 - 4050);
+	});
+}
+
+#[test]
+fn interest_compounds_over_long_gaps() {
+	new_test_ext().execute_with(|| {
+		let point_one: u128 = 100000000000000000;
+		let rate = FixedU128::saturating_from_rational(1, 100000);
+		let seconds: u64 = 10000;
+
+		// constant rate so only compounding matters
+		assert_ok!(Lending::set_interest_rate_model(Origin::root(), ASSET1, InterestRateModel::Linear {
+			initial_interest_rate: rate,
+			utilization_factor: FixedU128::zero(),
+		}));
+		assert_ok!(Lending::supply(Origin::signed(USER1), ASSET1, 2 * point_one));
+		assert_ok!(Lending::borrow(Origin::signed(USER1), ASSET1, point_one));
+
+		let mut touched_every_second = Lending::pool(ASSET1).unwrap();
+		let mut touched_once = touched_every_second.clone();
+
+		for second in 7..=(6 + seconds) {
+			Timestamp::set_timestamp(second * 1000);
+			Lending::accrue_interest(&mut touched_every_second);
+		}
+		Lending::accrue_interest(&mut touched_once);
+
+		let tolerance = FixedU128::from_inner(1000000);
+		let (a, b) = (touched_every_second.total_debt_index, touched_once.total_debt_index);
+		assert!(a.max(b) - a.min(b) <= tolerance);
+
+		// simple interest would only give 1 + 1e-5 * 10000 = 1.1
+		assert!(b > FixedU128::saturating_from_rational(1105, 1000));
+		assert!(b < FixedU128::saturating_from_rational(1106, 1000));
+	});
+}
+
+#[test]
+fn migrates_pools_to_time_based_interest() {
+	new_test_ext().execute_with(|| {
+		let point_one: u128 = 100000000000000000;
+		let pool = Lending::pool(ASSET1).unwrap();
+		let old = migrations::PoolV0::<Test> {
+			enabled: pool.enabled,
+			can_be_collateral: pool.can_be_collateral,
+			asset: pool.asset,
+			supply: 2 * point_one,
+			debt: point_one,
+			safe_factor: pool.collateral_factor,
+			close_factor: pool.close_factor,
+			discount_factor: pool.discount_factor,
+			total_supply_index: pool.total_supply_index,
+			total_debt_index: pool.total_debt_index,
+			last_updated: 1,
+			// the former per block defaults
+			utilization_factor: FixedU128::saturating_from_rational(385, 10000000000u64),
+			initial_interest_rate: FixedU128::saturating_from_rational(385, 100000000000u64),
+		};
+		frame_support::storage::unhashed::put(&Pools::<Test>::hashed_key_for(ASSET1), &old);
+		// storage holds only the deployed layout, which has no version
+		Pools::<Test>::remove(ASSET2);
+		StorageVersion::kill();
+
+		System::set_block_number(11);
+		Timestamp::set_timestamp(66000);
+		migrations::migrate::<Test>(6000);
+
+		let pool = Lending::pool(ASSET1).unwrap();
+		assert_eq!(pool.last_updated, 66);
+		// 10 blocks accrued at the per block rates
+		assert_eq!(pool.debt, point_one + 23100002400);
+		assert_eq!(pool.interest_rate_model, InterestRateModel::default());
+		assert_eq!(pool.reserve_factor, FixedU128::zero());
+		assert_eq!(pool.supply_cap, None);
+		assert_eq!(pool.collateral_factor, old.safe_factor);
+		assert_eq!(pool.liquidation_threshold, old.safe_factor);
+		assert_eq!(Lending::storage_version(), Releases::V9);
+
+		// runs only once
+		Timestamp::set_timestamp(126000);
+		migrations::migrate::<Test>(6000);
+		assert_eq!(Lending::pool(ASSET1).unwrap().last_updated, 66);
+	});
+}
+
+#[test]
+fn flash_loan_pays_fee_to_suppliers() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Lending::supply(Origin::signed(USER1), ASSET1, 100000));
+
+		assert_noop!(
+			Lending::set_flash_loan_fee(Origin::signed(USER1), FixedU128::saturating_from_rational(1, 100)),
+			DispatchError::BadOrigin,
+		);
+		assert_noop!(
+			Lending::set_flash_loan_fee(Origin::root(), FixedU128::saturating_from_rational(11, 10)),
+			Error::<Test>::ParameterOutOfBounds,
+		);
+		assert_ok!(Lending::set_flash_loan_fee(Origin::root(), FixedU128::saturating_from_rational(1, 100)));
+
+		let remark = Box::new(mock::Call::System(frame_system::Call::remark(vec![])));
+		assert_noop!(
+			Lending::flash_loan(Origin::signed(USER2), ASSET1, 100001, remark.clone()),
+			Error::<Test>::NotEnoughLiquidity,
+		);
+
+		let balance_before = Assets::get_asset_balance((ASSET1, USER2));
+		assert_ok!(Lending::flash_loan(Origin::signed(USER2), ASSET1, 10000, remark));
+		assert_eq!(balance_before - Assets::get_asset_balance((ASSET1, USER2)), 100);
+
+		let pool = Lending::pool(ASSET1).unwrap();
+		assert_eq!(pool.supply, 100100);
+		assert_eq!(pool.debt, 0);
+		assert_eq!(Lending::get_user_supply_with_interest(ASSET1, USER1), 100100);
+	});
+}
+
+#[test]
+fn flash_loan_reverts_without_repayment() {
+	new_test_ext().execute_with(|| {
+		let borrower: u64 = 3;
+		assert_ok!(Lending::supply(Origin::signed(USER1), ASSET1, 100000));
+		assert_ok!(Lending::set_flash_loan_fee(Origin::root(), FixedU128::saturating_from_rational(1, 100)));
+
+		// the loan is sent away and the borrower can't pay it back
+		let send_away = Box::new(mock::Call::Assets(pallet_assets::Call::transfer_asset(ASSET1, USER2, 10000)));
+		assert_noop!(
+			Lending::flash_loan(Origin::signed(borrower), ASSET1, 10000, send_away),
+			Error::<Test>::FlashLoanNotRepaid,
+		);
+
+		// errors of the call revert the loan too
+		let failing = Box::new(mock::Call::Lending(crate::Call::withdraw(ASSET1, 1)));
+		assert_noop!(
+			Lending::flash_loan(Origin::signed(borrower), ASSET1, 10000, failing),
+			Error::<Test>::UserNoSupply,
+		);
+
+		assert_ok!(Lending::set_action_paused(Origin::root(), ASSET1, PoolAction::FlashLoan, true));
+		assert_noop!(
+			Lending::flash_loan(Origin::signed(USER2), ASSET1, 10000, Box::new(mock::Call::System(frame_system::Call::remark(vec![])))),
+			Error::<Test>::ActionPaused,
+		);
+	});
+}
+
+#[test]
+fn emode_enhances_correlated_collateral() {
+	new_test_ext().execute_with(|| {
+		let category = EModeCategory {
+			collateral_factor: FixedU128::saturating_from_rational(9, 10),
+			liquidation_threshold: FixedU128::saturating_from_rational(95, 100),
+		};
+		assert_noop!(
+			Lending::set_emode_category(Origin::signed(USER1), 1, Some(category.clone())),
+			DispatchError::BadOrigin,
+		);
+		assert_noop!(
+			Lending::set_emode_category(Origin::root(), 1, Some(EModeCategory {
+				collateral_factor: FixedU128::saturating_from_rational(9, 10),
+				liquidation_threshold: FixedU128::saturating_from_rational(8, 10),
+			})),
+			Error::<Test>::ParameterOutOfBounds,
+		);
+		assert_noop!(
+			Lending::set_asset_emode_category(Origin::root(), ASSET1, Some(1)),
+			Error::<Test>::EModeCategoryNotExist,
+		);
+		assert_ok!(Lending::set_emode_category(Origin::root(), 1, Some(category)));
+		assert_ok!(Lending::set_asset_emode_category(Origin::root(), ASSET1, Some(1)));
+		assert_ok!(Lending::set_asset_emode_category(Origin::root(), ASSET2, Some(1)));
+
+		assert_ok!(Lending::supply(Origin::signed(USER1), ASSET1, 100000));
+		assert_ok!(Lending::supply(Origin::signed(USER2), ASSET2, 100000));
+
+		// 100000 * 60 * 0.7 only covers 42000 of asset 1
+		assert_noop!(
+			Lending::borrow(Origin::signed(USER2), ASSET1, 50000),
+			Error::<Test>::BelowCollateralFactor,
+		);
+
+		assert_noop!(
+			Lending::set_user_emode(Origin::signed(USER2), Some(2)),
+			Error::<Test>::EModeCategoryNotExist,
+		);
+		assert_ok!(Lending::set_user_emode(Origin::signed(USER2), Some(1)));
+		// 100000 * 60 * 0.9
+		assert_eq!(Lending::get_user_info(USER2), (6000000, 5400000, 0));
+		assert_ok!(Lending::borrow(Origin::signed(USER2), ASSET1, 50000));
+
+		// the position only holds in efficiency mode
+		assert_noop!(
+			Lending::set_user_emode(Origin::signed(USER2), None),
+			Error::<Test>::BelowCollateralFactor,
+		);
+		assert_noop!(
+			Lending::liquidate(Origin::signed(USER1), USER2, ASSET1, ASSET2, 10000),
+			Error::<Test>::AboveLiquidationThreshold,
+		);
+		assert_noop!(
+			Lending::withdraw(Origin::signed(USER2), ASSET2, 10000),
+			Error::<Test>::BelowCollateralFactor,
+		);
+
+		// debt must stay in the category
+		assert_ok!(Lending::set_asset_emode_category(Origin::root(), ASSET1, None));
+		assert_noop!(
+			Lending::borrow(Origin::signed(USER2), ASSET1, 1),
+			Error::<Test>::AssetNotInEModeCategory,
+		);
+		assert_noop!(
+			Lending::set_user_emode(Origin::signed(USER2), Some(1)),
+			Error::<Test>::AssetNotInEModeCategory,
+		);
+	});
+}
+
+#[test]
+fn liquidation_threshold_leaves_buffer_above_collateral_factor() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Lending::supply(Origin::signed(USER1), ASSET1, 100000));
+		assert_ok!(Lending::supply(Origin::signed(USER2), ASSET2, 100000));
+
+		// 100000 * 60 * 0.7
+		assert_ok!(Lending::borrow(Origin::signed(USER2), ASSET1, 42000));
+		assert_noop!(
+			Lending::borrow(Origin::signed(USER2), ASSET1, 1),
+			Error::<Test>::BelowCollateralFactor,
+		);
+		// 100000 * 60 * 0.8 still covers the debt
+		assert_noop!(
+			Lending::liquidate(Origin::signed(USER1), USER2, ASSET1, ASSET2, 1000),
+			Error::<Test>::AboveLiquidationThreshold,
+		);
+
+		// 100000 * 55 * 0.8 > 4200000
+		assert_ok!(Assets::set_price(Origin::root(), ASSET2, FixedU128::saturating_from_integer(55)));
+		assert_noop!(
+			Lending::liquidate(Origin::signed(USER1), USER2, ASSET1, ASSET2, 1000),
+			Error::<Test>::AboveLiquidationThreshold,
+		);
+
+		// 100000 * 52 * 0.8 < 4200000
+		assert_ok!(Assets::set_price(Origin::root(), ASSET2, FixedU128::saturating_from_integer(52)));
+		assert_ok!(Lending::liquidate(Origin::signed(USER1), USER2, ASSET1, ASSET2, 1000));
+	});
+}
+
+#[test]
+fn migrates_safe_factor_to_collateral_factor_and_liquidation_threshold() {
+	new_test_ext().execute_with(|| {
+		let pool = Lending::pool(ASSET1).unwrap();
+		let old = migrations::PoolV5::<Test> {
+			enabled: pool.enabled,
+			can_be_collateral: pool.can_be_collateral,
+			asset: pool.asset,
+			supply: pool.supply,
+			debt: pool.debt,
+			safe_factor: FixedU128::saturating_from_rational(75, 100),
+			close_factor: pool.close_factor,
+			discount_factor: pool.discount_factor,
+			total_supply_index: pool.total_supply_index,
+			total_debt_index: pool.total_debt_index,
+			last_updated: pool.last_updated,
+			interest_rate_model: pool.interest_rate_model,
+			reserve_factor: pool.reserve_factor,
+			reserves: pool.reserves,
+			supply_cap: pool.supply_cap,
+			borrow_cap: pool.borrow_cap,
+		};
+		frame_support::storage::unhashed::put(&Pools::<Test>::hashed_key_for(ASSET1), &old);
+		// storage holds only the old layout
+		Pools::<Test>::remove(ASSET2);
+		frame_support::storage::unhashed::put(&EModeCategories::hashed_key_for(1), &migrations::EModeCategoryV5 {
+			safe_factor: FixedU128::saturating_from_rational(9, 10),
+			liquidation_threshold: FixedU128::saturating_from_rational(9, 8),
+		});
+		frame_support::storage::migration::put_storage_value(
+			b"Lending", b"LiquidationThreshold", &[], FixedU128::saturating_from_rational(5, 4),
+		);
+		StorageVersion::put(Releases::V5);
+
+		migrations::migrate::<Test>(6000);
+
+		// 0.75 / 1.25
+		let pool = Lending::pool(ASSET1).unwrap();
+		assert_eq!(pool.collateral_factor, FixedU128::saturating_from_rational(6, 10));
+		assert_eq!(pool.liquidation_threshold, FixedU128::saturating_from_rational(6, 10));
+		// 0.9 / 1.125
+		assert_eq!(Lending::emode_category(1), Some(EModeCategory {
+			collateral_factor: FixedU128::saturating_from_rational(8, 10),
+			liquidation_threshold: FixedU128::saturating_from_rational(8, 10),
+		}));
+		assert!(frame_support::storage::migration::get_storage_value::<FixedU128>(b"Lending", b"LiquidationThreshold", &[]).is_none());
+		assert_eq!(Lending::storage_version(), Releases::V9);
+	});
+}
+
+#[test]
+fn auction_sells_collateral_at_descending_price() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Lending::supply(Origin::signed(USER1), ASSET1, 100000));
+		assert_ok!(Lending::supply(Origin::signed(USER2), ASSET2, 100000));
+		assert_ok!(Lending::borrow(Origin::signed(USER2), ASSET1, 2000));
+
+		assert_noop!(
+			Lending::start_auction(Origin::signed(USER1), USER2, ASSET1, ASSET2),
+			Error::<Test>::AboveLiquidationThreshold,
+		);
+		// 100000 * 2 * 0.8 < 2000 * 100
+		assert_ok!(Assets::set_price(Origin::root(), ASSET2, FixedU128::saturating_from_integer(2)));
+		assert_ok!(Lending::start_auction(Origin::signed(USER1), USER2, ASSET1, ASSET2));
+		assert_noop!(
+			Lending::start_auction(Origin::signed(USER1), USER2, ASSET1, ASSET2),
+			Error::<Test>::AuctionInProgress,
+		);
+
+		// the collateral is held by the auction
+		assert!(Lending::user_supply(ASSET2, USER2).is_none());
+		let auction = Lending::auction(0).unwrap();
+		assert_eq!(auction.collateral, 100000);
+		assert_eq!(auction.debt, 2000);
+		// 0.02 * 1.1 down to 0.02 * 0.8
+		assert_eq!(auction.start_price, FixedU128::saturating_from_rational(22, 1000));
+		assert_eq!(auction.end_price, FixedU128::saturating_from_rational(16, 1000));
+
+		let (pay_before, get_before) = (Assets::get_asset_balance((ASSET1, USER1)), Assets::get_asset_balance((ASSET2, USER1)));
+		assert_ok!(Lending::bid(Origin::signed(USER1), 0, 50000));
+		// 50000 * 0.022
+		assert_eq!(pay_before - Assets::get_asset_balance((ASSET1, USER1)), 1100);
+		assert_eq!(Assets::get_asset_balance((ASSET2, USER1)) - get_before, 50000);
+		assert_eq!(Lending::user_debt(ASSET1, USER2).unwrap().amount, 900);
+
+		// half way the price is 0.019, the last 900 of debt buys 900 / 0.019 of collateral
+		Timestamp::set_timestamp(906000);
+		assert_ok!(Lending::bid(Origin::signed(USER1), 0, 100000));
+		assert_eq!(Assets::get_asset_balance((ASSET2, USER1)) - get_before, 50000 + 47368);
+		assert!(Lending::user_debt(ASSET1, USER2).is_none());
+
+		// the rest goes back to the borrower
+		assert!(Lending::auction(0).is_none());
+		assert!(Lending::user_auction(USER2).is_none());
+		assert!(!AuctionExpiries::contains_key(6 + 1800));
+		assert!(Lending::auction_expiry_times().is_empty());
+		assert_eq!(Lending::get_user_supply_with_interest(ASSET2, USER2), 100000 - 50000 - 47368);
+		assert_eq!(Lending::assets_in(Lending::user_collateral_set(USER2)), vec![ASSET2]);
+		assert_eq!(Lending::pool(ASSET2).unwrap().supply, 100000 - 50000 - 47368);
+	});
+}
+
+#[test]
+fn expired_auction_is_closed_on_initialize() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Lending::set_auction_config(Origin::root(), AuctionConfig {
+				duration: 600,
+				start_factor: FixedU128::saturating_from_rational(9, 10),
+				end_factor: FixedU128::one(),
+			}),
+			Error::<Test>::ParameterOutOfBounds,
+		);
+		assert_ok!(Lending::set_auction_config(Origin::root(), AuctionConfig {
+			duration: 600,
+			start_factor: FixedU128::one(),
+			end_factor: FixedU128::saturating_from_rational(9, 10),
+		}));
+
+		assert_ok!(Lending::supply(Origin::signed(USER1), ASSET1, 100000));
+		assert_ok!(Lending::supply(Origin::signed(USER2), ASSET2, 100000));
+		assert_ok!(Lending::borrow(Origin::signed(USER2), ASSET1, 2000));
+		assert_ok!(Assets::set_price(Origin::root(), ASSET2, FixedU128::saturating_from_integer(2)));
+		assert_ok!(Lending::start_auction(Origin::signed(USER1), USER2, ASSET1, ASSET2));
+		assert_eq!(Lending::auction_expiry_times(), vec![606]);
+
+		Lending::on_initialize(2);
+		assert!(Lending::auction(0).is_some());
+
+		Timestamp::set_timestamp(606000);
+		assert_noop!(Lending::bid(Origin::signed(USER1), 0, 1000), Error::<Test>::AuctionExpired);
+		Lending::on_initialize(3);
+
+		assert!(Lending::auction(0).is_none());
+		assert!(Lending::auction_expiry_times().is_empty());
+		assert_noop!(Lending::bid(Origin::signed(USER1), 0, 1000), Error::<Test>::AuctionNotExist);
+		assert_eq!(Lending::get_user_supply_with_interest(ASSET2, USER2), 100000);
+		assert_eq!(Lending::user_debt(ASSET1, USER2).unwrap().amount, 2000);
+	});
+}
+
+#[test]
+fn user_position_breaks_down_supply_and_debt() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Lending::supply(Origin::signed(USER1), ASSET1, 100000));
+		assert_ok!(Lending::supply(Origin::signed(USER2), ASSET2, 100000));
+		assert_ok!(Lending::borrow(Origin::signed(USER2), ASSET1, 10000));
+
+		let position = Lending::get_user_position(USER2);
+		assert_eq!(position.assets.len(), 2);
+		assert_eq!(position.assets[0].asset_id, ASSET2);
+		assert_eq!(position.assets[0].supply, 100000);
+		assert_eq!(position.assets[0].debt, 0);
+		assert_eq!(position.assets[0].price, FixedU128::saturating_from_integer(60));
+		assert!(position.assets[0].is_collateral);
+		assert_eq!(position.assets[0].collateral_factor, FixedU128::saturating_from_rational(7, 10));
+		assert_eq!(position.assets[0].liquidation_threshold, FixedU128::saturating_from_rational(8, 10));
+		assert_eq!(position.assets[1].asset_id, ASSET1);
+		assert_eq!(position.assets[1].supply, 0);
+		assert_eq!(position.assets[1].debt, 10000);
+
+		assert_eq!(position.supply_value, 6000000);
+		assert_eq!(position.collateral_value, 6000000);
+		assert_eq!(position.borrow_limit, 4200000);
+		assert_eq!(position.liquidation_threshold_value, 4800000);
+		assert_eq!(position.debt_value, 1000000);
+		assert_eq!(position.health_factor, Some(FixedU128::saturating_from_rational(48, 10)));
+		assert_eq!(Lending::get_user_info(USER2), (6000000, 4200000, 1000000));
+
+		// interest is included without a transaction
+		Timestamp::set_timestamp(6000 + 1000 * 86400 * 365);
+		let position = Lending::get_user_position(USER2);
+		assert!(position.assets[1].debt > 10000);
+		assert_eq!(position.assets[1].debt, Lending::get_user_debt_with_interest(ASSET1, USER2));
+
+		// no debt, no health factor
+		assert_eq!(Lending::get_user_position(USER1).health_factor, None);
+	});
+}
+
+#[test]
+fn supply_mints_and_withdraw_burns_ktokens() {
+	new_test_ext().execute_with(|| {
+		let ktoken = Lending::ktoken(ASSET1).unwrap();
+		assert_eq!(Lending::ktoken_underlying(ktoken), Some(ASSET1));
+
+		assert_ok!(Lending::supply(Origin::signed(USER1), ASSET1, 100000));
+		assert_eq!(Assets::balance(ktoken, USER1), 100000);
+
+		// the exchange rate follows the supply index
+		Pools::<Test>::mutate(ASSET1, |pool| pool.as_mut().unwrap().total_supply_index = FixedU128::saturating_from_rational(5, 4));
+		assert_ok!(Lending::supply(Origin::signed(USER1), ASSET1, 50000));
+		assert_eq!(Lending::user_supply(ASSET1, USER1).unwrap().amount, 125000 + 50000);
+		assert_eq!(Assets::balance(ktoken, USER1), 100000 + 40000);
+
+		assert_ok!(Lending::withdraw(Origin::signed(USER1), ASSET1, 75000));
+		assert_eq!(Assets::balance(ktoken, USER1), 80000);
+		assert_eq!(Assets::total_supply(ktoken), 80000);
+
+		// only withdrawing burns them
+		assert_noop!(
+			mock::Call::Assets(pallet_assets::Call::destroy(ktoken)).dispatch(Origin::signed(USER1)),
+			Error::<Test>::KTokenNotDestroyable,
+		);
+
+		assert_noop!(
+			Lending::init_pool(Origin::root(), ktoken, true, InterestRateModel::default()),
+			Error::<Test>::PoolOfKToken,
+		);
+	});
+}
+
+#[test]
+fn ktoken_transfer_moves_supply() {
+	new_test_ext().execute_with(|| {
+		let ktoken = Lending::ktoken(ASSET1).unwrap();
+		assert_ok!(Lending::supply(Origin::signed(USER1), ASSET1, 100000));
+		Pools::<Test>::mutate(ASSET1, |pool| pool.as_mut().unwrap().total_supply_index = FixedU128::saturating_from_integer(2));
+
+		assert_ok!(Assets::transfer_asset(Origin::signed(USER1), ktoken, USER2, 40000));
+
+		assert_eq!(Assets::balance(ktoken, USER1), 60000);
+		assert_eq!(Assets::balance(ktoken, USER2), 40000);
+		assert_eq!(Lending::user_supply(ASSET1, USER1).unwrap().amount, 120000);
+		assert_eq!(Lending::user_supply(ASSET1, USER2).unwrap().amount, 80000);
+		assert_eq!(Lending::assets_in(Lending::user_supply_set(USER2)), vec![ASSET1]);
+		// the receiver opts in to use it as collateral
+		assert!(Lending::user_collateral_set(USER2).is_empty());
+		assert_ok!(Lending::enable_collateral(Origin::signed(USER2), ASSET1));
+		assert_eq!(Lending::assets_in(Lending::user_collateral_set(USER2)), vec![ASSET1]);
+		assert_eq!(Lending::pool(ASSET1).unwrap().supply, 100000);
+
+		// the receiver can withdraw what it got
+		assert_ok!(Lending::withdraw(Origin::signed(USER2), ASSET1, 80000));
+		assert_eq!(Assets::balance(ktoken, USER2), 0);
+		assert!(Lending::user_supply(ASSET1, USER2).is_none());
+	});
+}
+
+#[test]
+fn ktoken_transfer_keeps_sender_healthy() {
+	new_test_ext().execute_with(|| {
+		let ktoken = Lending::ktoken(ASSET2).unwrap();
+		assert_ok!(Lending::supply(Origin::signed(USER1), ASSET1, 100000));
+		assert_ok!(Lending::supply(Origin::signed(USER2), ASSET2, 100000));
+		// 100000 * 60 * 0.7 is the limit
+		assert_ok!(Lending::borrow(Origin::signed(USER2), ASSET1, 40000));
+
+		assert_noop!(
+			Assets::transfer_asset(Origin::signed(USER2), ktoken, USER1, 10000),
+			Error::<Test>::BelowCollateralFactor,
+		);
+		assert_ok!(Assets::transfer_asset(Origin::signed(USER2), ktoken, USER1, 4000));
+		assert_eq!(Lending::user_supply(ASSET2, USER2).unwrap().amount, 96000);
+
+		// seized collateral burns the kTokens of the target
+		assert_ok!(Assets::set_price(Origin::root(), ASSET2, FixedU128::saturating_from_integer(40)));
+		assert_ok!(Lending::liquidate(Origin::signed(USER1), USER2, ASSET1, ASSET2, 1900));
+		// 1900 * 100 / 40 / 0.95 rounds down
+		assert_eq!(Lending::user_supply(ASSET2, USER2).unwrap().amount, 96000 - 4999);
+		assert_eq!(Assets::balance(ktoken, USER2), 96000 - 4999);
+	});
+}
+
+#[test]
+fn migrates_supplies_to_ktokens() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Lending::supply(Origin::signed(USER1), ASSET1, 100000));
+		// storage holds no kTokens yet
+		for asset_id in vec![ASSET1, ASSET2] {
+			let ktoken = KTokens::<Test>::take(asset_id).unwrap();
+			KTokenUnderlying::<Test>::remove(ktoken);
+		}
+		StorageVersion::put(Releases::V6);
+
+		migrations::migrate::<Test>(6000);
+
+		let ktoken = Lending::ktoken(ASSET1).unwrap();
+		assert_eq!(Lending::ktoken_underlying(ktoken), Some(ASSET1));
+		assert!(Lending::ktoken(ASSET2).is_some());
+		assert_eq!(Assets::balance(ktoken, USER1), 100000);
+		assert_eq!(Lending::storage_version(), Releases::V9);
+	});
+}
+
+#[test]
+fn stable_borrow_locks_rate() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Lending::supply(Origin::signed(USER1), ASSET1, 100000));
+		assert_ok!(Lending::supply(Origin::signed(USER2), ASSET2, 100000));
+		assert_noop!(
+			Lending::borrow_stable(Origin::signed(USER2), ASSET1, 10000),
+			Error::<Test>::StableBorrowDisabled,
+		);
+		assert_eq!(Lending::stable_debt_rate(ASSET1), None);
+
+		let premium = FixedU128::saturating_from_rational(1, 10000000000u64);
+		assert_ok!(Lending::set_stable_rate_config(Origin::root(), ASSET1, Some(StableRateConfig {
+			premium,
+			rebalance_spread: FixedU128::zero(),
+		})));
+		assert_ok!(Lending::borrow_stable(Origin::signed(USER2), ASSET1, 10000));
+		assert_noop!(
+			Lending::borrow(Origin::signed(USER2), ASSET1, 1000),
+			Error::<Test>::RateModeMismatch,
+		);
+
+		// the variable rate after the borrow plus the premium
+		let rate = InterestRateModel::default().debt_rate(FixedU128::saturating_from_rational(1, 10)) + premium;
+		assert_eq!(Lending::user_debt(ASSET1, USER2).unwrap().stable_rate, Some(rate));
+		let pool = Lending::pool(ASSET1).unwrap();
+		assert_eq!(pool.stable_debt, 10000);
+		assert_eq!(pool.average_stable_rate, rate);
+
+		// more variable debt raises the variable rate, the stable debt still grows at its rate
+		assert_ok!(Lending::borrow(Origin::signed(USER1), ASSET1, 50000));
+		Timestamp::set_timestamp(6000 + 1000 * 86400 * 365);
+		let debt = interest_rate::compound(rate, 86400 * 365).saturating_mul_int(10000u128);
+		assert_eq!(Lending::get_user_debt_with_interest(ASSET1, USER2), debt);
+
+		assert_ok!(Lending::repay_all(Origin::signed(USER2), ASSET1));
+		let pool = Lending::pool(ASSET1).unwrap();
+		assert_eq!(pool.stable_debt, 0);
+		assert_eq!(pool.average_stable_rate, FixedU128::zero());
+		assert!(Lending::user_debt(ASSET1, USER2).is_none());
+	});
+}
+
+#[test]
+fn stable_rate_rebalances_when_variable_rate_drifts_above() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Lending::supply(Origin::signed(USER1), ASSET1, 100000));
+		assert_ok!(Lending::supply(Origin::signed(USER2), ASSET2, 100000));
+		let premium = FixedU128::saturating_from_rational(1, 10000000000u64);
+		assert_ok!(Lending::set_stable_rate_config(Origin::root(), ASSET1, Some(StableRateConfig {
+			premium,
+			rebalance_spread: FixedU128::saturating_from_rational(2, 1000000000u64),
+		})));
+		assert_ok!(Lending::borrow_stable(Origin::signed(USER2), ASSET1, 10000));
+		let locked = Lending::user_debt(ASSET1, USER2).unwrap().stable_rate.unwrap();
+
+		assert_noop!(
+			Lending::rebalance_stable_rate(Origin::signed(USER1), USER2, ASSET1),
+			Error::<Test>::RebalanceNotAllowed,
+		);
+		assert_noop!(
+			Lending::rebalance_stable_rate(Origin::signed(USER1), USER1, ASSET1),
+			Error::<Test>::UserNoDebt,
+		);
+
+		// utilization goes from 0.1 to 0.7
+		assert_ok!(Lending::borrow(Origin::signed(USER1), ASSET1, 60000));
+		assert_noop!(
+			Lending::rebalance_stable_rate(Origin::signed(USER2), USER1, ASSET1),
+			Error::<Test>::RateModeMismatch,
+		);
+		assert_ok!(Lending::rebalance_stable_rate(Origin::signed(USER1), USER2, ASSET1));
+
+		let rate = Lending::debt_rate(ASSET1) + premium;
+		assert!(rate > locked);
+		assert_eq!(Lending::user_debt(ASSET1, USER2).unwrap().stable_rate, Some(rate));
+		assert_eq!(Lending::stable_debt_rate(ASSET1), Some(rate));
+		let pool = Lending::pool(ASSET1).unwrap();
+		assert_eq!(pool.stable_debt, 10000);
+		assert_eq!(pool.average_stable_rate, rate);
+	});
+}
+
+#[test]
+fn migrates_debts_to_stable_rate_accounting() {
+	new_test_ext().execute_with(|| {
+		let pool = Lending::pool(ASSET1).unwrap();
+		let old = migrations::PoolV7::<Test> {
+			enabled: pool.enabled,
+			can_be_collateral: pool.can_be_collateral,
+			asset: pool.asset,
+			supply: 100000,
+			debt: 10000,
+			collateral_factor: pool.collateral_factor,
+			liquidation_threshold: pool.liquidation_threshold,
+			close_factor: pool.close_factor,
+			discount_factor: pool.discount_factor,
+			total_supply_index: pool.total_supply_index,
+			total_debt_index: pool.total_debt_index,
+			last_updated: pool.last_updated,
+			interest_rate_model: pool.interest_rate_model,
+			reserve_factor: pool.reserve_factor,
+			reserves: pool.reserves,
+			supply_cap: pool.supply_cap,
+			borrow_cap: pool.borrow_cap,
+		};
+		frame_support::storage::unhashed::put(&Pools::<Test>::hashed_key_for(ASSET1), &old);
+		// storage holds only the old layout
+		Pools::<Test>::remove(ASSET2);
+		frame_support::storage::unhashed::put(&UserDebts::<Test>::hashed_key_for(ASSET1, USER2), &migrations::UserDebtV7::<Test> {
+			amount: 10000,
+			index: FixedU128::saturating_from_rational(11, 10),
+		});
+		StorageVersion::put(Releases::V7);
+
+		migrations::migrate::<Test>(6000);
+
+		let pool = Lending::pool(ASSET1).unwrap();
+		assert_eq!(pool.debt, 10000);
+		assert_eq!(pool.stable_debt, 0);
+		assert_eq!(pool.stable_rate_config, None);
+		let user_debt = Lending::user_debt(ASSET1, USER2).unwrap();
+		assert_eq!(user_debt.amount, 10000);
+		assert_eq!(user_debt.index, FixedU128::saturating_from_rational(11, 10));
+		assert_eq!(user_debt.stable_rate, None);
+		assert_eq!(user_debt.last_updated, 6);
+		assert_eq!(Lending::storage_version(), Releases::V9);
+	});
+}
+
+#[test]
+fn delegatee_borrows_against_delegator_collateral() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Lending::supply(Origin::signed(USER1), ASSET1, 100000));
+		assert_ok!(Lending::supply(Origin::signed(USER2), ASSET2, 100000));
+		assert_noop!(
+			Lending::approve_delegation(Origin::signed(USER2), USER1, 2, 10000),
+			Error::<Test>::PoolNotExist,
+		);
+		assert_noop!(
+			Lending::borrow_on_behalf(Origin::signed(USER1), USER2, ASSET1, 1000),
+			Error::<Test>::DelegationExceeded,
+		);
+
+		assert_ok!(Lending::approve_delegation(Origin::signed(USER2), USER1, ASSET1, 10000));
+		let delegatee_balance = Assets::get_asset_balance((ASSET1, USER1));
+		let delegator_balance = Assets::get_asset_balance((ASSET1, USER2));
+		assert_ok!(Lending::borrow_on_behalf(Origin::signed(USER1), USER2, ASSET1, 6000));
+
+		// the debt is the delegator's, the funds the delegatee's
+		assert_eq!(Lending::user_debt(ASSET1, USER2).unwrap().amount, 6000);
+		assert_eq!(Lending::assets_in(Lending::user_debt_set(USER2)), vec![ASSET1]);
+		assert!(Lending::user_debt(ASSET1, USER1).is_none());
+		assert_eq!(Assets::get_asset_balance((ASSET1, USER1)), delegatee_balance + 6000);
+		assert_eq!(Assets::get_asset_balance((ASSET1, USER2)), delegator_balance);
+		assert_eq!(Lending::borrow_allowance(USER2, (USER1, ASSET1)), 4000);
+		assert_noop!(
+			Lending::borrow_on_behalf(Origin::signed(USER1), USER2, ASSET1, 4001),
+			Error::<Test>::DelegationExceeded,
+		);
+
+		// the allowance does not lift the collateral check of the delegator
+		assert_ok!(Lending::approve_delegation(Origin::signed(USER2), USER1, ASSET1, 100000));
+		assert_noop!(
+			Lending::borrow_on_behalf(Origin::signed(USER1), USER2, ASSET1, 50000),
+			Error::<Test>::BelowCollateralFactor,
+		);
+
+		assert_ok!(Lending::revoke_delegation(Origin::signed(USER2), USER1, ASSET1));
+		assert_eq!(Lending::borrow_allowance(USER2, (USER1, ASSET1)), 0);
+		assert_noop!(
+			Lending::revoke_delegation(Origin::signed(USER2), USER1, ASSET1),
+			Error::<Test>::DelegationNotExist,
+		);
+		assert_noop!(
+			Lending::borrow_on_behalf(Origin::signed(USER1), USER2, ASSET1, 1000),
+			Error::<Test>::DelegationExceeded,
+		);
+	});
+}
+
+#[test]
+fn user_asset_sets_are_capped() {
+	new_test_ext().execute_with(|| {
+		// user 1 supplies and borrows as many assets as allowed
+		let mut assets = vec![];
+		for _ in 0..MAX_USER_ASSETS {
+			let id = <Assets as MintableMultiAsset<_, _, _>>::create_asset();
+			assert_ok!(<Assets as MintableMultiAsset<_, _, _>>::mint(id, USER1, 1000000));
+			assert_ok!(Assets::set_price(Origin::root(), id, FixedU128::one()));
+			assert_ok!(Lending::init_pool(Origin::root(), id, true, InterestRateModel::default()));
+			assert_ok!(Lending::supply(Origin::signed(USER1), id, 1000));
+			assert_ok!(Lending::borrow(Origin::signed(USER1), id, 10));
+			assets.push(id);
+		}
+		assert_eq!(Lending::user_supply_set(USER1).len(), MAX_USER_ASSETS);
+		assert_eq!(Lending::user_debt_set(USER1).len(), MAX_USER_ASSETS);
+		assert_eq!(Lending::assets_in(Lending::user_debt_set(USER1)), assets);
+
+		assert_noop!(
+			Lending::supply(Origin::signed(USER1), ASSET1, 1000),
+			Error::<Test>::TooManyAssets,
+		);
+		assert_ok!(Lending::supply(Origin::signed(USER2), ASSET2, 100000));
+		assert_noop!(
+			Lending::borrow(Origin::signed(USER1), ASSET2, 10),
+			Error::<Test>::TooManyAssets,
+		);
+		// kTokens do not bring in a new asset either
+		let ktoken = Lending::ktoken(ASSET2).unwrap();
+		assert_noop!(
+			Assets::transfer_asset(Origin::signed(USER2), ktoken, USER1, 1000),
+			Error::<Test>::TooManyAssets,
+		);
+		// assets already in the sets are fine
+		assert_ok!(Lending::supply(Origin::signed(USER1), assets[0], 1000));
+		assert_ok!(Lending::borrow(Origin::signed(USER1), assets[0], 10));
+
+		// leaving a market makes room for another
+		assert_ok!(Lending::repay_all(Origin::signed(USER1), assets[0]));
+		assert_ok!(Lending::borrow(Origin::signed(USER1), ASSET2, 10));
+		assert!(Lending::assets_in(Lending::user_debt_set(USER1)).contains(&ASSET2));
+	});
+}
+
+#[test]
+fn migrates_user_asset_sets_to_bit_sets() {
+	new_test_ext().execute_with(|| {
+		// storage holds only the old layout
+		PoolIndices::<Test>::remove(ASSET1);
+		PoolIndices::<Test>::remove(ASSET2);
+		PoolAssets::<Test>::remove(0);
+		PoolAssets::<Test>::remove(1);
+		PoolCount::kill();
+		let put_set = |key: Vec<u8>, assets: Vec<u64>| frame_support::storage::unhashed::put(&key, &assets);
+		put_set(UserSupplySet::<Test>::hashed_key_for(USER1), vec![ASSET2, ASSET1]);
+		put_set(UserCollateralSet::<Test>::hashed_key_for(USER1), vec![ASSET2]);
+		put_set(UserDebtSet::<Test>::hashed_key_for(USER2), vec![ASSET1]);
+		// an asset without a pool is dropped, and a set left empty with it
+		put_set(UserSupplySet::<Test>::hashed_key_for(USER2), vec![5]);
+		StorageVersion::put(Releases::V8);
+
+		migrations::migrate::<Test>(6000);
+
+		// pools are indexed in the order of asset ids
+		assert_eq!(Lending::pool_index(ASSET1), Some(0));
+		assert_eq!(Lending::pool_index(ASSET2), Some(1));
+		assert_eq!(Lending::pool_asset(1), Some(ASSET2));
+		assert_eq!(Lending::pool_count(), 2);
+		assert_eq!(Lending::assets_in(Lending::user_supply_set(USER1)), vec![ASSET1, ASSET2]);
+		assert_eq!(Lending::assets_in(Lending::user_collateral_set(USER1)), vec![ASSET2]);
+		assert_eq!(Lending::assets_in(Lending::user_debt_set(USER2)), vec![ASSET1]);
+		assert!(!UserSupplySet::<Test>::contains_key(USER2));
+		assert_eq!(Lending::storage_version(), Releases::V9);
+
+		// the migrated sets back the position checks
+		assert_ok!(Lending::supply(Origin::signed(USER2), ASSET1, 100000));
+		assert_ok!(Lending::borrow(Origin::signed(USER2), ASSET1, 1000));
+		assert_noop!(
+			Lending::disable_collateral(Origin::signed(USER2), ASSET1),
+			Error::<Test>::BelowCollateralFactor,
+		);
+	});
+}
+
+#[test]
+fn migrates_collateral_sets_from_supply_sets() {
+	new_test_ext().execute_with(|| {
+		let put_pool = |id: u64, can_be_collateral: bool| {
+			let pool = Lending::pool(id).unwrap();
+			frame_support::storage::unhashed::put(&Pools::<Test>::hashed_key_for(id), &migrations::PoolV3::<Test> {
+				enabled: pool.enabled,
+				can_be_collateral,
+				asset: pool.asset,
+				supply: pool.supply,
+				debt: pool.debt,
+				safe_factor: pool.collateral_factor,
+				close_factor: pool.close_factor,
+				discount_factor: pool.discount_factor,
+				total_supply_index: pool.total_supply_index,
+				total_debt_index: pool.total_debt_index,
+				last_updated: 1,
+				interest_rate_model: pool.interest_rate_model,
+				reserve_factor: pool.reserve_factor,
+				reserves: pool.reserves,
+				supply_cap: pool.supply_cap,
+				borrow_cap: pool.borrow_cap,
+			});
+		};
+		// storage holds only the old layout, without collateral sets
+		put_pool(ASSET1, true);
+		put_pool(ASSET2, false);
+		let put_set = |key: Vec<u8>, assets: Vec<u64>| frame_support::storage::unhashed::put(&key, &assets);
+		put_set(UserSupplySet::<Test>::hashed_key_for(USER1), vec![ASSET1, ASSET2]);
+		put_set(UserSupplySet::<Test>::hashed_key_for(USER2), vec![ASSET2]);
+		StorageVersion::put(Releases::V3);
+
+		migrations::migrate::<Test>(6000);
+
+		// only the assets that can be collateral are enabled
+		assert_eq!(Lending::assets_in(Lending::user_collateral_set(USER1)), vec![ASSET1]);
+		assert!(!UserCollateralSet::<Test>::contains_key(USER2));
+		assert_eq!(Lending::assets_in(Lending::user_supply_set(USER1)), vec![ASSET1, ASSET2]);
+		assert_eq!(Lending::assets_in(Lending::user_supply_set(USER2)), vec![ASSET2]);
+		assert_eq!(Lending::storage_version(), Releases::V9);
+	});
+}
+
+#[test]
+fn expired_auctions_are_closed_in_order_up_to_a_cap() {
+	new_test_ext().execute_with(|| {
+		let queue = |auction_id: AuctionId, ends_at: u64| {
+			let target_user = 100 + auction_id as u64;
+			Auctions::<Test>::insert(auction_id, Auction::<Test> {
+				target_user,
+				pay_asset_id: ASSET1,
+				get_asset_id: ASSET2,
+				collateral: 0,
+				debt: 1000,
+				start_price: FixedU128::one(),
+				end_price: FixedU128::one(),
+				started_at: 0,
+				ends_at,
+			});
+			AuctionExpiries::append(ends_at, auction_id);
+			UserAuction::<Test>::insert(target_user, auction_id);
+		};
+		for auction_id in 0..MAX_AUCTION_CLOSES + 2 {
+			queue(auction_id, 10);
+		}
+		queue(100, 9);
+		AuctionExpiryTimes::put(vec![9, 10]);
+
+		// nothing expired yet
+		Lending::on_initialize(2);
+		assert_eq!(Auctions::<Test>::iter().count() as u32, MAX_AUCTION_CLOSES + 3);
+
+		// the earliest expiry goes first, and the rest of the cap after it
+		Timestamp::set_timestamp(10000);
+		Lending::on_initialize(3);
+		assert!(Lending::auction(100).is_none());
+		assert!(Lending::user_auction(200).is_none());
+		assert_eq!(Auctions::<Test>::iter().count(), 3);
+		assert_eq!(Lending::auction_expiries(10).len(), 3);
+		assert_eq!(Lending::auction_expiry_times(), vec![10]);
+
+		Lending::on_initialize(4);
+		assert!(Auctions::<Test>::iter().next().is_none());
+		assert!(!AuctionExpiries::contains_key(10));
+		assert!(Lending::auction_expiry_times().is_empty());
+	});
+}
+
+fn lending_events() -> Vec<Event<Test>> {
+	System::events().into_iter().filter_map(|record| match record.event {
+		TestEvent::lending(event) => Some(event),
+		_ => None,
+	}).collect()
+}
+
+#[test]
+fn state_changes_emit_events() {
+	new_test_ext().execute_with(|| {
+		let id = <Assets as MintableMultiAsset<_, _, _>>::create_asset();
+		assert_ok!(Lending::init_pool(Origin::root(), id, true, InterestRateModel::default()));
+		let ktoken = Lending::ktoken(id).unwrap();
+		assert!(lending_events().contains(&RawEvent::PoolCreated(id, 2, ktoken)));
+
+		assert_ok!(Lending::supply(Origin::signed(USER1), ASSET1, 100000));
+		assert_ok!(Lending::supply(Origin::signed(USER2), ASSET2, 100000));
+		assert_ok!(Lending::borrow(Origin::signed(USER2), ASSET1, 10000));
+		assert_ok!(Assets::set_price(Origin::root(), ASSET2, FixedU128::saturating_from_integer(10)));
+
+		Timestamp::set_timestamp(6000 + 1000 * 86400);
+		System::reset_events();
+		let pay_balance = Assets::get_asset_balance((ASSET1, USER1));
+		let seized_balance = Assets::get_asset_balance((ASSET2, USER1));
+		assert_ok!(Lending::liquidate(Origin::signed(USER1), USER2, ASSET1, ASSET2, 5000));
+		let paid = pay_balance - Assets::get_asset_balance((ASSET1, USER1));
+		let seized = Assets::get_asset_balance((ASSET2, USER1)) - seized_balance;
+
+		// the stored state can be followed from the events alone
+		let events = lending_events();
+		assert!(events.contains(&RawEvent::Liquidated(ASSET1, ASSET2, USER1, USER2, paid, seized)));
+		let pool = Lending::pool(ASSET1).unwrap();
+		assert!(events.contains(&RawEvent::InterestAccrued(ASSET1, pool.total_supply_index, pool.total_debt_index, pool.supply, pool.debt)));
+		let user_supply = Lending::user_supply(ASSET2, USER2).unwrap();
+		let get_pool = Lending::pool(ASSET2).unwrap();
+		assert_eq!(
+			events.iter().rev().find(|event| matches!(event, RawEvent::UserPositionUpdated(ASSET2, USER2, ..))),
+			Some(&RawEvent::UserPositionUpdated(ASSET2, USER2, user_supply.amount, user_supply.index, 0, get_pool.total_debt_index, None)),
+		);
+		let user_debt = Lending::user_debt(ASSET1, USER2).unwrap();
+		assert_eq!(
+			events.iter().rev().find(|event| matches!(event, RawEvent::UserPositionUpdated(ASSET1, USER2, ..))),
+			Some(&RawEvent::UserPositionUpdated(ASSET1, USER2, 0, pool.total_supply_index, user_debt.amount, user_debt.index, None)),
+		);
+	});
+}
+
+#[test]
+fn stale_or_missing_prices_are_rejected() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Lending::set_max_price_age(Origin::root(), 5, Some(60)),
+			Error::<Test>::PoolNotExist,
+		);
+		assert_ok!(Lending::set_max_price_age(Origin::root(), ASSET1, Some(60)));
+		assert_ok!(Lending::set_max_price_age(Origin::root(), ASSET2, Some(60)));
+		assert_eq!(Lending::max_price_age(ASSET2), Some(60));
+
+		// genesis prices are six seconds old
+		assert_ok!(Lending::supply(Origin::signed(USER1), ASSET1, 100000));
+		assert_ok!(Lending::supply(Origin::signed(USER2), ASSET2, 100000));
+		assert_ok!(Lending::borrow(Origin::signed(USER2), ASSET1, 1000));
+
+		Timestamp::set_timestamp(6000 + 1000 * 61);
+		assert_noop!(
+			Lending::borrow(Origin::signed(USER2), ASSET1, 1000),
+			Error::<Test>::PriceStale,
+		);
+		assert_noop!(
+			Lending::withdraw(Origin::signed(USER2), ASSET2, 1000),
+			Error::<Test>::PriceStale,
+		);
+		// a position without debt is not valued
+		assert_ok!(Lending::withdraw(Origin::signed(USER1), ASSET1, 1000));
+
+		// both the borrowed asset and the collateral need fresh prices
+		assert_ok!(Assets::set_price(Origin::root(), ASSET2, FixedU128::saturating_from_integer(60)));
+		assert_noop!(
+			Lending::borrow(Origin::signed(USER2), ASSET1, 1000),
+			Error::<Test>::PriceStale,
+		);
+		assert_ok!(Assets::set_price(Origin::root(), ASSET1, FixedU128::saturating_from_integer(100)));
+		assert_ok!(Lending::borrow(Origin::signed(USER2), ASSET1, 1000));
+
+		// a zero price is missing
+		assert_ok!(Assets::set_price(Origin::root(), ASSET2, FixedU128::zero()));
+		assert_noop!(
+			Lending::withdraw(Origin::signed(USER2), ASSET2, 1000),
+			Error::<Test>::PriceMissing,
+		);
+
+		// an unhealthy position is not liquidated at a stale price
+		assert_ok!(Assets::set_price(Origin::root(), ASSET2, FixedU128::saturating_from_integer(1)));
+		Timestamp::set_timestamp(6000 + 1000 * 200);
+		assert_noop!(
+			Lending::liquidate(Origin::signed(USER1), USER2, ASSET1, ASSET2, 1000),
+			Error::<Test>::PriceStale,
+		);
+		assert_eq!(Lending::max_liquidation(USER2, ASSET1, ASSET2), None);
+
+		// without a max age any price is fresh enough
+		assert_ok!(Lending::set_max_price_age(Origin::root(), ASSET1, None));
+		assert_ok!(Lending::set_max_price_age(Origin::root(), ASSET2, None));
+		assert_ok!(Lending::liquidate(Origin::signed(USER1), USER2, ASSET1, ASSET2, 1000));
+	});
+}
+
+#[test]
+fn collateral_changes_need_fresh_prices() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Lending::set_max_price_age(Origin::root(), ASSET1, Some(60)));
+		assert_ok!(Lending::set_max_price_age(Origin::root(), ASSET2, Some(60)));
+		assert_ok!(Lending::supply(Origin::signed(USER1), ASSET1, 100000));
+		assert_ok!(Lending::supply(Origin::signed(USER2), ASSET1, 50000));
+		assert_ok!(Lending::supply(Origin::signed(USER2), ASSET2, 100000));
+		assert_ok!(Lending::borrow(Origin::signed(USER2), ASSET1, 1000));
+		let ktoken = Lending::ktoken(ASSET1).unwrap();
+
+		Timestamp::set_timestamp(6000 + 1000 * 61);
+		assert_noop!(
+			Lending::disable_collateral(Origin::signed(USER2), ASSET1),
+			Error::<Test>::PriceStale,
+		);
+		assert_noop!(
+			Lending::set_user_emode(Origin::signed(USER2), None),
+			Error::<Test>::PriceStale,
+		);
+		assert_noop!(
+			Assets::transfer_asset(Origin::signed(USER2), ktoken, USER1, 1000),
+			Error::<Test>::PriceStale,
+		);
+		// a position without debt is not valued
+		assert_ok!(Lending::disable_collateral(Origin::signed(USER1), ASSET1));
+
+		assert_ok!(Assets::set_price(Origin::root(), ASSET1, FixedU128::saturating_from_integer(100)));
+		assert_ok!(Assets::set_price(Origin::root(), ASSET2, FixedU128::saturating_from_integer(100)));
+		assert_ok!(Assets::transfer_asset(Origin::signed(USER2), ktoken, USER1, 1000));
+		assert_ok!(Lending::set_user_emode(Origin::signed(USER2), None));
+		assert_ok!(Lending::disable_collateral(Origin::signed(USER2), ASSET1));
+	});
+}
+
+#[test]
+fn can_liquidate_in_batch() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Lending::supply(Origin::signed(USER1), ASSET1, 100000));
+		assert_ok!(Lending::supply(Origin::signed(USER1), ASSET2, 100000));
+		assert_ok!(Lending::supply(Origin::signed(USER2), ASSET2, 100000));
+		assert_ok!(Lending::supply(Origin::signed(USER2), ASSET1, 10000));
+		assert_ok!(Lending::borrow(Origin::signed(USER2), ASSET1, 20000));
+		assert_ok!(Lending::borrow(Origin::signed(USER2), ASSET2, 30000));
+		assert_ok!(Lending::set_close_factor(Origin::root(), ASSET2, FixedU128::saturating_from_rational(1, 2)));
+
+		let liquidations = vec![(ASSET1, ASSET2, 10000), (ASSET2, ASSET2, 30000), (ASSET1, ASSET1, 1000)];
+		assert_noop!(
+			Lending::liquidate_batch(Origin::signed(USER1), USER2, liquidations.clone()),
+			Error::<Test>::AboveLiquidationThreshold,
+		);
+		assert_noop!(
+			Lending::liquidate_batch(Origin::signed(USER1), USER2, vec![(ASSET1, ASSET2, 1); MAX_BATCH_LIQUIDATIONS as usize + 1]),
+			Error::<Test>::TooManyLiquidations,
+		);
+
+		assert_ok!(Assets::set_price(Origin::root(), ASSET2, FixedU128::saturating_from_integer(20)));
+		assert_noop!(
+			Lending::liquidate_batch(Origin::signed(USER1), USER2, vec![]),
+			Error::<Test>::NothingToLiquidate,
+		);
+		System::reset_events();
+		assert_ok!(Lending::liquidate_batch(Origin::signed(USER1), USER2, liquidations));
+
+		// the first pair seizes all the close factor allows, so the second seizes nothing more
+		assert_eq!(Lending::user_supply(ASSET2, USER2).unwrap().amount, 50000);
+		assert_eq!(Lending::user_debt(ASSET2, USER2).unwrap().amount, 30000);
+		assert_eq!(Lending::user_supply(ASSET1, USER2).unwrap().amount, 10000 - 1052);
+		assert_eq!(Lending::user_debt(ASSET1, USER2).unwrap().amount, 20000 - 9500 - 1000);
+		assert!(lending_events().contains(&RawEvent::BatchLiquidated(
+			USER1,
+			USER2,
+			vec![(ASSET1, ASSET2, 9500, 50000), (ASSET1, ASSET1, 1000, 1052)],
+		)));
+		assert!(!lending_events().iter().any(|event| matches!(event, RawEvent::Liquidated(..))));
+	});
+}
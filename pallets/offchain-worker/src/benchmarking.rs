@@ -0,0 +1,19 @@
+//! Offchain worker pallet benchmarking.
+
+#![cfg(feature = "runtime-benchmarks")]
+
+use super::*;
+use frame_system::RawOrigin;
+use frame_support::ensure;
+use frame_benchmarking::benchmarks;
+
+benchmarks! {
+	_ { }
+
+	submit_price_unsigned {
+	}: _(RawOrigin::None, 1u32.into(), 12345)
+	verify {
+		let asset_id = <T as assets::Trait>::AssetId::from(4u32);
+		ensure!(<assets::Module<T>>::price(asset_id) == FixedU128::saturating_from_rational(12345, 100), "Price was not set.");
+	}
+}
@@ -9,6 +9,7 @@ use frame_support::{
 	debug,
 	dispatch::DispatchResult, decl_module, decl_storage, decl_event,
 	traits::Get,
+	weights::Weight,
 };
 use sp_runtime::{
 	FixedU128, FixedPointNumber,
@@ -22,6 +23,13 @@ use sp_std::vec::Vec;
 use lite_json::json::JsonValue;
 use pallet_assets as assets;
 
+mod benchmarking;
+mod default_weights;
+
+pub trait WeightInfo {
+	fn submit_price_unsigned() -> Weight;
+}
+
 /// This pallet's configuration trait
 pub trait Trait: CreateSignedTransaction<Call<Self>> + assets::Trait {
 	/// The overarching event type.
@@ -32,6 +40,8 @@ pub trait Trait: CreateSignedTransaction<Call<Self>> + assets::Trait {
 	type UnsignedInterval: Get<Self::BlockNumber>;
 	/// A configuration for base priority of unsigned transactions.
 	type UnsignedPriority: Get<TransactionPriority>;
+	/// Weight information for extrinsics in this pallet.
+	type WeightInfo: WeightInfo;
 }
 
 decl_storage! {
@@ -53,7 +63,7 @@ decl_module! {
 	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
 		fn deposit_event() = default;
 
-		#[weight = 0]
+		#[weight = <T as Trait>::WeightInfo::submit_price_unsigned()]
 		pub fn submit_price_unsigned(origin, _block_number: T::BlockNumber, price: u32)
 			-> DispatchResult
 		{
@@ -0,0 +1,81 @@
+//! Swap pallet benchmarking.
+
+#![cfg(feature = "runtime-benchmarks")]
+
+use super::*;
+use sp_std::result::Result;
+use frame_system::RawOrigin;
+use frame_support::{ensure, traits::UnfilteredDispatchable};
+use frame_benchmarking::{benchmarks, whitelisted_caller};
+
+use crate::Module as Swap;
+
+const ISSUED: u32 = 1_000_000_000;
+const LIQUIDITY: u32 = 1_000_000;
+
+/// issue a new asset to `owner`, returning its id
+fn issue<T: Trait>(owner: T::AccountId) -> T::AssetId {
+    let id = <assets::Module<T>>::next_asset_id();
+    assets::Call::<T>::issue(ISSUED.into())
+        .dispatch_bypass_filter(RawOrigin::Signed(owner).into())
+        .expect("issue never fails");
+    id
+}
+
+/// the inherent asset and two paired assets, with both pools funded by `owner`
+fn setup_pools<T: Trait>(owner: T::AccountId) -> Result<(T::AssetId, T::AssetId), &'static str> {
+    let inherent_asset_id = issue::<T>(owner.clone());
+    <assets::Module<T>>::set_inherent_asset(RawOrigin::Signed(owner.clone()).into(), inherent_asset_id)?;
+    let asset_a = issue::<T>(owner.clone());
+    let asset_b = issue::<T>(owner.clone());
+    Swap::<T>::_add_liquidity(RawOrigin::Signed(owner.clone()).into(), asset_a, LIQUIDITY.into(), LIQUIDITY.into(), Zero::zero())?;
+    Swap::<T>::_add_liquidity(RawOrigin::Signed(owner).into(), asset_b, LIQUIDITY.into(), LIQUIDITY.into(), Zero::zero())?;
+    Ok((asset_a, asset_b))
+}
+
+benchmarks! {
+    _ { }
+
+    set_fee_rate {
+        let caller: T::AccountId = whitelisted_caller();
+    }: _(RawOrigin::Signed(caller), 3u32.into())
+    verify {
+        ensure!(Swap::<T>::fee_rate() == 3u32.into(), "Fee rate was not set.");
+    }
+
+    // swapping between two paired assets goes through both pools
+    swap_assets_with_exact_input {
+        let caller: T::AccountId = whitelisted_caller();
+        let (asset_a, asset_b) = setup_pools::<T>(caller.clone())?;
+        let balance = <assets::Module<T>>::balance(asset_b, caller.clone());
+    }: _(RawOrigin::Signed(caller.clone()), caller.clone(), asset_a, asset_b, 1_000u32.into(), 1u32.into())
+    verify {
+        ensure!(<assets::Module<T>>::balance(asset_b, caller) > balance, "Assets were not swapped.");
+    }
+
+    swap_assets_with_exact_output {
+        let caller: T::AccountId = whitelisted_caller();
+        let (asset_a, asset_b) = setup_pools::<T>(caller.clone())?;
+        let balance = <assets::Module<T>>::balance(asset_b, caller.clone());
+    }: _(RawOrigin::Signed(caller.clone()), caller.clone(), asset_a, asset_b, 1_000u32.into(), LIQUIDITY.into())
+    verify {
+        ensure!(<assets::Module<T>>::balance(asset_b, caller) > balance, "Assets were not swapped.");
+    }
+
+    // adding to a funded pool mints liquidity in proportion
+    add_liquidity {
+        let caller: T::AccountId = whitelisted_caller();
+        let (asset_a, _) = setup_pools::<T>(caller.clone())?;
+    }: _(RawOrigin::Signed(caller.clone()), asset_a, 1_000u32.into(), 1_000u32.into(), Zero::zero())
+    verify {
+        ensure!(Swap::<T>::total_liquidity(asset_a) == (LIQUIDITY + 1_000).into(), "Liquidity was not added.");
+    }
+
+    remove_liquidity {
+        let caller: T::AccountId = whitelisted_caller();
+        let (asset_a, _) = setup_pools::<T>(caller.clone())?;
+    }: _(RawOrigin::Signed(caller.clone()), asset_a, 1_000u32.into(), Zero::zero(), Zero::zero())
+    verify {
+        ensure!(Swap::<T>::total_liquidity(asset_a) == (LIQUIDITY - 1_000).into(), "Liquidity was not removed.");
+    }
+}
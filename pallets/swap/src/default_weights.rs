@@ -0,0 +1,34 @@
+//! Weights for pallet_swap, from its benchmarks run natively with 20 repeats over 14 steps.
+//! Regenerate with the node's `benchmark` command against the wasm runtime.
+
+#![allow(unused_parens)]
+
+use frame_support::weights::{Weight, constants::RocksDbWeight as DbWeight};
+
+impl crate::WeightInfo for () {
+	fn set_fee_rate() -> Weight {
+		(885_000 as Weight)
+			.saturating_add(DbWeight::get().reads(1 as Weight))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	fn swap_assets_with_exact_input() -> Weight {
+		(80_182_000 as Weight)
+			.saturating_add(DbWeight::get().reads(13 as Weight))
+			.saturating_add(DbWeight::get().writes(6 as Weight))
+	}
+	fn swap_assets_with_exact_output() -> Weight {
+		(78_154_000 as Weight)
+			.saturating_add(DbWeight::get().reads(12 as Weight))
+			.saturating_add(DbWeight::get().writes(6 as Weight))
+	}
+	fn add_liquidity() -> Weight {
+		(59_877_000 as Weight)
+			.saturating_add(DbWeight::get().reads(10 as Weight))
+			.saturating_add(DbWeight::get().writes(7 as Weight))
+	}
+	fn remove_liquidity() -> Weight {
+		(59_463_000 as Weight)
+			.saturating_add(DbWeight::get().reads(10 as Weight))
+			.saturating_add(DbWeight::get().writes(6 as Weight))
+	}
+}
@@ -1,10 +1,11 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 use frame_support::{
-    decl_event, decl_module, decl_storage, Parameter,
+    decl_event, decl_module, decl_storage, transactional, Parameter,
     StorageMap, StorageValue,
+    weights::Weight,
 };
-use sp_runtime::DispatchResult as Result;
+use sp_runtime::{DispatchError, DispatchResult as Result};
 use frame_system::{self as system, ensure_signed};
 use sp_core::crypto::{UncheckedFrom, UncheckedInto};
 use sp_std::prelude::*;
@@ -14,6 +15,17 @@ use sp_runtime::traits::{
 };
 use pallet_assets as assets;
 
+mod benchmarking;
+mod default_weights;
+
+pub trait WeightInfo {
+    fn set_fee_rate() -> Weight;
+    fn swap_assets_with_exact_input() -> Weight;
+    fn swap_assets_with_exact_output() -> Weight;
+    fn add_liquidity() -> Weight;
+    fn remove_liquidity() -> Weight;
+}
+
 /// The module's configuration trait.
 pub trait Trait: assets::Trait {
     /// The overarching event type.
@@ -22,6 +34,8 @@ pub trait Trait: assets::Trait {
     type ExchangeAddress: ExchangeFactory<<Self as assets::Trait>::AssetId, Self::AccountId>;
     /// The global fee rate
     type FeeRate: Parameter + AtLeast32BitUnsigned + Default + Copy;
+    /// Weight information for extrinsics in this pallet.
+    type WeightInfo: WeightInfo;
 }
 
 decl_event!(
@@ -68,7 +82,7 @@ decl_module! {
         /// Set global fee rate, need root permission
         /// @origin
         /// @fee_rate    the global fee rate on each transaction
-        #[weight = 1]
+        #[weight = <T as Trait>::WeightInfo::set_fee_rate()]
         pub fn set_fee_rate(origin, fee_rate: T::FeeRate) -> Result {
             //ensure_root(origin)?;
             <FeeRateGlobal<T>>::mutate(|fr| *fr = fee_rate);
@@ -82,7 +96,9 @@ decl_module! {
         /// @asset_input       Input asset id
         /// @asset_output      Output asset id
         /// @input_amount      The exact input amount of input asset
-        #[weight = 1]
+        #[weight = <T as Trait>::WeightInfo::swap_assets_with_exact_input()
+            .saturating_add(<assets::Module<T>>::transfer_hook_weight().saturating_mul(3))]
+        #[transactional]
         pub fn swap_assets_with_exact_input(
             origin,
             output_account: T::AccountId,
@@ -90,50 +106,7 @@ decl_module! {
             asset_output: T::AssetId,
             input_amount: T::Balance,
             min_output: T::Balance) -> Result {
-
-            let input_account = ensure_signed(origin)?;
-            let inherent_asset_id = <assets::Module<T>>::inherent_asset_id();
-            // check
-
-
-            let fee_rate = Self::fee_rate();
-            if asset_input == inherent_asset_id {
-                // inherent asset to another asset
-                Self::inherent_asset_to_paired_asset_with_exact_input(
-                    input_account,
-                    output_account,
-                    asset_output,
-                    input_amount,
-                    min_output,
-                    fee_rate
-                )?;
-            }
-            else if asset_output == inherent_asset_id {
-                // another asset to inherent asset
-                Self::paired_asset_to_inherent_asset_with_exact_input(
-                    input_account,
-                    output_account,
-                    asset_input,
-                    input_amount,
-                    min_output,
-                    fee_rate
-                )?;
-            }
-            else {
-                // asset A to asset B
-                Self::asset_a_to_asset_b_with_exact_input(
-                    input_account,
-                    output_account,
-                    asset_input,
-                    asset_output,
-                    input_amount,
-                    min_output,
-                    fee_rate
-                )?;
-
-            }
-
-            Ok(())
+            Self::_swap_assets_with_exact_input(origin, output_account, asset_input, asset_output, input_amount, min_output)
         }
 
         /// Swap two assets, output is exact
@@ -143,7 +116,9 @@ decl_module! {
         /// @asset_output      Output asset id
         /// @output_amount     The exact output amount of output asset
         /// @max_input         The limitation of max amount input asset
-        #[weight = 1]
+        #[weight = <T as Trait>::WeightInfo::swap_assets_with_exact_output()
+            .saturating_add(<assets::Module<T>>::transfer_hook_weight().saturating_mul(3))]
+        #[transactional]
         pub fn swap_assets_with_exact_output(
             origin,
             output_account: T::AccountId,
@@ -151,50 +126,7 @@ decl_module! {
             asset_output: T::AssetId,
             output_amount: T::Balance,
             max_input: T::Balance) -> Result {
-
-            let input_account = ensure_signed(origin)?;
-            let inherent_asset_id = <assets::Module<T>>::inherent_asset_id();
-            // check
-
-
-            let fee_rate = Self::fee_rate();
-            if asset_input == inherent_asset_id {
-                // inherent asset to another asset
-                Self::inherent_asset_to_paired_asset_with_exact_output(
-                    input_account,
-                    output_account,
-                    asset_output,
-                    output_amount,
-                    max_input,
-                    fee_rate
-                )?;
-            }
-            else if asset_output == inherent_asset_id {
-                // another asset to inherent asset
-                Self::paired_asset_to_inherent_asset_with_exact_output(
-                    input_account,
-                    output_account,
-                    asset_input,
-                    output_amount,
-                    max_input,
-                    fee_rate
-                )?;
-            }
-            else {
-                // asset A to asset B
-                Self::asset_a_to_asset_b_with_exact_output(
-                    input_account,
-                    output_account,
-                    asset_input,
-                    asset_output,
-                    output_amount,
-                    max_input,
-                    fee_rate
-                )?;
-
-            }
-
-            Ok(())
+            Self::_swap_assets_with_exact_output(origin, output_account, asset_input, asset_output, output_amount, max_input)
         }
 
         /// Add liquidity to a pool
@@ -203,26 +135,22 @@ decl_module! {
         /// @inherent_asset_amount    The exact amount of inherent asset to be injected
         /// @asset_amount             The amount of paired asset to be injected
         /// @min_liquidity            The minimum liquidity required to be injected once
-        #[weight = 1]
+        #[weight = <T as Trait>::WeightInfo::add_liquidity()
+            .saturating_add(<assets::Module<T>>::transfer_hook_weight().saturating_mul(2))]
+        #[transactional]
         pub fn add_liquidity(
             origin,
             asset_id: T::AssetId,
             inherent_asset_amount: T::Balance,
             asset_amount: T::Balance,
             min_liquidity: T::Balance) -> Result {
-            let account = ensure_signed(origin)?;
-
             Self::_add_liquidity(
-                account.clone(),
+                origin,
                 asset_id,
                 inherent_asset_amount,
                 asset_amount,
                 min_liquidity
-            );
-
-            Self::deposit_event(RawEvent::LiquidityAdded(account, inherent_asset_amount, asset_id));
-
-            Ok(())
+            )
         }
 
         /// Remove liquidity from a pool
@@ -231,32 +159,148 @@ decl_module! {
         /// @liquidity   The exact amount liquidity to be removed
         /// @min_inherent_asset_amount    The minimum amount of inherent asset to be removed
         /// @min_asset_amount             The minimum amount of paired asset to be removed
-        #[weight = 1]
+        #[weight = <T as Trait>::WeightInfo::remove_liquidity()
+            .saturating_add(<assets::Module<T>>::transfer_hook_weight().saturating_mul(2))]
+        #[transactional]
         fn remove_liquidity(
             origin,
             asset_id: T::AssetId,
             liquidity: T::Balance,
             min_inherent_asset_amount: T::Balance,
             min_asset_amount: T::Balance) -> Result {
-            let account = ensure_signed(origin)?;
-
             Self::_remove_liquidity(
-                account.clone(),
+                origin,
                 asset_id,
                 liquidity,
                 min_inherent_asset_amount,
                 min_asset_amount,
-            );
-
-            Self::deposit_event(RawEvent::LiquidityRemoved(account, liquidity, asset_id));
-
-            Ok(())
+            )
         }
         
     }
 }
 
 impl<T: Trait> Module<T> {
+    /// Swap two assets, input is exact
+    /// @origin
+    /// @output_account    The recipient of output asset
+    /// @asset_input       Input asset id
+    /// @asset_output      Output asset id
+    /// @input_amount      The exact input amount of input asset
+    /// @min_output        The limitation of min amount output asset
+    fn _swap_assets_with_exact_input(
+        origin: T::Origin,
+        output_account: T::AccountId,
+        asset_input: T::AssetId,
+        asset_output: T::AssetId,
+        input_amount: T::Balance,
+        min_output: T::Balance,
+    ) -> Result {
+        let input_account = ensure_signed(origin)?;
+        let inherent_asset_id = <assets::Module<T>>::inherent_asset_id();
+        // check
+
+
+        let fee_rate = Self::fee_rate();
+        if asset_input == inherent_asset_id {
+            // inherent asset to another asset
+            Self::inherent_asset_to_paired_asset_with_exact_input(
+                input_account,
+                output_account,
+                asset_output,
+                input_amount,
+                min_output,
+                fee_rate
+            )?;
+        }
+        else if asset_output == inherent_asset_id {
+            // another asset to inherent asset
+            Self::paired_asset_to_inherent_asset_with_exact_input(
+                input_account,
+                output_account,
+                asset_input,
+                input_amount,
+                min_output,
+                fee_rate
+            )?;
+        }
+        else {
+            // asset A to asset B
+            Self::asset_a_to_asset_b_with_exact_input(
+                input_account,
+                output_account,
+                asset_input,
+                asset_output,
+                input_amount,
+                min_output,
+                fee_rate
+            )?;
+
+        }
+
+        Ok(())
+    }
+
+    /// Swap two assets, output is exact
+    /// @origin
+    /// @output_account    The recipient of output asset
+    /// @asset_input       Input asset id
+    /// @asset_output      Output asset id
+    /// @output_amount     The exact output amount of output asset
+    /// @max_input         The limitation of max amount input asset
+    fn _swap_assets_with_exact_output(
+        origin: T::Origin,
+        output_account: T::AccountId,
+        asset_input: T::AssetId,
+        asset_output: T::AssetId,
+        output_amount: T::Balance,
+        max_input: T::Balance,
+    ) -> Result {
+        let input_account = ensure_signed(origin)?;
+        let inherent_asset_id = <assets::Module<T>>::inherent_asset_id();
+        // check
+
+
+        let fee_rate = Self::fee_rate();
+        if asset_input == inherent_asset_id {
+            // inherent asset to another asset
+            Self::inherent_asset_to_paired_asset_with_exact_output(
+                input_account,
+                output_account,
+                asset_output,
+                output_amount,
+                max_input,
+                fee_rate
+            )?;
+        }
+        else if asset_output == inherent_asset_id {
+            // another asset to inherent asset
+            Self::paired_asset_to_inherent_asset_with_exact_output(
+                input_account,
+                output_account,
+                asset_input,
+                output_amount,
+                max_input,
+                fee_rate
+            )?;
+        }
+        else {
+            // asset A to asset B
+            Self::asset_a_to_asset_b_with_exact_output(
+                input_account,
+                output_account,
+                asset_input,
+                asset_output,
+                output_amount,
+                max_input,
+                fee_rate
+            )?;
+
+        }
+
+        Ok(())
+    }
+
     /// Input inherent asset, output paired asset, with exact input amount
     /// @input_account    The account to send inherent asset to paired pool
     /// @output_account   The account to receive paired asset from paired pool
@@ -271,7 +315,7 @@ impl<T: Trait> Module<T> {
         input_amount: T::Balance,
         min_output_amount: T::Balance,
         fee_rate: T::FeeRate,
-    ) -> sp_std::result::Result<T::Balance, &'static str> {
+    ) -> sp_std::result::Result<T::Balance, DispatchError> {
         let paired_asset_output_amount =
             Self::calc_paired_asset_output_amount(paired_asset_id, input_amount, fee_rate)?;
 
@@ -289,13 +333,13 @@ impl<T: Trait> Module<T> {
             inherent_asset_id.clone(),
             exchange_address.clone(),
             input_amount,
-        );
+        )?;
         <assets::Module<T>>::transfer(
             exchange_address.clone(),
             paired_asset_id.clone(),
             output_account,
             paired_asset_output_amount,
-        );
+        )?;
 
         // debug
         sp_runtime::print("----> exchange inherent asset balance, exchange paired asset balance");
@@ -357,7 +401,7 @@ impl<T: Trait> Module<T> {
         output_amount: T::Balance,
         max_input_amount: T::Balance,
         fee_rate: T::FeeRate,
-    ) -> sp_std::result::Result<T::Balance, &'static str> {
+    ) -> sp_std::result::Result<T::Balance, DispatchError> {
         let inherent_asset_input_amount =
             Self::calc_inherent_asset_input_amount(paired_asset_id, output_amount, fee_rate)?;
 
@@ -375,13 +419,13 @@ impl<T: Trait> Module<T> {
             inherent_asset_id,
             exchange_address.clone(),
             inherent_asset_input_amount,
-        );
+        )?;
         <assets::Module<T>>::transfer(
             exchange_address.clone(),
             paired_asset_id.clone(),
             output_account,
             output_amount,
-        );
+        )?;
 
         // debug
         sp_runtime::print("----> exchange inherent asset balance, exchange paired asset balance");
@@ -443,7 +487,7 @@ impl<T: Trait> Module<T> {
         input_amount: T::Balance,
         min_output_amount: T::Balance,
         fee_rate: T::FeeRate,
-    ) -> sp_std::result::Result<T::Balance, &'static str> {
+    ) -> sp_std::result::Result<T::Balance, DispatchError> {
         let inherent_asset_output_amount =
             Self::calc_inherent_asset_output_amount(paired_asset_id, input_amount, fee_rate)?;
 
@@ -463,13 +507,13 @@ impl<T: Trait> Module<T> {
             paired_asset_id.clone(),
             exchange_address.clone(),
             input_amount,
-        );
+        )?;
         <assets::Module<T>>::transfer(
             exchange_address.clone(),
             inherent_asset_id.clone(),
             output_account,
             inherent_asset_output_amount,
-        );
+        )?;
 
         // debug
         sp_runtime::print("----> exchange inherent asset balance, exchange paired asset balance");
@@ -531,7 +575,7 @@ impl<T: Trait> Module<T> {
         output_amount: T::Balance,
         max_input_amount: T::Balance,
         fee_rate: T::FeeRate,
-    ) -> sp_std::result::Result<T::Balance, &'static str> {
+    ) -> sp_std::result::Result<T::Balance, DispatchError> {
         let paired_asset_input_amount =
             Self::calc_paired_asset_input_amount(paired_asset_id, output_amount, fee_rate)?;
 
@@ -551,13 +595,13 @@ impl<T: Trait> Module<T> {
             paired_asset_id,
             exchange_address.clone(),
             paired_asset_input_amount,
-        );
+        )?;
         <assets::Module<T>>::transfer(
             exchange_address.clone(),
             inherent_asset_id.clone(),
             output_account,
             output_amount,
-        );
+        )?;
 
         // debug
         sp_runtime::print("----> exchange inherent asset balance, exchange paired asset balance");
@@ -620,7 +664,7 @@ impl<T: Trait> Module<T> {
         input_amount: T::Balance,
         min_output_amount: T::Balance,
         fee_rate: T::FeeRate,
-    ) -> sp_std::result::Result<T::Balance, &'static str> {
+    ) -> sp_std::result::Result<T::Balance, DispatchError> {
         let inherent_asset_id = <assets::Module<T>>::inherent_asset_id();
         let exchange_a_address = Self::get_exchange_address(inherent_asset_id.clone(), asset_a);
         let exchange_b_address = Self::get_exchange_address(inherent_asset_id.clone(), asset_b);
@@ -638,19 +682,19 @@ impl<T: Trait> Module<T> {
             asset_a.clone(),
             exchange_a_address.clone(),
             input_amount,
-        );
+        )?;
         <assets::Module<T>>::transfer(
             exchange_a_address.clone(),
             inherent_asset_id.clone(),
             exchange_b_address.clone(),
             inherent_asset_output_amount,
-        );
+        )?;
         <assets::Module<T>>::transfer(
             exchange_b_address.clone(),
             asset_b.clone(),
             output_account,
             asset_b_output_amount,
-        );
+        )?;
 
         // debug
         sp_runtime::print("----> exchange a inherent asset balance, exchange a paired asset balance");
@@ -728,7 +772,7 @@ impl<T: Trait> Module<T> {
         output_amount: T::Balance,
         max_input_amount: T::Balance,
         fee_rate: T::FeeRate,
-    ) -> sp_std::result::Result<T::Balance, &'static str> {
+    ) -> sp_std::result::Result<T::Balance, DispatchError> {
         let inherent_asset_id = <assets::Module<T>>::inherent_asset_id();
         let exchange_a_address = Self::get_exchange_address(inherent_asset_id.clone(), asset_a);
         let exchange_b_address = Self::get_exchange_address(inherent_asset_id.clone(), asset_b);
@@ -746,19 +790,19 @@ impl<T: Trait> Module<T> {
             asset_a.clone(),
             exchange_a_address.clone(),
             asset_a_input_amount,
-        );
+        )?;
         <assets::Module<T>>::transfer(
             exchange_a_address.clone(),
             inherent_asset_id.clone(),
             exchange_b_address.clone(),
             inherent_asset_input_amount,
-        );
+        )?;
         <assets::Module<T>>::transfer(
             exchange_b_address.clone(),
             asset_b.clone(),
             output_account,
             output_amount,
-        );
+        )?;
 
         // debug
         sp_runtime::print("----> exchange a inherent asset balance, exchange a paired asset balance");
@@ -1020,18 +1064,19 @@ impl<T: Trait> Module<T> {
 
     /// Add liquidity
     /// the value of liquidity is equal to the value of input inherent asset
-    /// @account    The account to inject liquidity to some paired pool
+    /// @origin     The account to inject liquidity to some paired pool
     /// @asset_id   The paired asset, used to represent which paired pool to act on
     /// @inherent_asset_amount    The amount of inherent asset part to be injected
     /// @asset_amount    The amount of paired asset part to be injected
     /// @min_liquidity   The limitation of minimum liquidity injected this time
     fn _add_liquidity(
-        account: T::AccountId,
+        origin: T::Origin,
         asset_id: T::AssetId,
         inherent_asset_amount: T::Balance,
         asset_amount: T::Balance,
         min_liquidity: T::Balance,
-    ) {
+    ) -> Result {
+        let account = ensure_signed(origin)?;
         let inherent_asset_id = <assets::Module<T>>::inherent_asset_id();
         let exchange_address = Self::get_exchange_address(inherent_asset_id, asset_id);
 
@@ -1046,13 +1091,13 @@ impl<T: Trait> Module<T> {
                 inherent_asset_id.clone(),
                 exchange_address.clone(),
                 inherent_asset_amount,
-            );
+            )?;
             <assets::Module<T>>::transfer(
                 account.clone(),
                 asset_id.clone(),
                 exchange_address.clone(),
                 asset_amount,
-            );
+            )?;
 
             Self::set_liquidity(asset_id.clone(), account.clone(), inherent_asset_amount);
             Self::increase_total_liquidity(asset_id.clone(), inherent_asset_amount);
@@ -1068,13 +1113,13 @@ impl<T: Trait> Module<T> {
                 inherent_asset_id.clone(),
                 exchange_address.clone(),
                 inherent_asset_amount,
-            );
+            )?;
             <assets::Module<T>>::transfer(
                 account.clone(),
                 asset_id.clone(),
                 exchange_address.clone(),
                 asset_amount,
-            );
+            )?;
 
             // TODO: type convertions
             let minted_liquidity = total_liquidity * inherent_asset_amount / inherent_asset_in_pool;
@@ -1131,21 +1176,25 @@ impl<T: Trait> Module<T> {
             inherent_asset_id,
             inherent_asset_balance_in_pool,
         ));
+        Self::deposit_event(RawEvent::LiquidityAdded(account, inherent_asset_amount, asset_id));
+
+        Ok(())
     }
 
     /// Remove liquidity
-    /// @account    The account to do removing liquidity from some paired pool
+    /// @origin     The account to do removing liquidity from some paired pool
     /// @asset_id   The paired asset, used to represent which paired pool to act on
     /// @liquidity  The amount of liquidity to be removed
     /// @min_inherent_asset_amount    The minimum amount of inherent asset to be removed, used to check
     /// @min_asset_amount   The minimum amount of paired asset to be removed, used to check
     fn _remove_liquidity(
-        account: T::AccountId,
+        origin: T::Origin,
         asset_id: T::AssetId,
         liquidity: T::Balance,
         min_inherent_asset_amount: T::Balance,
         min_asset_amount: T::Balance,
-    ) {
+    ) -> Result {
+        let account = ensure_signed(origin)?;
         let inherent_asset_id = <assets::Module<T>>::inherent_asset_id();
         let exchange_address = Self::get_exchange_address(inherent_asset_id.clone(), asset_id);
         let account_liquidity = Self::get_liquidity(asset_id, account.clone());
@@ -1165,13 +1214,13 @@ impl<T: Trait> Module<T> {
             inherent_asset_id.clone(),
             account.clone(),
             inherent_asset_amount,
-        );
+        )?;
         <assets::Module<T>>::transfer(
             exchange_address.clone(),
             asset_id,
             account.clone(),
             asset_amount,
-        );
+        )?;
 
         Self::set_liquidity(asset_id, account.clone(), account_liquidity - liquidity);
         Self::decrease_total_liquidity(asset_id, liquidity);
@@ -1217,6 +1266,9 @@ impl<T: Trait> Module<T> {
             inherent_asset_id,
             inherent_asset_balance_in_pool,
         ));
+        Self::deposit_event(RawEvent::LiquidityRemoved(account, liquidity, asset_id));
+
+        Ok(())
     }
 
     /// Set liquidity of an account in a pool
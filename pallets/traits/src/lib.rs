@@ -1,9 +1,62 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
+use sp_runtime::DispatchResult;
+use frame_support::weights::Weight;
+
 pub trait Oracle<AssetId, Rate> {
-	fn get_rate(asset_id: AssetId) -> Rate;
+	/// The rate of an asset and the unix time in seconds it was last updated at, none if it has no rate
+	fn get_rate(asset_id: AssetId) -> Option<(Rate, u64)>;
+
+	/// Set the rate of an asset, for benchmarks to value positions
+	#[cfg(feature = "runtime-benchmarks")]
+	fn set_rate(asset_id: AssetId, rate: Rate);
 }
 
 pub trait MultiAsset<AccountId, AssetId, Balance> {
-	fn transfer(from: AccountId, id: AssetId, to: AccountId, amount: Balance) -> sp_std::result::Result<(), &'static str>;
-}
\ No newline at end of file
+	fn transfer(from: AccountId, id: AssetId, to: AccountId, amount: Balance) -> DispatchResult;
+}
+
+/// Multiple assets that other pallets can create, mint and burn
+pub trait MintableMultiAsset<AccountId, AssetId, Balance> {
+	/// Create a new asset with no supply
+	fn create_asset() -> AssetId;
+
+	fn mint(id: AssetId, to: AccountId, amount: Balance) -> DispatchResult;
+
+	fn burn(id: AssetId, from: AccountId, amount: Balance) -> DispatchResult;
+
+	fn balance(id: AssetId, who: AccountId) -> Balance;
+}
+
+/// Hook on asset transfers, for assets standing for positions kept by another pallet
+pub trait OnAssetTransfer<AccountId, AssetId, Balance> {
+	/// If a transfer may happen, checked before any balance moves
+	fn can_transfer(from: &AccountId, id: AssetId, to: &AccountId, amount: Balance) -> DispatchResult;
+
+	/// A transfer happened, an error reverts it
+	fn on_transfer(from: &AccountId, id: AssetId, to: &AccountId, amount: Balance) -> DispatchResult;
+
+	/// If the holder may destroy its balance of an asset
+	fn can_destroy(who: &AccountId, id: AssetId) -> DispatchResult;
+
+	/// The most weight `can_transfer` and `on_transfer` of a single transfer may take together
+	fn transfer_weight() -> Weight;
+}
+
+impl<AccountId, AssetId, Balance> OnAssetTransfer<AccountId, AssetId, Balance> for () {
+	fn can_transfer(_: &AccountId, _: AssetId, _: &AccountId, _: Balance) -> DispatchResult {
+		Ok(())
+	}
+
+	fn on_transfer(_: &AccountId, _: AssetId, _: &AccountId, _: Balance) -> DispatchResult {
+		Ok(())
+	}
+
+	fn can_destroy(_: &AccountId, _: AssetId) -> DispatchResult {
+		Ok(())
+	}
+
+	fn transfer_weight() -> Weight {
+		0
+	}
+}
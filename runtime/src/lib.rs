@@ -97,10 +97,10 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	spec_name: create_runtime_str!("konomi-node"),
 	impl_name: create_runtime_str!("konomi-node"),
 	authoring_version: 1,
-	spec_version: 1,
+	spec_version: 2,
 	impl_version: 1,
 	apis: RUNTIME_API_VERSIONS,
-	transaction_version: 1,
+	transaction_version: 2,
 };
 
 pub const MILLISECS_PER_BLOCK: u64 = 6000;
@@ -269,6 +269,9 @@ impl pallet_assets::Trait for Runtime {
     type Event = Event;
     type Balance = Balance;
     type AssetId = AssetId;
+    type OnAssetTransfer = Lending;
+    type UnixTime = Timestamp;
+    type WeightInfo = ();
 }
 
 pub use pallet_swap;
@@ -277,6 +280,7 @@ impl pallet_swap::Trait for Runtime {
     type Event = Event;
     type ExchangeAddress = pallet_swap::ExchangeAddress<Self>;
     type FeeRate = u64;
+    type WeightInfo = ();
 }
 
 impl pallet_lending::Trait for Runtime {
@@ -285,8 +289,14 @@ impl pallet_lending::Trait for Runtime {
 	type Event = Event;
 	type Oracle = Assets;
 	type MultiAsset = Assets;
+	type UnixTime = Timestamp;
+	type Call = Call;
+	type WeightInfo = ();
 }
 
+impl pallet_lending_keeper::Trait for Runtime {
+	type AuthorityId = pallet_lending_keeper::crypto::KeeperAuthId;
+}
 
 parameter_types! {
 	pub const UnsignedInterval: BlockNumber = 4;
@@ -299,6 +309,7 @@ impl pallet_offchain_worker::Trait for Runtime {
 	type Call = Call;
 	type UnsignedInterval = UnsignedInterval;
 	type UnsignedPriority = UnsignedPriority;
+	type WeightInfo = ();
 }
 
 pub type SignedPayload = generic::SignedPayload<Call, SignedExtra>;
@@ -377,6 +388,7 @@ construct_runtime!(
 		Swap: pallet_swap::{Module, Call, Storage, Config<T>, Event<T>},
 		Lending: pallet_lending::{Module, Call, Storage, Config<T>, Event<T>},
 		OffchainWorker: pallet_offchain_worker::{Module, Call, Storage, Event<T>, ValidateUnsigned},
+		LendingKeeper: pallet_lending_keeper::{Module},
 	}
 );
 
@@ -404,6 +416,14 @@ pub type SignedExtra = (
 pub type UncheckedExtrinsic = generic::UncheckedExtrinsic<Address, Call, Signature, SignedExtra>;
 /// Extrinsic type that has already been checked.
 pub type CheckedExtrinsic = generic::CheckedExtrinsic<AccountId, Call, SignedExtra>;
+/// Storage migrations run on runtime upgrade, before those of the modules.
+pub struct CustomOnRuntimeUpgrade;
+impl frame_support::traits::OnRuntimeUpgrade for CustomOnRuntimeUpgrade {
+	fn on_runtime_upgrade() -> Weight {
+		pallet_lending::migrations::migrate::<Runtime>(MILLISECS_PER_BLOCK)
+	}
+}
+
 /// Executive: handles dispatch to the various modules.
 pub type Executive = frame_executive::Executive<
 	Runtime,
@@ -411,6 +431,7 @@ pub type Executive = frame_executive::Executive<
 	frame_system::ChainContext<Runtime>,
 	Runtime,
 	AllModules,
+	CustomOnRuntimeUpgrade,
 >;
 
 impl_runtime_apis! {
@@ -551,10 +572,18 @@ impl_runtime_apis! {
 		fn debt_rate(id: AssetId) -> FixedU128 {
             Lending::debt_rate(id)
 		}
+
+		fn stable_debt_rate(id: AssetId) -> Option<FixedU128> {
+			Lending::stable_debt_rate(id)
+		}
 		
-		fn get_user_info(user: AccountId) -> (u64, u64, u64) {
-			let (a, b, c) = Lending::get_user_info(user);
-			(a.saturated_into(), b.saturated_into(), c.saturated_into())
+		fn get_user_info(user: AccountId) -> pallet_lending_rpc_runtime_api::UserInfo<Balance> {
+			let (supply_value, borrow_limit, debt_value) = Lending::get_user_info(user);
+			pallet_lending_rpc_runtime_api::UserInfo { supply_value, borrow_limit, debt_value }
+		}
+
+		fn get_user_position(user: AccountId) -> pallet_lending_rpc_runtime_api::UserPosition<AssetId, Balance, FixedU128> {
+			Lending::get_user_position(user)
 		}
 
 		fn get_user_debt_with_interest(asset_id: AssetId, user: AccountId) -> Balance {
@@ -565,6 +594,18 @@ impl_runtime_apis! {
 			Lending::get_user_supply_with_interest(asset_id, user)
 		}
 
+		fn get_reserves(id: AssetId) -> Balance {
+			Lending::get_reserves(id)
+		}
+
+		fn supply_cap(id: AssetId) -> Option<Balance> {
+			Lending::supply_cap(id)
+		}
+
+		fn borrow_cap(id: AssetId) -> Option<Balance> {
+			Lending::borrow_cap(id)
+		}
+
     }
 
 	#[cfg(feature = "runtime-benchmarks")]
@@ -596,6 +637,10 @@ impl_runtime_apis! {
 			add_benchmark!(params, batches, frame_system, SystemBench::<Runtime>);
 			add_benchmark!(params, batches, pallet_balances, Balances);
 			add_benchmark!(params, batches, pallet_timestamp, Timestamp);
+			add_benchmark!(params, batches, pallet_assets, Assets);
+			add_benchmark!(params, batches, pallet_swap, Swap);
+			add_benchmark!(params, batches, pallet_lending, Lending);
+			add_benchmark!(params, batches, pallet_offchain_worker, OffchainWorker);
 
 			if batches.is_empty() { return Err("Benchmark not found for this pallet.".into()) }
 			Ok(batches)